@@ -0,0 +1,50 @@
+//! Times diffing a large table whose cells are built from several text siblings, which
+//! the DOM renderer and the diff merge into one text node per cell. The same table with
+//! one text child per cell is timed alongside as the baseline. Creating the DOM nodes
+//! needs a browser, so this measures what merging costs the diff on every update.
+//!
+//! Run with `cargo bench --bench text_nodes`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use rusty_react::{Element, Node, diff};
+
+const ROWS: usize = 500;
+const COLUMNS: usize = 10;
+const ITERATIONS: u32 = 20;
+
+/// A table of numbers; `split` writes each cell as a value and a unit, in two siblings.
+fn table(offset: usize, split: bool) -> Node {
+    let mut body = Element::new("tbody");
+    for row in 0..ROWS {
+        let mut tr = Element::new("tr");
+        for column in 0..COLUMNS {
+            let value = (row * COLUMNS + column + offset).to_string();
+            let td = Element::new("td");
+            tr = tr.child(if split {
+                td.child(value).child(" ms")
+            } else {
+                td.child(format!("{} ms", value))
+            });
+        }
+        body = body.child(tr);
+    }
+    Node::Element(Element::new("table").child(body))
+}
+
+fn time(name: &str, split: bool) {
+    let (old, new) = (table(0, split), table(1, split));
+    let mut total = Duration::ZERO;
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        black_box(diff(black_box(&old), black_box(&new)));
+        total += start.elapsed();
+    }
+    println!("{:<24} {:>10.2?} per diff", name, total / ITERATIONS);
+}
+
+fn main() {
+    time("one text per cell", false);
+    time("two text siblings", true);
+}
@@ -0,0 +1,271 @@
+//! A hook that runs a callback on every animation frame while its component is alive.
+
+use std::cell::{Cell, OnceCell, RefCell};
+use std::rc::{Rc, Weak};
+
+use wasm_bindgen::JsCast;
+use wasm_bindgen::closure::Closure;
+
+use crate::hooks::with_slot;
+
+/// Calls `callback` with the frame timestamp (in milliseconds, like
+/// `requestAnimationFrame`) once per frame, starting with the frame after the first
+/// render, for as long as the component is mounted. The loop is cancelled when the
+/// component's hook state is dropped, i.e. by the update that removes the component
+/// from its root's tree.
+///
+/// Each render replaces the callback, so it always sees the latest props. Outside the
+/// browser, e.g. when the string renderer renders the component on a server, there are
+/// no frames and the hook does nothing.
+pub fn use_animation_frame(callback: impl Fn(f64) + 'static) {
+    if !cfg!(target_arch = "wasm32") || web_sys::window().is_none() {
+        // Still takes a slot, so the hooks after this one keep their indices.
+        return with_slot(|| (), |_| ());
+    }
+    use_animation_frame_with(callback, |tick| Box::new(BrowserClock::new(tick)));
+}
+
+/// Advances an animation loop by one frame, given the frame timestamp.
+type Tick = Rc<dyn Fn(f64)>;
+
+/// Schedules ticks of an animation loop.
+trait FrameClock {
+    /// Asks for one tick on the next frame and returns an id for `cancel`.
+    fn request(&self) -> i32;
+    fn cancel(&self, id: i32);
+}
+
+/// Ticks on `requestAnimationFrame`. The closure is created once and handed to every
+/// request, so it is never dropped while the browser still holds it.
+struct BrowserClock {
+    tick: Closure<dyn FnMut(f64)>,
+}
+
+impl BrowserClock {
+    fn new(tick: Tick) -> Self {
+        BrowserClock {
+            tick: Closure::new(move |timestamp: f64| tick(timestamp)),
+        }
+    }
+}
+
+impl FrameClock for BrowserClock {
+    fn request(&self) -> i32 {
+        web_sys::window()
+            .expect("no global `window` exists")
+            .request_animation_frame(self.tick.as_ref().unchecked_ref())
+            .expect("Failed to request an animation frame")
+    }
+
+    fn cancel(&self, id: i32) {
+        if let Some(window) = web_sys::window() {
+            let _ = window.cancel_animation_frame(id);
+        }
+    }
+}
+
+/// The hook slot. Dropping it cancels the frame that is still pending.
+struct AnimationLoop {
+    state: Rc<LoopState>,
+}
+
+struct LoopState {
+    callback: RefCell<Rc<dyn Fn(f64)>>,
+    clock: OnceCell<Box<dyn FrameClock>>,
+    pending: Cell<Option<i32>>,
+}
+
+impl LoopState {
+    fn tick(&self, timestamp: f64) {
+        self.pending.set(None);
+        // Cloned out first so the callback may re-render the component, which swaps in
+        // a new callback.
+        let callback = self.callback.borrow().clone();
+        callback(timestamp);
+        if let Some(clock) = self.clock.get() {
+            self.pending.set(Some(clock.request()));
+        }
+    }
+}
+
+impl Drop for AnimationLoop {
+    fn drop(&mut self) {
+        if let (Some(clock), Some(id)) = (self.state.clock.get(), self.state.pending.take()) {
+            clock.cancel(id);
+        }
+    }
+}
+
+fn use_animation_frame_with(
+    callback: impl Fn(f64) + 'static,
+    clock: impl FnOnce(Tick) -> Box<dyn FrameClock>,
+) {
+    let callback: Rc<dyn Fn(f64)> = Rc::new(callback);
+    let initial = callback.clone();
+    with_slot(
+        move || {
+            let state = Rc::new(LoopState {
+                callback: RefCell::new(initial),
+                clock: OnceCell::new(),
+                pending: Cell::new(None),
+            });
+            // The clock only holds on to the loop weakly, so dropping the slot ends it.
+            let weak: Weak<LoopState> = Rc::downgrade(&state);
+            let clock = clock(Rc::new(move |timestamp| {
+                if let Some(state) = weak.upgrade() {
+                    state.tick(timestamp);
+                }
+            }));
+            state.pending.set(Some(clock.request()));
+            let _ = state.clock.set(clock);
+            AnimationLoop { state }
+        },
+        |animation| *animation.state.callback.borrow_mut() = callback,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commit::commit_in;
+    use crate::hooks::HookTree;
+    use crate::{Component, Element, Node, component, render_node_to_string};
+
+    /// Records requests and cancellations, and lets the test fire the pending frame.
+    #[derive(Clone, Default)]
+    struct FakeClock {
+        log: Rc<RefCell<Vec<String>>>,
+        tick: Rc<RefCell<Option<Tick>>>,
+    }
+
+    impl FrameClock for FakeClock {
+        fn request(&self) -> i32 {
+            let mut log = self.log.borrow_mut();
+            let id = log.len();
+            log.push(format!("request {}", id));
+            id as i32
+        }
+
+        fn cancel(&self, id: i32) {
+            self.log.borrow_mut().push(format!("cancel {}", id));
+        }
+    }
+
+    #[derive(Clone)]
+    struct Spinner {
+        clock: FakeClock,
+        angle: Rc<Cell<f64>>,
+    }
+
+    impl std::fmt::Debug for Spinner {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("Spinner")
+        }
+    }
+
+    impl Component for Spinner {
+        fn render(&self) -> Node {
+            let angle = self.angle.clone();
+            let clock = self.clock.clone();
+            use_animation_frame_with(
+                move |timestamp| angle.set(timestamp / 10.0),
+                move |tick| {
+                    *clock.tick.borrow_mut() = Some(tick);
+                    Box::new(clock)
+                },
+            );
+            Node::Text(format!("{}deg", self.angle.get()))
+        }
+
+        fn clone_rc(&self) -> Rc<dyn Component> {
+            Rc::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_animation_frame_runs_while_mounted_and_cancels_on_unmount() {
+        let clock = FakeClock::default();
+        let angle = Rc::new(Cell::new(0.0));
+        let spinner = component(Spinner {
+            clock: clock.clone(),
+            angle: angle.clone(),
+        });
+
+        spinner.render();
+        spinner.render();
+        assert_eq!(*clock.log.borrow(), ["request 0"]);
+
+        let tick = clock.tick.borrow().clone().unwrap();
+        tick(900.0);
+        assert_eq!(angle.get(), 90.0);
+        assert_eq!(*clock.log.borrow(), ["request 0", "request 1"]);
+
+        drop(spinner);
+        assert_eq!(*clock.log.borrow(), ["request 0", "request 1", "cancel 1"]);
+        tick(1000.0);
+        assert_eq!(angle.get(), 90.0);
+    }
+
+    #[derive(Debug, Clone)]
+    struct Loader {
+        spinner: Option<Spinner>,
+    }
+
+    impl Component for Loader {
+        fn render(&self) -> Node {
+            let mut section = Element::new("section");
+            if let Some(spinner) = &self.spinner {
+                section = section.child(component(spinner.clone()));
+            }
+            Node::Element(section)
+        }
+
+        fn clone_rc(&self) -> Rc<dyn Component> {
+            Rc::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_nested_animation_frame_lives_as_long_as_it_is_mounted() {
+        let clock = FakeClock::default();
+        let spinner = Spinner {
+            clock: clock.clone(),
+            angle: Rc::new(Cell::new(0.0)),
+        };
+        let loader = |spinning: bool| {
+            Node::Component(component(Loader {
+                spinner: spinning.then(|| spinner.clone()),
+            }))
+        };
+        let hooks = HookTree::default();
+
+        let mounted = commit_in(&loader(true), &hooks);
+        drop(mounted);
+        commit_in(&loader(true), &hooks);
+        assert_eq!(*clock.log.borrow(), ["request 0"]);
+
+        commit_in(&loader(false), &hooks);
+        assert_eq!(*clock.log.borrow(), ["request 0", "cancel 0"]);
+    }
+
+    #[derive(Debug, Clone)]
+    struct Clock;
+
+    impl Component for Clock {
+        fn render(&self) -> Node {
+            use_animation_frame(|_| {});
+            Node::Element(Element::new("time").child("12:00"))
+        }
+
+        fn clone_rc(&self) -> Rc<dyn Component> {
+            Rc::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_animation_frame_does_nothing_in_the_string_renderer() {
+        let html = render_node_to_string(&Node::Component(component(Clock)));
+
+        assert_eq!(html, "<time>12:00</time>");
+    }
+}
@@ -0,0 +1,136 @@
+//! Gating parts of the UI on whether someone is signed in. The signed-in user lives in
+//! a per-thread `Store`, like feature flags, so roots can subscribe and re-render when
+//! they sign in or out.
+
+use std::rc::Rc;
+
+use crate::store::{Store, SubscriptionId, create_store};
+use crate::{Component, Fragment, IntoNode, Node};
+
+thread_local! {
+    static CURRENT_USER: Store<Option<String>> = create_store(None);
+}
+
+/// Records who is signed in (`Some` with a user id or name) or that nobody is (`None`).
+/// Subscribers are only notified when this actually changes.
+pub fn set_current_user(user: Option<String>) {
+    CURRENT_USER.with(|current| {
+        if current.get() != user {
+            current.update(|current| *current = user);
+        }
+    });
+}
+
+/// The signed-in user, if any.
+pub fn current_user() -> Option<String> {
+    CURRENT_USER.with(|current| current.get())
+}
+
+/// Calls `callback` whenever someone signs in or out:
+/// `subscribe_auth(move || handle.schedule_update(app()))`.
+pub fn subscribe_auth(callback: impl Fn() + 'static) -> SubscriptionId {
+    CURRENT_USER.with(|current| current.subscribe(move |_| callback()))
+}
+
+pub fn unsubscribe_auth(id: SubscriptionId) {
+    CURRENT_USER.with(|current| current.unsubscribe(id));
+}
+
+/// Renders its children only while a user is signed in. The state is read when the
+/// component renders, so re-rendering the root from `subscribe_auth` is enough.
+#[derive(Debug, Clone)]
+pub struct Authenticated {
+    children: Node,
+}
+
+impl Authenticated {
+    pub fn new(children: impl IntoNode) -> Self {
+        Authenticated {
+            children: children.into_node(),
+        }
+    }
+}
+
+impl Component for Authenticated {
+    fn render(&self) -> Node {
+        match current_user() {
+            Some(_) => self.children.clone(),
+            None => Node::Fragment(Fragment::default()),
+        }
+    }
+
+    fn clone_rc(&self) -> Rc<dyn Component> {
+        Rc::new(self.clone())
+    }
+}
+
+/// Renders its children only while nobody is signed in, e.g. a "Sign in" link.
+#[derive(Debug, Clone)]
+pub struct Guest {
+    children: Node,
+}
+
+impl Guest {
+    pub fn new(children: impl IntoNode) -> Self {
+        Guest {
+            children: children.into_node(),
+        }
+    }
+}
+
+impl Component for Guest {
+    fn render(&self) -> Node {
+        match current_user() {
+            Some(_) => Node::Fragment(Fragment::default()),
+            None => self.children.clone(),
+        }
+    }
+
+    fn clone_rc(&self) -> Rc<dyn Component> {
+        Rc::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::{Element, component, render_node_to_string};
+
+    fn header() -> Node {
+        Node::Element(
+            Element::new("header")
+                .child(component(Authenticated::new(
+                    Element::new("a").attr("href", "/account").child("Account"),
+                )))
+                .child(component(Guest::new(
+                    Element::new("a").attr("href", "/login").child("Sign in"),
+                ))),
+        )
+    }
+
+    #[test]
+    fn test_authenticated_renders_only_for_signed_in_users() {
+        let changes = Rc::new(Cell::new(0));
+        let counter = changes.clone();
+        let id = subscribe_auth(move || counter.set(counter.get() + 1));
+
+        assert_eq!(
+            render_node_to_string(&header()),
+            "<header><a href=\"/login\">Sign in</a></header>"
+        );
+
+        set_current_user(Some("ada".to_string()));
+        set_current_user(Some("ada".to_string()));
+        assert_eq!(
+            render_node_to_string(&header()),
+            "<header><a href=\"/account\">Account</a></header>"
+        );
+        assert_eq!(changes.get(), 1);
+
+        unsubscribe_auth(id);
+        set_current_user(None);
+        assert_eq!(changes.get(), 1);
+    }
+}
@@ -0,0 +1,223 @@
+//! Incremental server rendering: reuse the markup of subtrees that did not change
+//! since the previous render.
+
+use std::collections::HashMap;
+
+use crate::commit::commit_on_server;
+use crate::escape::{is_raw_text_element, is_void_element};
+use crate::{Node, end_tag, render_node_to_string, write_start_tag};
+
+/// Markup of the elements rendered last time, keyed by `Node::fingerprint`. Only the
+/// entries used by the most recent render are kept, so the cache does not grow with
+/// every version of the page.
+#[derive(Debug, Default)]
+pub struct RenderCache {
+    entries: HashMap<u64, String>,
+    hits: usize,
+}
+
+impl RenderCache {
+    pub fn new() -> Self {
+        RenderCache::default()
+    }
+
+    /// How many subtrees the last `render_node_to_string_cached` call took from the cache.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+}
+
+/// Renders `node` like `render_node_to_string`, but copies the markup of every element
+/// whose fingerprint was already rendered last time instead of serializing it again.
+///
+/// Fingerprinting walks each subtree, so a page that changes everywhere costs more
+/// than a plain render; it pays off when large sections stay the same.
+///
+/// Markup is always written with `RenderOptions::default()`, as by
+/// `render_node_to_string`; pages that need other options can't use the cache. A hit
+/// is trusted on the fingerprint alone, without comparing the trees, so two different
+/// subtrees whose 64-bit fingerprints collide would share the markup of whichever was
+/// cached first. Don't share a cache across content an attacker could pick to
+/// collide.
+pub fn render_node_to_string_cached(node: &Node, cache: &mut RenderCache) -> String {
+    let previous = std::mem::take(&mut cache.entries);
+    let mut render = CachedRender {
+        previous,
+        used: HashMap::new(),
+        hits: 0,
+    };
+    let mut html = String::new();
+    // Committed first, so `use_id` gives the same ids as `render_node_to_string`.
+    render.write(&commit_on_server(node), &mut html);
+    cache.entries = render.used;
+    cache.hits = render.hits;
+    html
+}
+
+struct CachedRender {
+    previous: HashMap<u64, String>,
+    used: HashMap<u64, String>,
+    hits: usize,
+}
+
+impl CachedRender {
+    fn write(&mut self, node: &Node, out: &mut String) {
+        match node {
+            Node::Element(element) => {
+                let fingerprint = node.fingerprint();
+                if let Some(markup) = self.used.get(&fingerprint) {
+                    self.hits += 1;
+                    out.push_str(markup);
+                    return;
+                }
+                if let Some(markup) = self.previous.remove(&fingerprint) {
+                    self.hits += 1;
+                    out.push_str(&markup);
+                    self.used.insert(fingerprint, markup);
+                    return;
+                }
+
+                // Script and style text is written without escaping, and names in SVG
+                // and MathML keep their case, so those go through the string renderer.
+                let tag_name = element.tag_name.as_str();
+                if is_raw_text_element(tag_name)
+                    || tag_name.eq_ignore_ascii_case("svg")
+                    || tag_name.eq_ignore_ascii_case("math")
+                {
+                    let markup = render_node_to_string(node);
+                    out.push_str(&markup);
+                    self.used.insert(fingerprint, markup);
+                    return;
+                }
+
+                let mut markup = Vec::new();
+                write_start_tag(element, &mut markup).expect("Writing to a Vec never fails");
+                let mut markup = String::from_utf8(markup).expect("Markup is valid UTF-8");
+                if !is_void_element(tag_name) {
+                    for child in &element.children {
+                        self.write(child, &mut markup);
+                    }
+                    markup.push_str(&end_tag(element));
+                }
+                out.push_str(&markup);
+                self.used.insert(fingerprint, markup);
+            }
+            Node::Component(v_component) => self.write(&v_component.render(), out),
+            Node::Lazy(lazy) => self.write(&lazy.render(), out),
+            Node::ServerOnly(inner) => self.write(inner, out),
+            Node::Fragment(fragment) => {
+                for child in &fragment.children {
+                    self.write(child, out);
+                }
+            }
+            Node::Text(_) | Node::RawHtml(_) | Node::Async(_) | Node::ClientOnly(_) => {
+                out.push_str(&render_node_to_string(node))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        CasePolicy, Component, Element, RenderOptions, component, render_node_to_string_with,
+        use_id,
+    };
+    use std::rc::Rc;
+
+    fn page(count: u32) -> Node {
+        Node::Element(
+            Element::new("body")
+                .child(Element::new("header").child(Element::new("h1").child("Shop")))
+                .child(Element::new("main").child(format!("{} items in cart", count)))
+                .child(Element::new("footer").child("Contact us")),
+        )
+    }
+
+    #[test]
+    fn test_unchanged_sections_come_from_the_cache() {
+        let mut cache = RenderCache::new();
+        assert_eq!(
+            render_node_to_string_cached(&page(1), &mut cache),
+            render_node_to_string(&page(1))
+        );
+        assert_eq!(cache.hits(), 0);
+
+        let html = render_node_to_string_cached(&page(2), &mut cache);
+
+        assert_eq!(html, render_node_to_string(&page(2)));
+        // header and footer are reused; body and main changed.
+        assert_eq!(cache.hits(), 2);
+    }
+
+    #[test]
+    fn test_hits_are_trusted_on_the_fingerprint_alone() {
+        let mut cache = RenderCache::new();
+        render_node_to_string_cached(&page(1), &mut cache);
+        let Node::Element(body) = page(1) else {
+            unreachable!("page renders a body element");
+        };
+        let header = body.children[0].fingerprint();
+        // What a colliding subtree with different markup would have left in the cache.
+        cache
+            .entries
+            .insert(header, "<header>Stale</header>".to_string());
+
+        let html = render_node_to_string_cached(&page(2), &mut cache);
+
+        assert!(html.starts_with("<body><header>Stale</header>"));
+    }
+
+    #[test]
+    fn test_cached_markup_ignores_render_options() {
+        let list = Node::Element(
+            Element::new("UL")
+                .paginate()
+                .child(Element::new("li").child("a"))
+                .child(Element::new("li").child("b")),
+        );
+        let options = RenderOptions {
+            case: CasePolicy::LowercaseHtml,
+            max_children: Some(1),
+            ..Default::default()
+        };
+        let mut cache = RenderCache::new();
+
+        let html = render_node_to_string_cached(&list, &mut cache);
+
+        assert_eq!(html, render_node_to_string(&list));
+        assert_ne!(html, render_node_to_string_with(&list, &options));
+    }
+
+    #[derive(Debug, Clone)]
+    struct Field;
+
+    impl Component for Field {
+        fn render(&self) -> Node {
+            Node::Element(Element::new("input").attr("id", use_id()))
+        }
+
+        fn clone_rc(&self) -> Rc<dyn Component> {
+            Rc::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_cached_markup_matches_plain_markup_for_scripts_and_ids() {
+        let page = Node::Element(
+            Element::new("body")
+                .child(Element::new("script").child("if (a < b && c) {}"))
+                .child(component(Field))
+                .child(component(Field)),
+        );
+        let mut cache = RenderCache::new();
+
+        let html = render_node_to_string_cached(&page, &mut cache);
+
+        assert_eq!(html, render_node_to_string(&page));
+        assert!(html.contains("<script>if (a < b && c) {}</script>"));
+        assert!(html.contains("id=\"rr-id-1\"") && html.contains("id=\"rr-id-2\""));
+        assert_eq!(render_node_to_string_cached(&page, &mut cache), html);
+    }
+}
@@ -0,0 +1,124 @@
+//! A proof-of-concept `Renderer` that draws onto a 2D canvas instead of building DOM.
+//!
+//! Only a handful of tags are understood:
+//! - `rect`: a filled rectangle from `x`, `y`, `width`, `height` and `fill`.
+//! - `text`: its text children drawn at `x`, `y` with `fill` and an optional `font`.
+//! - anything else is treated as a group whose children are drawn in order.
+
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+
+use crate::renderer::Renderer;
+use crate::{Element, Node};
+
+/// Draws VDOM trees onto a `CanvasRenderingContext2d`.
+#[derive(Debug, Clone)]
+pub struct CanvasRenderer {
+    context: CanvasRenderingContext2d,
+}
+
+impl CanvasRenderer {
+    pub fn new(context: CanvasRenderingContext2d) -> Self {
+        CanvasRenderer { context }
+    }
+
+    /// Uses the 2D context of `canvas`.
+    pub fn from_canvas(canvas: &HtmlCanvasElement) -> Self {
+        let context = canvas
+            .get_context("2d")
+            .expect("Failed to get canvas context")
+            .expect("Canvas has no 2d context")
+            .dyn_into::<CanvasRenderingContext2d>()
+            .expect("Context is not a CanvasRenderingContext2d");
+        CanvasRenderer::new(context)
+    }
+
+    pub fn context(&self) -> &CanvasRenderingContext2d {
+        &self.context
+    }
+
+    fn draw_element(&self, element: &Element) {
+        match element.tag_name.as_str() {
+            "rect" => {
+                self.set_fill(element);
+                self.context.fill_rect(
+                    number_prop(element, "x"),
+                    number_prop(element, "y"),
+                    number_prop(element, "width"),
+                    number_prop(element, "height"),
+                );
+            }
+            "text" => {
+                self.set_fill(element);
+                if let Some(font) = element.props.get("font") {
+                    self.context.set_font(font);
+                }
+                let mut content = String::new();
+                collect_text(&element.children, &mut content);
+                self.context
+                    .fill_text(
+                        &content,
+                        number_prop(element, "x"),
+                        number_prop(element, "y"),
+                    )
+                    .expect("Failed to draw text");
+            }
+            _ => {
+                for child in &element.children {
+                    self.draw(child);
+                }
+            }
+        }
+    }
+
+    fn set_fill(&self, element: &Element) {
+        if let Some(fill) = element.props.get("fill") {
+            self.context.set_fill_style_str(fill);
+        }
+    }
+}
+
+impl Renderer for CanvasRenderer {
+    fn render(&mut self, node: &Node) {
+        self.draw(node);
+    }
+}
+
+impl CanvasRenderer {
+    fn draw(&self, node: &Node) {
+        match node {
+            Node::Element(element) => self.draw_element(element),
+            Node::Component(component) => self.draw(&component.render()),
+            Node::Lazy(lazy) => self.draw(&lazy.render()),
+            Node::Async(v_async) => self.draw(&v_async.fallback()),
+            Node::ClientOnly(inner) | Node::ServerOnly(inner) => self.draw(inner),
+            Node::Fragment(fragment) => {
+                for child in &fragment.children {
+                    self.draw(child);
+                }
+            }
+            // Loose text and raw HTML have no position on a canvas.
+            Node::Text(_) | Node::RawHtml(_) => {}
+        }
+    }
+}
+
+/// Reads a numeric prop, treating missing or malformed values as 0.
+fn number_prop(element: &Element, name: &str) -> f64 {
+    element
+        .props
+        .get(name)
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(0.0)
+}
+
+fn collect_text(children: &[Node], out: &mut String) {
+    for child in children {
+        match child {
+            Node::Text(text) => out.push_str(text),
+            Node::Element(element) => collect_text(&element.children, out),
+            Node::Fragment(fragment) => collect_text(&fragment.children, out),
+            _ => {}
+        }
+    }
+}
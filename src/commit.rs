@@ -0,0 +1,172 @@
+//! Rendering a tree's components once, before it is put on screen.
+//!
+//! A component renders from whatever state is current when `render` is called, so
+//! rendering the previous tree again during an update would show the new state on
+//! both sides of the diff. Committing captures each component's output at the time
+//! the tree is rendered, and every later pass over the committed tree (the diff, the
+//! DOM renderer, warnings, fingerprints) reuses it.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::hooks::{HookEntries, HookTree, Segment};
+use crate::profile::record_render;
+use crate::{Element, Fragment, Node, VComponent};
+
+/// Returns `node` with every component rendered and its output, committed in turn,
+/// attached to it; lazy nodes are replaced by what they build. Components that already
+/// carry an output are kept as they are. Async components are left alone, since their
+/// content arrives later.
+///
+/// Components take their hook state from `hooks` by position, so a component its
+/// parent builds anew on every render keeps its state. Positions that no longer hold a
+/// component of the same type are dropped from `hooks`.
+pub(crate) fn commit_in(node: &Node, hooks: &HookTree) -> Node {
+    Commit::new(hooks, false).finish(node)
+}
+
+/// `commit` for the string renderer, which leaves `Node::ClientOnly` content alone
+/// since it never renders it.
+pub(crate) fn commit_on_server(node: &Node) -> Node {
+    commit_on_server_at(node, &[])
+}
+
+/// `commit_on_server` for content that sits at `position` in a larger tree, such as
+/// what an async component resolves to.
+pub(crate) fn commit_on_server_at(node: &Node, position: &[Segment]) -> Node {
+    let hooks = HookTree::default();
+    let commit = Commit {
+        position: position.to_vec(),
+        ..Commit::new(&hooks, true)
+    };
+    commit.finish(node)
+}
+
+struct Commit<'a> {
+    hooks: &'a HookTree,
+    /// The hook state of every component rendered so far, by position.
+    visited: HookEntries,
+    position: Vec<Segment>,
+    /// Whether `Node::ClientOnly` content is left uncommitted.
+    server: bool,
+}
+
+impl<'a> Commit<'a> {
+    fn new(hooks: &'a HookTree, server: bool) -> Self {
+        Commit {
+            hooks,
+            visited: HashMap::new(),
+            position: Vec::new(),
+            server,
+        }
+    }
+
+    fn finish(mut self, node: &Node) -> Node {
+        let node = self.node(node);
+        self.hooks.replace(self.visited);
+        node
+    }
+}
+
+impl Commit<'_> {
+    fn node(&mut self, node: &Node) -> Node {
+        match node {
+            Node::Element(element) => Node::Element(Element {
+                children: self.children(&element.children),
+                ..element.clone()
+            }),
+            Node::Fragment(fragment) => Node::Fragment(Fragment {
+                children: self.children(&fragment.children),
+                key: fragment.key.clone(),
+            }),
+            Node::Component(v_component) if v_component.output.is_some() => node.clone(),
+            Node::Component(v_component) => {
+                let props = (*v_component.props).type_id();
+                let hooks = self
+                    .hooks
+                    .find(&self.position, props)
+                    .unwrap_or_else(|| v_component.hooks.clone());
+                self.visited
+                    .insert(self.position.clone(), (props, hooks.clone()));
+
+                // Only these renders count: the later passes reuse their output.
+                record_render(v_component.type_name);
+                let rendered = v_component.render_with(&hooks, &self.position);
+                self.position.push(Segment::Index(0));
+                let output = self.node(&rendered);
+                self.position.pop();
+                Node::Component(VComponent {
+                    hooks,
+                    output: Some(Rc::new(output)),
+                    ..v_component.clone()
+                })
+            }
+            Node::Lazy(lazy) => self.node(&lazy.render()),
+            Node::ClientOnly(_) if self.server => node.clone(),
+            Node::ClientOnly(inner) => Node::ClientOnly(Box::new(self.node(inner))),
+            Node::ServerOnly(inner) => Node::ServerOnly(Box::new(self.node(inner))),
+            Node::Text(_) | Node::RawHtml(_) | Node::Async(_) => node.clone(),
+        }
+    }
+
+    fn children(&mut self, children: &[Node]) -> Vec<Node> {
+        children
+            .iter()
+            .enumerate()
+            .map(|(index, child)| {
+                self.position.push(match child.key() {
+                    Some(key) => Segment::Key(key.to_string()),
+                    None => Segment::Index(index),
+                });
+                let child = self.node(child);
+                self.position.pop();
+                child
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Component, component, diff, render_node_to_string};
+    use std::cell::Cell;
+
+    thread_local! {
+        static BETA: Cell<bool> = const { Cell::new(false) };
+    }
+
+    #[derive(Debug, Clone)]
+    struct Banner;
+
+    impl Component for Banner {
+        fn render(&self) -> Node {
+            let text = if BETA.with(Cell::get) {
+                "Beta!"
+            } else {
+                "Stable"
+            };
+            Node::Element(Element::new("p").child(text))
+        }
+
+        fn clone_rc(&self) -> Rc<dyn Component> {
+            Rc::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_committed_tree_keeps_the_output_it_was_rendered_with() {
+        let page = || Node::Element(Element::new("main").child(component(Banner)));
+        let hooks = HookTree::default();
+        let before = commit_in(&page(), &hooks);
+
+        BETA.with(|beta| beta.set(true));
+        let after = commit_in(&page(), &hooks);
+
+        assert_eq!(render_node_to_string(&before), "<main><p>Stable</p></main>");
+        assert_eq!(render_node_to_string(&after), "<main><p>Beta!</p></main>");
+        assert_eq!(diff(&before, &after).len(), 1);
+        // Without committing, both sides render with the current state.
+        assert!(diff(&page(), &page()).is_empty());
+    }
+}
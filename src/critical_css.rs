@@ -0,0 +1,189 @@
+//! Moving inline styles into one `<style>` block for server-rendered pages.
+
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::transform::transform_node;
+use crate::{Element, Node, RawHtml, merge_class, render_node_to_string};
+
+/// Renders `node` with every `style` attribute (such as the ones `Element::style_struct`
+/// sets) replaced by a generated class, and the rules for those classes collected into
+/// a single inline `<style>` block. Identical styles share one class and one rule, so
+/// a list of styled rows costs one rule rather than one attribute per row.
+///
+/// The block is appended to the tree's `<head>` when it has one and put in front of the
+/// output otherwise. A class loses to other stylesheet rules that an inline style
+/// would have overridden, so this suits styles that don't rely on that precedence.
+///
+/// Only plain declarations are moved. A style with braces, comments, `<` or an
+/// unclosed quote could end its rule and style the rest of the page, so it stays an
+/// inline attribute, as does a style whose class happens to collide with another's.
+pub fn render_node_to_string_with_critical_css(node: &Node) -> String {
+    // (class, declarations) in document order, each style once.
+    let rules: RefCell<Vec<(String, String)>> = RefCell::new(Vec::new());
+    let extracted = transform_node(node, &|node| {
+        let Node::Element(element) = node else {
+            return None;
+        };
+        let css = element.props.get("style")?;
+        let class = class_for(css, &mut rules.borrow_mut())?;
+        let mut element = element.clone();
+        element.props.remove("style");
+        let own = element.props.get("class").map_or("", String::as_str);
+        let merged = merge_class(Some(&class), own);
+        Some(Node::Element(element.attr("class", merged)))
+    });
+
+    let rules = rules.into_inner();
+    if rules.is_empty() {
+        return render_node_to_string(&extracted);
+    }
+    let css: String = rules
+        .iter()
+        .map(|(class, declarations)| format!(".{} {{ {} }}", class, declarations))
+        .collect::<Vec<_>>()
+        .join(" ");
+    // `class_for` only lets through declarations without a `<`, so nothing in them can
+    // end the style element early.
+    let css = RawHtml::new(css);
+    let style = Element::new("style").child(Node::RawHtml(css));
+
+    let has_head = Cell::new(false);
+    let with_style = transform_node(&extracted, &|node| match node {
+        Node::Element(element)
+            if element.tag_name.eq_ignore_ascii_case("head") && !has_head.get() =>
+        {
+            has_head.set(true);
+            Some(Node::Element(element.clone().child(style.clone())))
+        }
+        _ => None,
+    });
+    if has_head.get() {
+        render_node_to_string(&with_style)
+    } else {
+        render_node_to_string(&Node::Element(style)) + &render_node_to_string(&extracted)
+    }
+}
+
+/// The class that replaces the style `css`, with its rule added to `rules` unless an
+/// earlier identical style added it. `None` keeps the style inline.
+fn class_for(css: &str, rules: &mut Vec<(String, String)>) -> Option<String> {
+    if !is_plain_declarations(css) {
+        return None;
+    }
+    let class = style_class(css);
+    match rules.iter().find(|(existing, _)| *existing == class) {
+        Some((_, declarations)) if declarations != css => None,
+        Some(_) => Some(class),
+        None => {
+            rules.push((class.clone(), css.to_string()));
+            Some(class)
+        }
+    }
+}
+
+/// Whether `css` is only declarations, which can't reach outside the rule they are put in.
+fn is_plain_declarations(css: &str) -> bool {
+    let breaks_out = css.contains(['{', '}', '<', '\\']) || css.contains("/*");
+    let balanced = |quote: char| css.matches(quote).count().is_multiple_of(2);
+    !breaks_out && balanced('"') && balanced('\'')
+}
+
+/// A class name derived from the declarations, so equal styles get the same class on
+/// every render.
+fn style_class(css: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    css.hash(&mut hasher);
+    format!("rr-css-{:016x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Component, Style, component};
+    use std::rc::Rc;
+
+    #[derive(Debug, Clone)]
+    struct Badge(&'static str);
+
+    impl Component for Badge {
+        fn render(&self) -> Node {
+            let style = Style {
+                color: Some("white".to_string()),
+                background: Some("teal".to_string()),
+                ..Default::default()
+            };
+            Node::Element(
+                Element::new("span")
+                    .attr("class", "badge")
+                    .style_struct(style)
+                    .child(self.0),
+            )
+        }
+
+        fn clone_rc(&self) -> Rc<dyn Component> {
+            Rc::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_critical_css_collects_deduplicated_rules_into_head() {
+        let page = Node::Element(
+            Element::new("html")
+                .child(Element::new("head").child(Element::new("title").child("Team")))
+                .child(
+                    Element::new("body")
+                        .child(component(Badge("Ada")))
+                        .child(component(Badge("Grace")))
+                        .child(Element::new("p").style_struct(Style::new().custom("margin", "0"))),
+                ),
+        );
+
+        let html = render_node_to_string_with_critical_css(&page);
+        let badge = style_class("color: white; background: teal");
+        let paragraph = style_class("margin: 0");
+        assert_eq!(
+            html,
+            format!(
+                "<html><head><title>Team</title><style>.{badge} {{ color: white; background: teal }} \
+                 .{paragraph} {{ margin: 0 }}</style></head><body>\
+                 <span class=\"badge {badge}\">Ada</span><span class=\"badge {badge}\">Grace</span>\
+                 <p class=\"{paragraph}\"></p></body></html>"
+            )
+        );
+        assert_eq!(html.matches("<style>").count(), 1);
+        assert!(!html.contains("style=\""));
+    }
+
+    #[test]
+    fn test_styles_that_could_escape_their_rule_stay_inline() {
+        let page = Node::Element(
+            Element::new("div")
+                .attr("style", "color:red} body{display:none")
+                .child(Element::new("p").attr("style", "font-family: \"Fira"))
+                .child(Element::new("p").attr("style", "color: red /* </style> */")),
+        );
+
+        let html = render_node_to_string_with_critical_css(&page);
+        assert!(!html.contains("<style>"));
+        assert!(html.starts_with("<div style=\"color:red} body{display:none\">"));
+    }
+
+    #[test]
+    fn test_colliding_class_keeps_the_second_style_inline() {
+        let mut rules = vec![(style_class("margin: 0"), "padding: 0".to_string())];
+
+        assert_eq!(class_for("margin: 0", &mut rules), None);
+        assert_eq!(rules.len(), 1);
+        assert_eq!(
+            class_for("padding: 0", &mut rules),
+            Some(style_class("padding: 0"))
+        );
+        assert_eq!(
+            class_for("padding: 0", &mut rules),
+            Some(style_class("padding: 0"))
+        );
+        assert_eq!(rules.len(), 2);
+    }
+}
@@ -0,0 +1,254 @@
+//! Accessible modal dialogs: rendered into their own container on `<body>`, with Tab
+//! focus trapped inside, Escape to close and focus handed back on close.
+
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+
+use wasm_bindgen::JsCast;
+use wasm_bindgen::closure::Closure;
+use web_sys::{Element as DomElement, HtmlElement, KeyboardEvent};
+
+use crate::scheduler::queue_microtask;
+use crate::{Element, IntoNode, Node, render_node_to_dom};
+
+/// Elements that take part in the Tab order.
+const FOCUSABLE: &str = "a[href], button:not([disabled]), input:not([disabled]), \
+                         select:not([disabled]), textarea:not([disabled]), \
+                         [tabindex]:not([tabindex=\"-1\"])";
+
+/// A modal dialog. `open` renders it; the returned handle closes it again.
+#[derive(Clone)]
+pub struct Dialog {
+    content: Node,
+    label: Option<String>,
+    on_close: Option<Rc<dyn Fn()>>,
+}
+
+impl std::fmt::Debug for Dialog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Dialog")
+            .field("content", &self.content)
+            .field("label", &self.label)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Dialog {
+    pub fn new(content: impl IntoNode) -> Self {
+        Dialog {
+            content: content.into_node(),
+            label: None,
+            on_close: None,
+        }
+    }
+
+    /// The accessible name screen readers announce for the dialog.
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Called once the dialog has closed, whether by Escape or `DialogHandle::close`.
+    pub fn on_close(mut self, callback: impl Fn() + 'static) -> Self {
+        self.on_close = Some(Rc::new(callback));
+        self
+    }
+
+    /// Renders the dialog and its backdrop at the end of `<body>` and moves focus to
+    /// its first focusable element (or the dialog itself, if it has none). The dialog
+    /// stays open, and Escape keeps closing it, even if the handle is dropped.
+    #[must_use = "the handle is the only way to close the dialog from code"]
+    pub fn open(&self) -> DialogHandle {
+        let document = web_sys::window()
+            .and_then(|window| window.document())
+            .expect("should have a document on window");
+        let body = document.body().expect("document should have a body");
+        let previous_focus = document
+            .active_element()
+            .and_then(|element| element.dyn_into::<HtmlElement>().ok());
+
+        let container = document
+            .create_element("div")
+            .expect("Failed to create element");
+        container
+            .set_attribute("class", "dialog-backdrop")
+            .expect("Failed to set attribute");
+        let mut dialog = Element::new("div")
+            .attr("class", "dialog")
+            .attr("role", "dialog")
+            .attr("aria-modal", "true")
+            .attr("tabindex", "-1")
+            .child(self.content.clone());
+        if let Some(label) = &self.label {
+            dialog = dialog.attr("aria-label", label.as_str());
+        }
+        render_node_to_dom(&Node::Element(dialog), &document, &container);
+        body.append_child(&container)
+            .expect("Failed to append dialog");
+        let dialog: HtmlElement = container
+            .first_element_child()
+            .expect("Dialog was just rendered")
+            .unchecked_into();
+
+        let state = Rc::new_cyclic(|weak: &Weak<DialogState>| {
+            let weak = weak.clone();
+            let keydown = Closure::<dyn FnMut(KeyboardEvent)>::new(move |event: KeyboardEvent| {
+                if let Some(state) = weak.upgrade() {
+                    DialogHandle { state }.handle_key(&event);
+                }
+            });
+            dialog
+                .add_event_listener_with_callback("keydown", keydown.as_ref().unchecked_ref())
+                .expect("Failed to add keydown listener");
+            DialogState {
+                container,
+                dialog: dialog.clone(),
+                previous_focus,
+                on_close: self.on_close.clone(),
+                open: Cell::new(true),
+                keydown,
+                open_dialog: RefCell::new(None),
+            }
+        });
+        *state.open_dialog.borrow_mut() = Some(state.clone());
+
+        let handle = DialogHandle { state };
+        match handle.focusables().first() {
+            Some(first) => first.focus().expect("Failed to focus"),
+            None => dialog.focus().expect("Failed to focus"),
+        }
+        handle
+    }
+}
+
+struct DialogState {
+    container: DomElement,
+    dialog: HtmlElement,
+    previous_focus: Option<HtmlElement>,
+    on_close: Option<Rc<dyn Fn()>>,
+    open: Cell<bool>,
+    keydown: Closure<dyn FnMut(KeyboardEvent)>,
+    /// Keeps the state, and with it the keydown listener, alive until the dialog is
+    /// closed, however many handles are left.
+    open_dialog: RefCell<Option<Rc<DialogState>>>,
+}
+
+impl Drop for DialogState {
+    fn drop(&mut self) {
+        let _ = self.dialog.remove_event_listener_with_callback(
+            "keydown",
+            self.keydown.as_ref().unchecked_ref(),
+        );
+    }
+}
+
+/// An open dialog. Clones refer to the same dialog.
+#[derive(Clone)]
+pub struct DialogHandle {
+    state: Rc<DialogState>,
+}
+
+impl DialogHandle {
+    /// The `role="dialog"` element.
+    pub fn element(&self) -> &HtmlElement {
+        &self.state.dialog
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.state.open.get()
+    }
+
+    /// Removes the dialog, gives focus back to the element that had it before the
+    /// dialog opened and calls `on_close`. Closing twice does nothing.
+    pub fn close(&self) {
+        if !self.state.open.replace(false) {
+            return;
+        }
+        self.state.container.remove();
+        if let Some(previous) = &self.state.previous_focus {
+            // The element may be gone by now, in which case focus stays on the body.
+            let _ = previous.focus();
+        }
+        if let Some(on_close) = &self.state.on_close {
+            on_close();
+        }
+        // Released later, since Escape closes the dialog from inside the listener.
+        if let Some(state) = self.state.open_dialog.take() {
+            queue_microtask(move || drop(state));
+        }
+    }
+
+    fn focusables(&self) -> Vec<HtmlElement> {
+        let list = self
+            .state
+            .dialog
+            .query_selector_all(FOCUSABLE)
+            .expect("Focusable selector is valid");
+        (0..list.length())
+            .filter_map(|i| list.item(i))
+            .filter_map(|node| node.dyn_into::<HtmlElement>().ok())
+            .collect()
+    }
+
+    fn handle_key(&self, event: &KeyboardEvent) {
+        match event.key().as_str() {
+            "Escape" => {
+                event.prevent_default();
+                self.close();
+            }
+            "Tab" => {
+                let focusables = self.focusables();
+                let active = self
+                    .state
+                    .dialog
+                    .owner_document()
+                    .and_then(|document| document.active_element());
+                let current = focusables.iter().position(|element| {
+                    active
+                        .as_ref()
+                        .is_some_and(|active| active == element.unchecked_ref::<DomElement>())
+                });
+                event.prevent_default();
+                match next_focus_index(focusables.len(), current, event.shift_key()) {
+                    Some(next) => focusables[next].focus().expect("Failed to focus"),
+                    None => self.state.dialog.focus().expect("Failed to focus"),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Where Tab (or Shift+Tab, when `backwards`) moves focus among `count` focusable
+/// elements when the one at `current` has it: to the next one, wrapping around at
+/// either end. With focus outside the list it enters at the first (or last) element.
+pub(crate) fn next_focus_index(
+    count: usize,
+    current: Option<usize>,
+    backwards: bool,
+) -> Option<usize> {
+    if count == 0 {
+        return None;
+    }
+    Some(match (current, backwards) {
+        (None, false) => 0,
+        (None, true) => count - 1,
+        (Some(i), false) => (i + 1) % count,
+        (Some(i), true) => (i + count - 1) % count,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_focus_wraps_around_both_ends() {
+        assert_eq!(next_focus_index(3, Some(0), false), Some(1));
+        assert_eq!(next_focus_index(3, Some(2), false), Some(0));
+        assert_eq!(next_focus_index(3, Some(0), true), Some(2));
+        assert_eq!(next_focus_index(3, None, false), Some(0));
+        assert_eq!(next_focus_index(3, None, true), Some(2));
+        assert_eq!(next_focus_index(0, None, false), None);
+    }
+}
@@ -0,0 +1,682 @@
+//! The reconciler: compares two VDOM trees and describes the DOM mutations that turn
+//! the first into the second.
+
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::{Element, Fragment, Listener, Node, coalesce_text, render_node_to_string};
+
+/// A single DOM mutation. A `path` is the list of child indices leading from the root
+/// to the node the patch applies to; the root itself is the empty path.
+///
+/// Patches are meant to be applied in order: the indices in each one refer to the
+/// DOM as it looks after every earlier patch has been applied.
+#[derive(Debug, Clone)]
+pub enum Patch {
+    /// Throw away the node at `path` and render `node` in its place.
+    Replace {
+        path: Vec<usize>,
+        node: Node,
+    },
+    /// Change the content of the text node at `path` in place. The text node and the
+    /// element around it are kept, which makes counters and labels cheap to update.
+    ReplaceText {
+        path: Vec<usize>,
+        text: String,
+    },
+    SetAttr {
+        path: Vec<usize>,
+        name: String,
+        value: String,
+    },
+    RemoveAttr {
+        path: Vec<usize>,
+        name: String,
+    },
+    /// Render `node` and insert it so it becomes child `index` of the element at `path`.
+    InsertChild {
+        path: Vec<usize>,
+        index: usize,
+        node: Node,
+    },
+    RemoveChild {
+        path: Vec<usize>,
+        index: usize,
+    },
+    /// Take the `count` children starting at `from` out of the element at `path` and
+    /// re-insert them, in order, so the first one ends up at `to`. The existing DOM
+    /// nodes, and everything attached to them, are kept. `count` is above one when a
+    /// keyed fragment moves as a unit.
+    MoveChild {
+        path: Vec<usize>,
+        from: usize,
+        count: usize,
+        to: usize,
+    },
+    /// Add and remove single classes of the element at `path` through `classList`,
+    /// leaving any other classes on the DOM element alone. Only emitted when both the
+    /// old and the new element set their classes with `Element::class_list`.
+    UpdateClasses {
+        path: Vec<usize>,
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// Unbind the listeners of the element at `path` and bind `listeners` instead.
+    /// Emitted unless the element keeps the very same handlers (as `Rc`s) for the
+    /// same events. Closures built during a render are new every time, so in practice
+    /// most elements with listeners are rebound.
+    SetListeners {
+        path: Vec<usize>,
+        listeners: Vec<Listener>,
+    },
+}
+
+/// Computes the patches that turn `old` into `new`.
+///
+/// Components and lazy nodes are compared by what they render, and fragments are
+/// flattened into their parent's children. Siblings are matched by key when every child
+/// on both sides has one and no key repeats, which lets a reorder become a handful of
+/// `MoveChild` patches instead of re-rendering each row; otherwise they are matched by
+/// position.
+///
+/// Paths can't point into a fragment that is itself the root, so a fragment root is
+/// always replaced. `RootHandle` sidesteps this by diffing relative to its container.
+pub fn diff(old: &Node, new: &Node) -> Vec<Patch> {
+    let mut patches = Vec::new();
+    diff_node(old, new, &mut Vec::new(), &mut patches);
+    patches
+}
+
+/// One line per patch in plain words, for logging what an update actually changes:
+/// `set attr class="big" on [0]`, `replace text at [0, 1] with "2"`. Paths are
+/// printed as the child indices leading to the node; `[]` is the root.
+pub fn describe_patches(patches: &[Patch]) -> String {
+    patches
+        .iter()
+        .map(describe_patch)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn describe_patch(patch: &Patch) -> String {
+    match patch {
+        Patch::Replace { path, node } => format!("replace {:?} with {}", path, describe_node(node)),
+        Patch::ReplaceText { path, text } => format!("replace text at {:?} with {:?}", path, text),
+        Patch::SetAttr { path, name, value } => {
+            format!("set attr {}={:?} on {:?}", name, value, path)
+        }
+        Patch::RemoveAttr { path, name } => format!("remove attr {} from {:?}", name, path),
+        Patch::InsertChild { path, index, node } => {
+            format!(
+                "insert {} into {:?} at {}",
+                describe_node(node),
+                path,
+                index
+            )
+        }
+        Patch::RemoveChild { path, index } => format!("remove child {} of {:?}", index, path),
+        Patch::MoveChild {
+            path,
+            from,
+            count: 1,
+            to,
+        } => {
+            format!("move child {} of {:?} to {}", from, path, to)
+        }
+        Patch::MoveChild {
+            path,
+            from,
+            count,
+            to,
+        } => {
+            format!(
+                "move children {}..{} of {:?} to {}",
+                from,
+                from + count,
+                path,
+                to
+            )
+        }
+        Patch::UpdateClasses { path, add, remove } => {
+            format!(
+                "add classes {:?} and remove {:?} on {:?}",
+                add, remove, path
+            )
+        }
+        Patch::SetListeners { path, listeners } => {
+            format!("rebind {} listeners on {:?}", listeners.len(), path)
+        }
+    }
+}
+
+/// A short label for a node: `<li>`, `text "hi"`, `fragment (2 nodes)`.
+fn describe_node(node: &Node) -> String {
+    match node {
+        Node::Element(element) => format!("<{}>", element.tag_name),
+        Node::Text(text) => format!("text {:?}", text),
+        Node::RawHtml(_) => "raw HTML".to_string(),
+        Node::Fragment(fragment) => format!("fragment ({} nodes)", fragment.children.len()),
+        Node::Component(_) | Node::Lazy(_) | Node::Async(_) => "component".to_string(),
+        Node::ClientOnly(inner) | Node::ServerOnly(inner) => describe_node(inner),
+    }
+}
+
+fn diff_node(old: &Node, new: &Node, path: &mut Vec<usize>, patches: &mut Vec<Patch>) {
+    match (old, new) {
+        (Node::Component(old_component), _) => {
+            diff_node(&old_component.render(), new, path, patches)
+        }
+        (_, Node::Component(new_component)) => {
+            diff_node(old, &new_component.render(), path, patches)
+        }
+        (Node::Lazy(old_lazy), _) => diff_node(&old_lazy.render(), new, path, patches),
+        (_, Node::Lazy(new_lazy)) => diff_node(old, &new_lazy.render(), path, patches),
+        (Node::Async(old_async), _) => diff_node(&old_async.fallback(), new, path, patches),
+        (_, Node::Async(new_async)) => diff_node(old, &new_async.fallback(), path, patches),
+        // In the browser both render their content like any other subtree.
+        (Node::ClientOnly(old_inner) | Node::ServerOnly(old_inner), _) => {
+            diff_node(old_inner, new, path, patches)
+        }
+        (_, Node::ClientOnly(new_inner) | Node::ServerOnly(new_inner)) => {
+            diff_node(old, new_inner, path, patches)
+        }
+        // Text always diffs against text in place, so `<span>1</span>` -> `<span>2</span>`
+        // reuses both the span and its text node instead of rebuilding the subtree.
+        (Node::Text(old_text), Node::Text(new_text)) => {
+            if old_text != new_text {
+                patches.push(Patch::ReplaceText {
+                    path: path.clone(),
+                    text: new_text.clone(),
+                });
+            }
+        }
+        (Node::Element(old_element), Node::Element(new_element))
+            if old_element.tag_name == new_element.tag_name
+                && old_element.key == new_element.key =>
+        {
+            diff_element(old_element, new_element, path, patches)
+        }
+        (Node::RawHtml(old_raw), Node::RawHtml(new_raw))
+            if old_raw.to_html() == new_raw.to_html() => {}
+        _ => patches.push(Patch::Replace {
+            path: path.clone(),
+            node: new.clone(),
+        }),
+    }
+}
+
+fn diff_element(old: &Element, new: &Element, path: &mut Vec<usize>, patches: &mut Vec<Patch>) {
+    let class_lists = old.class_list.as_ref().zip(new.class_list.as_ref());
+    if let Some((old_classes, new_classes)) = class_lists {
+        let add: Vec<String> = new_classes
+            .iter()
+            .filter(|name| !old_classes.contains(name))
+            .cloned()
+            .collect();
+        let remove: Vec<String> = old_classes
+            .iter()
+            .filter(|name| !new_classes.contains(name))
+            .cloned()
+            .collect();
+        if !add.is_empty() || !remove.is_empty() {
+            patches.push(Patch::UpdateClasses {
+                path: path.clone(),
+                add,
+                remove,
+            });
+        }
+    }
+    for (name, value) in &new.props {
+        if class_lists.is_some() && name == "class" {
+            continue;
+        }
+        if old.props.get(name) != Some(value) {
+            patches.push(Patch::SetAttr {
+                path: path.clone(),
+                name: name.clone(),
+                value: value.clone(),
+            });
+        }
+    }
+    for name in old
+        .props
+        .keys()
+        .filter(|name| !new.props.contains_key(name))
+    {
+        patches.push(Patch::RemoveAttr {
+            path: path.clone(),
+            name: name.clone(),
+        });
+    }
+
+    let same_listeners =
+        old.listeners.len() == new.listeners.len()
+            && old.listeners.iter().zip(&new.listeners).all(|(old, new)| {
+                old.event == new.event && Rc::ptr_eq(&old.handler.0, &new.handler.0)
+            });
+    if !same_listeners {
+        patches.push(Patch::SetListeners {
+            path: path.clone(),
+            listeners: new.listeners.clone(),
+        });
+    }
+
+    let old_slots = slots(&old.children);
+    let new_slots = slots(&new.children);
+
+    let has_raw_html = |slots: &[Slot]| {
+        slots
+            .iter()
+            .flat_map(|slot| &slot.nodes)
+            .any(|node| matches!(node, Node::RawHtml(_)))
+    };
+    if has_raw_html(&old_slots) || has_raw_html(&new_slots) {
+        // Raw markup can expand to any number of DOM nodes, so child indices can't be
+        // trusted. Fall back to re-rendering the element when its content changed.
+        if render_node_to_string(&Node::Element(old.clone()))
+            != render_node_to_string(&Node::Element(new.clone()))
+        {
+            patches.push(Patch::Replace {
+                path: path.clone(),
+                node: Node::Element(new.clone()),
+            });
+        }
+        return;
+    }
+
+    // Matching by key needs every key to name one slot; with duplicates, which of
+    // them an old slot corresponds to is ambiguous, so positions decide instead.
+    let uniquely_keyed = |slots: &[Slot]| {
+        let mut keys = HashSet::new();
+        slots
+            .iter()
+            .all(|slot| slot.key.as_ref().is_some_and(|key| keys.insert(key)))
+    };
+    if uniquely_keyed(&old_slots) && uniquely_keyed(&new_slots) {
+        diff_keyed_children(&old_slots, &new_slots, path, patches);
+    } else {
+        let flatten = |slots: Vec<Slot>| {
+            slots
+                .into_iter()
+                .flat_map(|slot| slot.nodes)
+                .collect::<Vec<_>>()
+        };
+        diff_children_by_index(&flatten(old_slots), &flatten(new_slots), 0, path, patches);
+    }
+}
+
+/// One child as the DOM sees it: components and lazy nodes are rendered and fragments
+/// are flattened, so `nodes` lines up one-to-one with the DOM children it produces.
+/// A keyed fragment stays a single slot and is therefore matched and moved as a unit.
+pub(crate) struct Slot {
+    pub(crate) key: Option<String>,
+    pub(crate) nodes: Vec<Node>,
+}
+
+pub(crate) fn slots(children: &[Node]) -> Vec<Slot> {
+    coalesce_text(children)
+        .iter()
+        .map(|child| {
+            let mut nodes = Vec::new();
+            flatten_into(child, &mut nodes);
+            Slot {
+                key: child.key().map(str::to_string),
+                nodes,
+            }
+        })
+        .collect()
+}
+
+fn flatten_into(node: &Node, nodes: &mut Vec<Node>) {
+    match node {
+        Node::Component(v_component) => flatten_into(&v_component.render(), nodes),
+        Node::Lazy(lazy) => flatten_into(&lazy.render(), nodes),
+        Node::Async(v_async) => flatten_into(&v_async.fallback(), nodes),
+        Node::ClientOnly(inner) | Node::ServerOnly(inner) => flatten_into(inner, nodes),
+        Node::Fragment(fragment) => {
+            for child in coalesce_text(&fragment.children).iter() {
+                flatten_into(child, nodes);
+            }
+        }
+        _ => nodes.push(node.clone()),
+    }
+}
+
+/// Turns the nodes of a slot back into a single node that renders all of them.
+fn slot_node(nodes: &[Node]) -> Node {
+    match nodes {
+        [node] => node.clone(),
+        nodes => Node::Fragment(Fragment {
+            children: nodes.to_vec(),
+            key: None,
+        }),
+    }
+}
+
+/// Diffs `old` against `new` position by position. `offset` is the DOM index of the
+/// first node, for when the lists are a slice of a larger set of siblings.
+fn diff_children_by_index(
+    old: &[Node],
+    new: &[Node],
+    offset: usize,
+    path: &mut Vec<usize>,
+    patches: &mut Vec<Patch>,
+) {
+    for (index, (old_child, new_child)) in old.iter().zip(new).enumerate() {
+        path.push(offset + index);
+        diff_node(old_child, new_child, path, patches);
+        path.pop();
+    }
+    // Remove from the end so the remaining indices stay valid.
+    for index in (new.len()..old.len()).rev() {
+        patches.push(Patch::RemoveChild {
+            path: path.clone(),
+            index: offset + index,
+        });
+    }
+    for (index, new_child) in new.iter().enumerate().skip(old.len()) {
+        patches.push(Patch::InsertChild {
+            path: path.clone(),
+            index: offset + index,
+            node: new_child.clone(),
+        });
+    }
+}
+
+fn diff_keyed_children(
+    old: &[Slot],
+    new: &[Slot],
+    path: &mut Vec<usize>,
+    patches: &mut Vec<Patch>,
+) {
+    let key = |slot: &Slot| slot.key.clone().expect("keyed diff needs keyed slots");
+    let old_by_key: HashMap<String, &Slot> = old.iter().map(|slot| (key(slot), slot)).collect();
+    let new_keys: Vec<String> = new.iter().map(key).collect();
+
+    // `current` mirrors the DOM children as the patches are applied: each slot's key
+    // and how many DOM nodes it occupies.
+    let mut current: Vec<(String, usize)> = old
+        .iter()
+        .map(|slot| (key(slot), slot.nodes.len()))
+        .collect();
+    let dom_index = |current: &[(String, usize)], slot_index: usize| -> usize {
+        current[..slot_index].iter().map(|(_, len)| len).sum()
+    };
+
+    for index in (0..current.len()).rev() {
+        if !new_keys.contains(&current[index].0) {
+            let at = dom_index(&current, index);
+            for _ in 0..current[index].1 {
+                patches.push(Patch::RemoveChild {
+                    path: path.clone(),
+                    index: at,
+                });
+            }
+            current.remove(index);
+        }
+    }
+
+    for (index, (key, new_slot)) in new_keys.iter().zip(new).enumerate() {
+        match current.iter().position(|(existing, _)| existing == key) {
+            Some(from) if from == index => {}
+            Some(from) => {
+                patches.push(Patch::MoveChild {
+                    path: path.clone(),
+                    from: dom_index(&current, from),
+                    count: current[from].1,
+                    to: dom_index(&current, index),
+                });
+                let moved = current.remove(from);
+                current.insert(index, moved);
+            }
+            None => {
+                patches.push(Patch::InsertChild {
+                    path: path.clone(),
+                    index: dom_index(&current, index),
+                    node: slot_node(&new_slot.nodes),
+                });
+                current.insert(index, (key.clone(), new_slot.nodes.len()));
+            }
+        }
+    }
+
+    // Every surviving slot is now in its final position; reconcile their contents.
+    // Slots before the current one already have their new length.
+    let mut offset = 0;
+    for (key, new_slot) in new_keys.iter().zip(new) {
+        if let Some(old_slot) = old_by_key.get(key) {
+            diff_children_by_index(&old_slot.nodes, &new_slot.nodes, offset, path, patches);
+        }
+        offset += new_slot.nodes.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Component, component};
+    use std::rc::Rc;
+
+    #[derive(Debug, Clone)]
+    struct Row(&'static str);
+
+    impl Component for Row {
+        fn render(&self) -> Node {
+            Node::Element(Element::new("li").child(self.0))
+        }
+
+        fn clone_rc(&self) -> Rc<dyn Component> {
+            Rc::new(self.clone())
+        }
+    }
+
+    fn list(rows: &[&'static str]) -> Node {
+        let mut ul = Element::new("ul");
+        for row in rows {
+            ul = ul.child(component(Row(row)).key(*row));
+        }
+        Node::Element(ul)
+    }
+
+    #[test]
+    fn test_keyed_components_are_moved_not_recreated() {
+        let patches = diff(&list(&["a", "b", "c"]), &list(&["c", "a", "b"]));
+
+        assert_eq!(patches.len(), 1, "unexpected patches: {:?}", patches);
+        assert!(matches!(
+            &patches[0],
+            Patch::MoveChild { path, from: 2, count: 1, to: 0 } if path.is_empty()
+        ));
+    }
+
+    #[test]
+    fn test_keyed_components_insert_and_remove_by_key() {
+        let patches = diff(&list(&["a", "b", "c"]), &list(&["a", "d", "c"]));
+
+        assert_eq!(patches.len(), 2, "unexpected patches: {:?}", patches);
+        assert!(matches!(&patches[0], Patch::RemoveChild { index: 1, .. }));
+        assert!(matches!(&patches[1], Patch::InsertChild { index: 1, .. }));
+    }
+
+    #[test]
+    fn test_duplicate_keys_fall_back_to_positions() {
+        let items = |texts: &[&str]| {
+            let mut ul = Element::new("ul");
+            for text in texts {
+                ul = ul.child(Element::new("li").key("a").child(*text));
+            }
+            Node::Element(ul)
+        };
+
+        let patches = diff(&items(&["1", "2"]), &items(&["1"]));
+
+        assert_eq!(patches.len(), 1, "unexpected patches: {:?}", patches);
+        assert!(matches!(
+            &patches[0],
+            Patch::RemoveChild { path, index: 1 } if path.is_empty()
+        ));
+        let patches = diff(&items(&["1"]), &items(&["2", "1"]));
+        assert_eq!(patches.len(), 2, "unexpected patches: {:?}", patches);
+        assert!(matches!(&patches[1], Patch::InsertChild { index: 1, .. }));
+    }
+
+    #[test]
+    fn test_unkeyed_children_are_diffed_by_position() {
+        let old = Node::Element(Element::new("div").attr("id", "x").child("one"));
+        let new = Node::Element(
+            Element::new("div")
+                .attr("class", "y")
+                .child("two")
+                .child(Element::new("b").child("three")),
+        );
+
+        let patches = diff(&old, &new);
+
+        assert_eq!(patches.len(), 4, "unexpected patches: {:?}", patches);
+        assert!(
+            matches!(&patches[0], Patch::SetAttr { name, value, .. } if name == "class" && value == "y")
+        );
+        assert!(matches!(&patches[1], Patch::RemoveAttr { name, .. } if name == "id"));
+        assert!(
+            matches!(&patches[2], Patch::ReplaceText { path, text } if path == &[0] && text == "two")
+        );
+        assert!(matches!(&patches[3], Patch::InsertChild { index: 1, .. }));
+    }
+
+    #[test]
+    fn test_text_change_reuses_element_and_text_node() {
+        let old = Node::Element(Element::new("span").attr("class", "count").child("1"));
+        let new = Node::Element(Element::new("span").attr("class", "count").child("2"));
+
+        let patches = diff(&old, &new);
+
+        assert_eq!(patches.len(), 1, "unexpected patches: {:?}", patches);
+        assert!(
+            matches!(&patches[0], Patch::ReplaceText { path, text } if path == &[0] && text == "2")
+        );
+    }
+
+    fn definitions(terms: &[&'static str]) -> Node {
+        let mut dl = Element::new("dl");
+        for term in terms {
+            dl = dl.child(
+                Fragment::new()
+                    .key(*term)
+                    .child(Element::new("dt").child(*term))
+                    .child(Element::new("dd").child(format!("about {}", term))),
+            );
+        }
+        Node::Element(dl)
+    }
+
+    #[test]
+    fn test_keyed_fragments_move_as_a_group() {
+        let patches = diff(
+            &definitions(&["a", "b", "c"]),
+            &definitions(&["c", "a", "b"]),
+        );
+
+        assert_eq!(patches.len(), 1, "unexpected patches: {:?}", patches);
+        assert!(matches!(
+            &patches[0],
+            Patch::MoveChild {
+                from: 4,
+                count: 2,
+                to: 0,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_keyed_fragments_insert_and_remove_whole_groups() {
+        let patches = diff(&definitions(&["a", "b"]), &definitions(&["b", "c"]));
+
+        assert_eq!(patches.len(), 3, "unexpected patches: {:?}", patches);
+        assert!(matches!(&patches[0], Patch::RemoveChild { index: 0, .. }));
+        assert!(matches!(&patches[1], Patch::RemoveChild { index: 0, .. }));
+        assert!(matches!(
+            &patches[2],
+            Patch::InsertChild { index: 2, node: Node::Fragment(group), .. } if group.children.len() == 2
+        ));
+    }
+
+    #[test]
+    fn test_adjacent_text_is_diffed_as_one_dom_node() {
+        let counter = |count: &str| {
+            Node::Element(
+                Element::new("p")
+                    .child("Count: ")
+                    .child(count)
+                    .child(Element::new("br")),
+            )
+        };
+
+        let patches = diff(&counter("1"), &counter("2"));
+
+        assert_eq!(patches.len(), 1, "unexpected patches: {:?}", patches);
+        assert!(matches!(
+            &patches[0],
+            Patch::ReplaceText { path, text } if path == &[0] && text == "Count: 2"
+        ));
+    }
+
+    #[test]
+    fn test_describe_patches() {
+        let old = Node::Element(Element::new("div").attr("class", "small").child("1"));
+        let new = Node::Element(
+            Element::new("div")
+                .attr("class", "big")
+                .child("2")
+                .child(Element::new("li")),
+        );
+
+        assert_eq!(
+            describe_patches(&diff(&old, &new)),
+            "set attr class=\"big\" on []\n\
+             replace text at [0] with \"2\"\n\
+             insert <li> into [] at 1"
+        );
+    }
+
+    #[test]
+    fn test_listeners_are_rebound_on_every_update() {
+        let render = || Node::Element(Element::new("button").on_click(|_| {}).child("Go"));
+
+        let patches = diff(&render(), &render());
+        assert_eq!(patches.len(), 1);
+        assert!(matches!(
+            &patches[0],
+            Patch::SetListeners { path, listeners } if path.is_empty() && listeners[0].event == "click"
+        ));
+    }
+
+    #[test]
+    fn test_unchanged_handlers_are_not_rebound() {
+        let button = Node::Element(Element::new("button").on_click(|_| {}).child("Go"));
+
+        assert!(diff(&button, &button.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_class_list_change_adds_and_removes_single_classes() {
+        let old = Node::Element(Element::new("li").class_list(["item", "selected"]));
+        let new = Node::Element(
+            Element::new("li")
+                .class_list(["item", "done"])
+                .attr("id", "a"),
+        );
+        let patches = diff(&old, &new);
+        assert_eq!(
+            describe_patches(&patches),
+            "add classes [\"done\"] and remove [\"selected\"] on []\nset attr id=\"a\" on []"
+        );
+
+        // A class string on either side falls back to rewriting the attribute.
+        let plain = Node::Element(Element::new("li").attr("class", "item"));
+        assert!(matches!(
+            &diff(&old, &plain)[..],
+            [Patch::SetAttr { name, value, .. }] if name == "class" && value == "item"
+        ));
+    }
+}
@@ -0,0 +1,141 @@
+//! Rendering complete HTML documents, with a doctype and the usual `<meta>` tags.
+
+use crate::commit::commit_on_server;
+use crate::{Element, Node, render_node_to_string};
+
+/// The `<meta>` tags `render_document_with` puts at the start of the `<head>`. Set a
+/// field to `None` to leave its tag out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentOptions {
+    /// `<meta charset="...">`. Defaults to `"utf-8"`, which is what the string
+    /// renderer produces.
+    pub charset: Option<String>,
+    /// `<meta name="viewport" content="...">`. Defaults to
+    /// `"width=device-width, initial-scale=1"`, without which mobile browsers lay the
+    /// page out for a desktop-sized screen.
+    pub viewport: Option<String>,
+}
+
+impl Default for DocumentOptions {
+    fn default() -> Self {
+        DocumentOptions {
+            charset: Some("utf-8".to_string()),
+            viewport: Some("width=device-width, initial-scale=1".to_string()),
+        }
+    }
+}
+
+/// Renders `node` as a full document with the default `DocumentOptions`.
+pub fn render_document(node: &Node) -> String {
+    render_document_with(node, &DocumentOptions::default())
+}
+
+/// Renders `node` as a full document: `<!DOCTYPE html>` followed by an `<html>` element
+/// whose `<head>` starts with the charset and viewport `<meta>` tags. A root `<html>`
+/// element is kept, gaining a `<head>` if it has none; anything else becomes the
+/// `<body>` of a new one. A tag the head already declares itself wins over the option.
+pub fn render_document_with(node: &Node, options: &DocumentOptions) -> String {
+    // Committed where it stands, so `use_id` gives the ids `hydrate` works out for it.
+    let node = commit_on_server(node);
+    let page = match root_element(&node) {
+        Some(element) if element.tag_name.eq_ignore_ascii_case("html") => element.clone(),
+        _ => Element::new("html")
+            .child(Element::new("head"))
+            .child(Element::new("body").child(node)),
+    };
+
+    let mut children = page.children.clone();
+    let head = match children.iter().position(is_head) {
+        Some(index) => index,
+        None => {
+            children.insert(0, Node::Element(Element::new("head")));
+            0
+        }
+    };
+    let Node::Element(head) = &mut children[head] else {
+        unreachable!("is_head only matches elements");
+    };
+    let mut metas = Vec::new();
+    if let Some(charset) = &options.charset
+        && !declares(head, |meta| meta.props.contains_key("charset"))
+    {
+        metas.push(Node::Element(Element::new("meta").attr("charset", charset)));
+    }
+    if let Some(viewport) = &options.viewport
+        && !declares(head, |meta| {
+            meta.props
+                .get("name")
+                .is_some_and(|name| name.eq_ignore_ascii_case("viewport"))
+        })
+    {
+        metas.push(Node::Element(
+            Element::new("meta")
+                .attr("name", "viewport")
+                .attr("content", viewport),
+        ));
+    }
+    // The charset has to come within the first 1024 bytes, so the tags go first.
+    head.children.splice(0..0, metas);
+
+    let html = render_node_to_string(&Node::Element(Element { children, ..page }));
+    format!("<!DOCTYPE html>{}", html)
+}
+
+/// The element the committed `node` renders as, looking through components.
+fn root_element(node: &Node) -> Option<&Element> {
+    match node {
+        Node::Element(element) => Some(element),
+        Node::Component(v_component) => v_component.output.as_deref().and_then(root_element),
+        _ => None,
+    }
+}
+
+fn is_head(node: &Node) -> bool {
+    matches!(node, Node::Element(element) if element.tag_name.eq_ignore_ascii_case("head"))
+}
+
+/// Whether `head` has a `<meta>` child matching `matches`.
+fn declares(head: &Element, matches: impl Fn(&Element) -> bool) -> bool {
+    head.children.iter().any(|child| {
+        matches!(child, Node::Element(meta)
+            if meta.tag_name.eq_ignore_ascii_case("meta") && matches(meta))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_document_includes_charset_and_viewport() {
+        let html = render_document(&Node::Element(Element::new("h1").child("Hello")));
+
+        assert_eq!(
+            html,
+            "<!DOCTYPE html><html><head><meta charset=\"utf-8\">\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+             </head><body><h1>Hello</h1></body></html>"
+        );
+    }
+
+    #[test]
+    fn test_document_options_and_head_tags_override_defaults() {
+        let page = Node::Element(
+            Element::new("html").attr("lang", "en").child(
+                Element::new("head")
+                    .child(Element::new("meta").attr("charset", "iso-8859-1"))
+                    .child(Element::new("title").child("Shop")),
+            ),
+        );
+        let options = DocumentOptions {
+            viewport: None,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render_document_with(&page, &options),
+            "<!DOCTYPE html><html lang=\"en\"><head><meta charset=\"iso-8859-1\">\
+             <title>Shop</title></head></html>"
+        );
+    }
+}
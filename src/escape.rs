@@ -0,0 +1,168 @@
+//! Escaping rules shared by everything that serializes VDOM to HTML.
+
+use std::borrow::Cow;
+
+/// Escapes a value for use inside a double-quoted attribute.
+///
+/// Besides `&`, `"`, `<` and `>`, the whitespace characters `\n`, `\r` and `\t` are
+/// encoded as numeric references (`&#10;`, `&#13;`, `&#9;`). Parsers normalize literal
+/// newlines inside attribute values, so encoding them is the only way to guarantee a
+/// multi-line `title` or inline JSON survives the round trip unchanged.
+pub(crate) fn escape_attr(value: &str) -> Cow<'_, str> {
+    escape_with(value, |c| match c {
+        '&' => Some("&amp;"),
+        '"' => Some("&quot;"),
+        '<' => Some("&lt;"),
+        '>' => Some("&gt;"),
+        '\n' => Some("&#10;"),
+        '\r' => Some("&#13;"),
+        '\t' => Some("&#9;"),
+        _ => None,
+    })
+}
+
+/// Escapes text content so it can never be mistaken for markup.
+pub(crate) fn escape_text(text: &str) -> Cow<'_, str> {
+    escape_with(text, |c| match c {
+        '&' => Some("&amp;"),
+        '<' => Some("&lt;"),
+        '>' => Some("&gt;"),
+        _ => None,
+    })
+}
+
+/// Elements that never have content. HTML has no end tag for them, so the serializers
+/// write none, and children put into them are left out.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+pub(crate) fn is_void_element(tag_name: &str) -> bool {
+    VOID_ELEMENTS
+        .iter()
+        .any(|void| void.eq_ignore_ascii_case(tag_name))
+}
+
+/// Prepares text for the inside of a raw-text element (`<script>` or `<style>`), whose
+/// content the parser takes literally instead of decoding entities. The text is kept as
+/// written, so `a < b && c` stays valid JavaScript; only a `</script` (in any case) that
+/// would end the element early is broken up as `<\/script`.
+pub(crate) fn escape_raw_text<'a>(text: &'a str, tag_name: &str) -> Cow<'a, str> {
+    let end_tag = format!("</{}", tag_name.to_ascii_lowercase());
+    // ASCII lowercasing keeps byte offsets, so matches line up with `text`.
+    let lower = text.to_ascii_lowercase();
+    if !lower.contains(&end_tag) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + 4);
+    let mut last = 0;
+    for (start, _) in lower.match_indices(&end_tag) {
+        escaped.push_str(&text[last..start]);
+        escaped.push_str("<\\/");
+        last = start + 2;
+    }
+    escaped.push_str(&text[last..]);
+    Cow::Owned(escaped)
+}
+
+/// Whether `tag_name` is a raw-text element, whose text children `escape_raw_text`
+/// handles instead of `escape_text`.
+pub(crate) fn is_raw_text_element(tag_name: &str) -> bool {
+    tag_name.eq_ignore_ascii_case("script") || tag_name.eq_ignore_ascii_case("style")
+}
+
+/// Decodes character references in parsed text or attribute values: the named ones
+/// `escape_attr` and `escape_text` produce (plus `&apos;` and `&nbsp;`) and numeric
+/// ones like `&#10;` or `&#x27;`. Anything else is left as written.
+pub(crate) fn unescape(input: &str) -> Cow<'_, str> {
+    if !input.contains('&') {
+        return Cow::Borrowed(input);
+    }
+    let mut decoded = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let reference = rest[1..].find(';').map(|end| &rest[1..end + 1]);
+        match reference.and_then(decode_reference) {
+            Some(c) => {
+                decoded.push(c);
+                rest = &rest[reference.map_or(0, str::len) + 2..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    Cow::Owned(decoded)
+}
+
+/// The character for a reference name such as `amp` or `#x27`.
+fn decode_reference(name: &str) -> Option<char> {
+    let code = match name {
+        "amp" => return Some('&'),
+        "lt" => return Some('<'),
+        "gt" => return Some('>'),
+        "quot" => return Some('"'),
+        "apos" => return Some('\''),
+        "nbsp" => return Some('\u{a0}'),
+        _ => name.strip_prefix('#')?,
+    };
+    let value = match code.strip_prefix(['x', 'X']) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+        None => code.parse().ok()?,
+    };
+    char::from_u32(value)
+}
+
+/// Only allocates when `input` actually contains a character that needs replacing.
+fn escape_with(input: &str, replacement: impl Fn(char) -> Option<&'static str>) -> Cow<'_, str> {
+    let Some(first) = input.find(|c| replacement(c).is_some()) else {
+        return Cow::Borrowed(input);
+    };
+    let mut escaped = String::with_capacity(input.len() + 8);
+    escaped.push_str(&input[..first]);
+    for c in input[first..].chars() {
+        match replacement(c) {
+            Some(entity) => escaped.push_str(entity),
+            None => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_attr_encodes_quotes_and_line_breaks() {
+        assert_eq!(escape_attr("plain"), "plain");
+        assert_eq!(escape_attr("a \"b\" & <c>"), "a &quot;b&quot; &amp; &lt;c&gt;");
+        assert_eq!(escape_attr("one\ntwo\r\n\tthree"), "one&#10;two&#13;&#10;&#9;three");
+    }
+
+    #[test]
+    fn test_escape_text_leaves_quotes_alone() {
+        assert_eq!(escape_text("1 < 2 & \"3\""), "1 &lt; 2 &amp; \"3\"");
+    }
+
+    #[test]
+    fn test_unescape_decodes_references() {
+        assert_eq!(unescape("a &quot;b&quot; &amp; &lt;c&gt;"), "a \"b\" & <c>");
+        assert_eq!(unescape("one&#10;two &#x27;q&#39;"), "one\ntwo 'q'");
+        assert_eq!(unescape("AT&T &unknown; &"), "AT&T &unknown; &");
+    }
+
+    #[test]
+    fn test_escape_raw_text_only_breaks_up_end_tags() {
+        assert_eq!(escape_raw_text("if (a < b && c > d) {}", "script"), "if (a < b && c > d) {}");
+        assert_eq!(
+            escape_raw_text("s = '</SCRIPT><b>'; t = '</style>'", "script"),
+            "s = '<\\/SCRIPT><b>'; t = '</style>'"
+        );
+    }
+}
@@ -0,0 +1,276 @@
+//! Helpers for pulling data out of DOM events inside handlers, and the glue that binds
+//! `Element::listeners` to DOM elements.
+
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::rc::Rc;
+
+use js_sys::{Array, Reflect};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    Element as DomElement, Event, HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement,
+};
+
+use crate::Listener;
+
+/// The property on a DOM element that holds the `[event, function]` pairs bound to it,
+/// so the next update can remove them before binding the new handlers.
+const BOUND_LISTENERS: &str = "__rustyReactListeners";
+
+/// An error returned by an `Element::on_fallible` handler, or a panic raised by any
+/// event handler, as passed to the error handler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventError {
+    /// The event the handler was listening for, e.g. `"click"`.
+    pub event: String,
+    /// The error's `Display` output, or the panic message.
+    pub message: String,
+}
+
+type ErrorHandler = Rc<dyn Fn(&EventError)>;
+
+/// A `debounce` timer that has not fired yet: the element whose listener started it,
+/// the event it debounces and the `setTimeout` handle.
+struct PendingTimer {
+    element: DomElement,
+    event: String,
+    timeout: i32,
+}
+
+thread_local! {
+    static ERROR_HANDLER: RefCell<Option<ErrorHandler>> = const { RefCell::new(None) };
+    static PENDING_TIMERS: RefCell<Vec<PendingTimer>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Routes failures of event handlers to `handler` instead of the default, which logs
+/// them with `console.error`: the errors returned by `Element::on_fallible` handlers,
+/// and panics where they can be caught.
+///
+/// Catching a panic needs unwinding. wasm32 builds use `panic = "abort"` by default,
+/// so there a panicking handler still takes the whole module down, and handlers that
+/// can fail should return their error through `on_fallible` instead.
+pub fn set_event_error_handler(handler: impl Fn(&EventError) + 'static) {
+    ERROR_HANDLER.with(|slot| *slot.borrow_mut() = Some(Rc::new(handler)));
+}
+
+/// Runs a handler for `event`, reporting a panic instead of letting it propagate.
+pub(crate) fn guard(event: &str, handler: impl FnOnce()) {
+    let Err(payload) = catch_unwind(AssertUnwindSafe(handler)) else {
+        return;
+    };
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "non-string panic payload".to_string());
+    report(event, message);
+}
+
+/// Reports the error of a fallible handler for `event`, if it returned one.
+pub(crate) fn report_failure<E: Display>(event: &str, result: Result<(), E>) {
+    if let Err(error) = result {
+        report(event, error.to_string());
+    }
+}
+
+fn report(event: &str, message: String) {
+    let error = EventError {
+        event: event.to_string(),
+        message,
+    };
+    match ERROR_HANDLER.with(|slot| slot.borrow().clone()) {
+        Some(handler) => handler(&error),
+        None => web_sys::console::error_1(&JsValue::from_str(&format!(
+            "Panic in '{}' handler: {}",
+            error.event, error.message
+        ))),
+    }
+}
+
+/// The current value of the form field an event was dispatched on, or `None` when
+/// the target is not an input, textarea or select.
+pub fn target_value(event: &Event) -> Option<String> {
+    let target = event.target()?;
+    field_value(&target)
+}
+
+/// The `value` of an input, textarea or select element.
+pub(crate) fn field_value(field: &JsValue) -> Option<String> {
+    if let Some(input) = field.dyn_ref::<HtmlInputElement>() {
+        Some(input.value())
+    } else if let Some(textarea) = field.dyn_ref::<HtmlTextAreaElement>() {
+        Some(textarea.value())
+    } else {
+        field
+            .dyn_ref::<HtmlSelectElement>()
+            .map(HtmlSelectElement::value)
+    }
+}
+
+/// Sets the `value` of an input, textarea or select element; other elements are left
+/// alone. Unlike the `value` attribute, this also changes what a field the user has
+/// already typed into shows.
+pub(crate) fn set_field_value(field: &JsValue, value: &str) {
+    if let Some(input) = field.dyn_ref::<HtmlInputElement>() {
+        input.set_value(value);
+    } else if let Some(textarea) = field.dyn_ref::<HtmlTextAreaElement>() {
+        textarea.set_value(value);
+    } else if let Some(select) = field.dyn_ref::<HtmlSelectElement>() {
+        select.set_value(value);
+    }
+}
+
+/// Wraps `handler` so that it only runs once events stop arriving for `ms`
+/// milliseconds, with the last event. Each event cancels the timer started by the one
+/// before it on the same element. Timers are kept per element and event rather than
+/// per handler, so one survives the handler being rebound by an update, and an update
+/// that removes the element cancels its timer. The handler is also skipped if the
+/// element has left the document some other way by the time the timer fires.
+pub fn debounce(ms: i32, handler: impl Fn(&Event) + 'static) -> impl Fn(&Event) {
+    let handler: Rc<dyn Fn(&Event)> = Rc::new(handler);
+    move |event| {
+        let Some(element) = event
+            .current_target()
+            .and_then(|target| target.dyn_into::<DomElement>().ok())
+        else {
+            // Called outside of a dispatch; there is nothing to debounce against.
+            handler(event);
+            return;
+        };
+        let window = web_sys::window().expect("no global `window` exists");
+        let name = event.type_();
+        if let Some(previous) = take_pending_timer(&element, &name) {
+            window.clear_timeout_with_handle(previous.timeout);
+        }
+
+        let handler = handler.clone();
+        let event = event.clone();
+        let target = element.clone();
+        let fire = Closure::once_into_js(move || {
+            let name = event.type_();
+            take_pending_timer(&target, &name);
+            if target.is_connected() {
+                guard(&name, || handler(&event));
+            }
+        });
+        let timeout = window
+            .set_timeout_with_callback_and_timeout_and_arguments_0(fire.unchecked_ref(), ms)
+            .expect("Failed to set timeout");
+        PENDING_TIMERS.with(|timers| {
+            timers.borrow_mut().push(PendingTimer {
+                element,
+                event: name,
+                timeout,
+            })
+        });
+    }
+}
+
+/// Removes the pending timer of `element` for `event` from the list, if it has one.
+fn take_pending_timer(element: &DomElement, event: &str) -> Option<PendingTimer> {
+    PENDING_TIMERS.with(|timers| {
+        let mut timers = timers.borrow_mut();
+        let index = timers
+            .iter()
+            .position(|timer| timer.element == *element && timer.event == event)?;
+        Some(timers.swap_remove(index))
+    })
+}
+
+/// Cancels the pending `debounce` timers of elements that are no longer in the
+/// document. Runs after patches that remove or replace nodes.
+pub(crate) fn cancel_detached_timers() {
+    let detached: Vec<PendingTimer> = PENDING_TIMERS.with(|timers| {
+        let mut timers = timers.borrow_mut();
+        let (detached, connected) = timers
+            .drain(..)
+            .partition(|timer: &PendingTimer| !timer.element.is_connected());
+        *timers = connected;
+        detached
+    });
+    if detached.is_empty() {
+        return;
+    }
+    let window = web_sys::window().expect("no global `window` exists");
+    for timer in detached {
+        window.clear_timeout_with_handle(timer.timeout);
+    }
+}
+
+/// Replaces the listeners bound to `element` with `listeners`. The closures are handed
+/// over to JavaScript, which frees them once the element is collected.
+pub(crate) fn bind_listeners(element: &DomElement, listeners: &[Listener]) {
+    let key = JsValue::from_str(BOUND_LISTENERS);
+    let bound = Reflect::get(element, &key).expect("Failed to read bound listeners");
+    if let Some(bound) = bound.dyn_ref::<Array>() {
+        for pair in bound.iter() {
+            let pair: Array = pair.unchecked_into();
+            let event = pair
+                .get(0)
+                .as_string()
+                .expect("Bound event name must be a string");
+            element
+                .remove_event_listener_with_callback(&event, pair.get(1).unchecked_ref())
+                .expect("Failed to remove event listener");
+        }
+    } else if listeners.is_empty() {
+        return;
+    }
+
+    let bound = Array::new();
+    for listener in listeners {
+        let handler = listener.handler.0.clone();
+        let name = listener.event.clone();
+        let callback =
+            Closure::<dyn Fn(Event)>::new(move |event: Event| guard(&name, || handler(&event)));
+        let callback = callback.into_js_value();
+        element
+            .add_event_listener_with_callback(&listener.event, callback.unchecked_ref())
+            .expect("Failed to add event listener");
+        bound.push(&Array::of2(&JsValue::from_str(&listener.event), &callback));
+    }
+    Reflect::set(element, &key, &bound).expect("Failed to store bound listeners");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panicking_handler_is_reported_not_propagated() {
+        let reported = Rc::new(RefCell::new(Vec::new()));
+        let recorder = reported.clone();
+        set_event_error_handler(move |error| recorder.borrow_mut().push(error.clone()));
+
+        guard("click", || panic!("button exploded"));
+        guard("click", || {});
+
+        assert_eq!(
+            *reported.borrow(),
+            [EventError {
+                event: "click".to_string(),
+                message: "button exploded".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_fallible_handler_errors_are_reported() {
+        let reported = Rc::new(RefCell::new(Vec::new()));
+        let recorder = reported.clone();
+        set_event_error_handler(move |error| recorder.borrow_mut().push(error.clone()));
+
+        report_failure("submit", Err("email is missing"));
+        report_failure::<&str>("submit", Ok(()));
+
+        assert_eq!(
+            *reported.borrow(),
+            [EventError {
+                event: "submit".to_string(),
+                message: "email is missing".to_string(),
+            }]
+        );
+    }
+}
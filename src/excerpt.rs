@@ -0,0 +1,209 @@
+//! Size-limited HTML, for previews and snippets.
+
+use crate::commit::commit_on_server;
+use crate::escape::{escape_raw_text, escape_text, is_raw_text_element, is_void_element};
+use crate::{CLIENT_ONLY_PLACEHOLDER, Node, end_tag, render_node_to_string, write_start_tag};
+
+/// Appended where the content was cut off.
+const ELLIPSIS: &str = "…";
+
+/// Renders `node` like `render_node_to_string`, but stops once the output would grow
+/// beyond `max_bytes`. Every element that is open at the cutoff is still closed, so
+/// the excerpt is well-formed HTML, and an ellipsis marks where content was left out.
+/// The closing tags and the ellipsis count towards the budget.
+///
+/// Text is cut at a character boundary; elements whose tags would not fit, and raw
+/// HTML or script and style code that does not fit as a whole, are left out entirely.
+pub fn render_node_to_string_truncated(node: &Node, max_bytes: usize) -> String {
+    let full = render_node_to_string(node);
+    if full.len() <= max_bytes {
+        return full;
+    }
+    let mut excerpt = Excerpt {
+        out: String::new(),
+        max_bytes,
+        closing_len: 0,
+        truncated: false,
+        foreign: false,
+        raw_text: None,
+    };
+    // Committed like `render_node_to_string` commits, so `use_id` gives the same ids.
+    excerpt.write(&commit_on_server(node));
+    excerpt.out
+}
+
+struct Excerpt {
+    out: String,
+    max_bytes: usize,
+    /// The length of the end tags the currently open elements still need.
+    closing_len: usize,
+    truncated: bool,
+    /// Inside `<svg>` or `<math>`, where `<script>` and `<style>` are ordinary elements.
+    foreign: bool,
+    /// The tag of the enclosing `<script>` or `<style>`, whose text is code.
+    raw_text: Option<String>,
+}
+
+impl Excerpt {
+    /// How much more can be written while leaving room for the end tags and ellipsis.
+    fn available(&self) -> usize {
+        self.max_bytes
+            .saturating_sub(self.out.len() + self.closing_len + ELLIPSIS.len())
+    }
+
+    fn cut_off(&mut self) {
+        self.truncated = true;
+        if self.out.len() + self.closing_len + ELLIPSIS.len() <= self.max_bytes {
+            self.out.push_str(ELLIPSIS);
+        }
+    }
+
+    /// Writes `markup` if it fits as a whole, or cuts off.
+    fn write_whole(&mut self, markup: &str) {
+        if markup.len() <= self.available() {
+            self.out.push_str(markup);
+        } else {
+            self.cut_off();
+        }
+    }
+
+    fn write(&mut self, node: &Node) {
+        if self.truncated {
+            return;
+        }
+        match node {
+            Node::Text(text) if self.raw_text.is_some() => {
+                let tag_name = self.raw_text.as_deref().unwrap_or_default();
+                // Half a script is broken code, so it goes in whole or not at all.
+                self.write_whole(&escape_raw_text(text, tag_name))
+            }
+            Node::Text(text) => {
+                let escaped = escape_text(text);
+                if escaped.len() <= self.available() {
+                    self.out.push_str(&escaped);
+                    return;
+                }
+                // Escape character by character so an entity is never split.
+                let mut buffer = [0; 4];
+                for c in text.chars() {
+                    let escaped = escape_text(c.encode_utf8(&mut buffer));
+                    if escaped.len() > self.available() {
+                        break;
+                    }
+                    self.out.push_str(&escaped);
+                }
+                self.cut_off();
+            }
+            Node::Element(element) => {
+                let mut start_tag = Vec::new();
+                write_start_tag(element, &mut start_tag).expect("Writing to a Vec never fails");
+                let start_tag = String::from_utf8(start_tag).expect("Tags are valid UTF-8");
+                let end_tag = end_tag(element);
+                if start_tag.len() + end_tag.len() > self.available() {
+                    self.cut_off();
+                    return;
+                }
+                self.out.push_str(&start_tag);
+                self.closing_len += end_tag.len();
+                let tag_name = element.tag_name.as_str();
+                let outer = (self.foreign, self.raw_text.take());
+                self.foreign = outer.0
+                    || tag_name.eq_ignore_ascii_case("svg")
+                    || tag_name.eq_ignore_ascii_case("math");
+                if !self.foreign && is_raw_text_element(tag_name) {
+                    self.raw_text = Some(tag_name.to_string());
+                }
+                if !is_void_element(tag_name) {
+                    for child in &element.children {
+                        self.write(child);
+                    }
+                }
+                (self.foreign, self.raw_text) = outer;
+                self.closing_len -= end_tag.len();
+                self.out.push_str(&end_tag);
+            }
+            Node::RawHtml(raw) => self.write_whole(&raw.to_html()),
+            Node::Component(v_component) => self.write(&v_component.render()),
+            Node::Lazy(lazy) => self.write(&lazy.render()),
+            Node::Async(v_async) => self.write(&v_async.fallback()),
+            Node::ClientOnly(_) => self.write_whole(CLIENT_ONLY_PLACEHOLDER),
+            Node::ServerOnly(inner) => self.write(inner),
+            Node::Fragment(fragment) => {
+                for child in &fragment.children {
+                    self.write(child);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Component, Element, component, use_id};
+    use std::rc::Rc;
+
+    fn article() -> Node {
+        Node::Element(
+            Element::new("article")
+                .child(Element::new("h2").child("Release notes"))
+                .child(
+                    Element::new("p")
+                        .child("Faster diffs & ")
+                        .child(Element::new("em").child("fewer allocations")),
+                ),
+        )
+    }
+
+    #[test]
+    fn test_truncated_output_is_balanced_and_within_budget() {
+        let excerpt = render_node_to_string_truncated(&article(), 60);
+
+        assert_eq!(
+            excerpt,
+            "<article><h2>Release notes</h2><p>Faster di…</p></article>"
+        );
+        assert!(excerpt.len() <= 60);
+    }
+
+    #[test]
+    fn test_output_within_budget_is_unchanged() {
+        let full = render_node_to_string(&article());
+        assert_eq!(
+            render_node_to_string_truncated(&article(), full.len()),
+            full
+        );
+    }
+
+    #[derive(Debug, Clone)]
+    struct Field;
+
+    impl Component for Field {
+        fn render(&self) -> Node {
+            Node::Element(Element::new("input").attr("id", use_id()))
+        }
+
+        fn clone_rc(&self) -> Rc<dyn Component> {
+            Rc::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_excerpt_keeps_code_unescaped_and_ids_apart() {
+        let page = Node::Element(
+            Element::new("div")
+                .child(Element::new("script").child("if (a < b && c) {}"))
+                .child(component(Field))
+                .child(component(Field))
+                .child(Element::new("p").child("A long closing paragraph")),
+        );
+
+        let excerpt = render_node_to_string_truncated(&page, 110);
+
+        assert_eq!(
+            excerpt,
+            "<div><script>if (a < b && c) {}</script><input id=\"rr-id-1\">\
+             <input id=\"rr-id-2\"><p>A long closing…</p></div>"
+        );
+    }
+}
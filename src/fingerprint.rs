@@ -0,0 +1,98 @@
+//! Cheap structural identity for VDOM trees.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+use crate::Node;
+
+impl Node {
+    /// A hash of what the tree renders to. Two trees with the same fingerprint produce
+    /// the same DOM, so an update between them can be skipped. Components and lazy
+    /// nodes are expanded, async components contribute their fallback, and `on_create`
+    /// callbacks are ignored since they only run when an element is first created.
+    /// Listeners count by identity: reusing the same handler keeps the fingerprint.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hash_node(self, &mut hasher);
+        hasher.finish()
+    }
+}
+
+fn hash_node(node: &Node, hasher: &mut DefaultHasher) {
+    match node {
+        Node::Element(element) => {
+            0u8.hash(hasher);
+            element.tag_name.hash(hasher);
+            element.key.hash(hasher);
+            element.props.len().hash(hasher);
+            for (name, value) in &element.props {
+                name.hash(hasher);
+                value.hash(hasher);
+            }
+            for listener in &element.listeners {
+                listener.event.hash(hasher);
+                (Rc::as_ptr(&listener.handler.0) as *const () as usize).hash(hasher);
+            }
+            hash_children(&element.children, hasher);
+        }
+        Node::Text(text) => {
+            1u8.hash(hasher);
+            text.hash(hasher);
+        }
+        Node::RawHtml(raw) => {
+            2u8.hash(hasher);
+            raw.to_html().hash(hasher);
+        }
+        Node::Fragment(fragment) => {
+            3u8.hash(hasher);
+            fragment.key.hash(hasher);
+            hash_children(&fragment.children, hasher);
+        }
+        Node::Component(v_component) => {
+            4u8.hash(hasher);
+            v_component.key.hash(hasher);
+            hash_node(&v_component.render(), hasher);
+        }
+        Node::Lazy(lazy) => hash_node(&lazy.render(), hasher),
+        Node::Async(v_async) => hash_node(&v_async.fallback(), hasher),
+        Node::ClientOnly(inner) => {
+            5u8.hash(hasher);
+            hash_node(inner, hasher);
+        }
+        Node::ServerOnly(inner) => {
+            6u8.hash(hasher);
+            hash_node(inner, hasher);
+        }
+    }
+}
+
+fn hash_children(children: &[Node], hasher: &mut DefaultHasher) {
+    children.len().hash(hasher);
+    for child in children {
+        hash_node(child, hasher);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{App, Element, Node, component};
+
+    #[test]
+    fn test_fingerprint_matches_only_identical_trees() {
+        let list = |last: &str| {
+            Node::Element(
+                Element::new("ul")
+                    .attr("class", "todo")
+                    .child(Element::new("li").child(last)),
+            )
+        };
+
+        assert_eq!(list("milk").fingerprint(), list("milk").fingerprint());
+        assert_ne!(list("milk").fingerprint(), list("eggs").fingerprint());
+        assert_eq!(
+            Node::Component(component(App)).fingerprint(),
+            Node::Component(component(App)).fingerprint()
+        );
+    }
+}
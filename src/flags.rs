@@ -0,0 +1,94 @@
+//! A runtime feature-flag registry for A/B tests and gradual rollouts. Flags live in a
+//! per-thread `Store`, so roots can subscribe and re-render when one is toggled.
+
+use std::collections::HashMap;
+
+use crate::store::{Store, SubscriptionId, create_store};
+use crate::{Fragment, IntoNode, Node};
+
+thread_local! {
+    static FLAGS: Store<HashMap<String, bool>> = create_store(HashMap::new());
+}
+
+/// Turns the flag `name` on or off. Subscribers are only notified when the value
+/// actually changes.
+pub fn set_flag(name: &str, enabled: bool) {
+    FLAGS.with(|flags| {
+        if flag(name) != enabled {
+            flags.update(|flags| {
+                flags.insert(name.to_string(), enabled);
+            });
+        }
+    });
+}
+
+/// Whether the flag `name` is on. Flags that were never set are off.
+pub fn flag(name: &str) -> bool {
+    FLAGS.with(|flags| flags.with(|flags| flags.get(name).copied().unwrap_or(false)))
+}
+
+/// `node` while the flag `name` is on, and nothing otherwise. The flag is read when
+/// the tree is built, so rebuild it from a `subscribe_flags` callback to pick up a
+/// toggle: `subscribe_flags(move || handle.schedule_update(app()))`.
+pub fn flagged(name: &str, node: impl IntoNode) -> Node {
+    if flag(name) {
+        node.into_node()
+    } else {
+        Node::Fragment(Fragment::default())
+    }
+}
+
+/// Calls `callback` whenever a flag is toggled.
+pub fn subscribe_flags(callback: impl Fn() + 'static) -> SubscriptionId {
+    FLAGS.with(|flags| flags.subscribe(move |_| callback()))
+}
+
+pub fn unsubscribe_flags(id: SubscriptionId) {
+    FLAGS.with(|flags| flags.unsubscribe(id));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{Element, render_node_to_string};
+
+    fn page() -> Node {
+        Node::Element(
+            Element::new("main")
+                .child(Element::new("h1").child("Shop"))
+                .child(flagged("new-checkout", Element::new("button").child("Buy"))),
+        )
+    }
+
+    #[test]
+    fn test_toggling_flag_includes_and_excludes_subtree() {
+        assert_eq!(render_node_to_string(&page()), "<main><h1>Shop</h1></main>");
+
+        set_flag("new-checkout", true);
+        assert_eq!(
+            render_node_to_string(&page()),
+            "<main><h1>Shop</h1><button>Buy</button></main>"
+        );
+
+        set_flag("new-checkout", false);
+        assert_eq!(render_node_to_string(&page()), "<main><h1>Shop</h1></main>");
+    }
+
+    #[test]
+    fn test_subscribers_are_notified_only_on_change() {
+        let toggles = Rc::new(Cell::new(0));
+        let counter = toggles.clone();
+        let id = subscribe_flags(move || counter.set(counter.get() + 1));
+
+        set_flag("beta", true);
+        set_flag("beta", true);
+        unsubscribe_flags(id);
+        set_flag("beta", false);
+
+        assert_eq!(toggles.get(), 1);
+        assert!(!flag("beta"));
+    }
+}
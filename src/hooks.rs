@@ -0,0 +1,449 @@
+//! Hooks: per-component state that survives re-renders.
+//!
+//! A mounted root keeps the hook state of its components by their position in the
+//! tree (see `HookTree`), so a component keeps its state across updates even when its
+//! parent builds a new `VComponent` for it on every render. Outside a root, the state
+//! lives in the `VComponent` and is shared by all clones of it. Hooks are identified by
+//! the order they are called in during `render`, so they must be called
+//! unconditionally and always in the same order.
+
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{Debug, Write};
+use std::rc::Rc;
+
+use crate::Node;
+
+/// The hook slots of one component instance.
+#[derive(Default)]
+pub(crate) struct Hooks {
+    slots: RefCell<Vec<Option<Box<dyn Any>>>>,
+}
+
+impl Debug for Hooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Hooks({} slots)", self.slots.borrow().len())
+    }
+}
+
+/// One step from a node to one of its children: the child's key if it has one, its
+/// index otherwise. A component's output is its child at index 0.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Segment {
+    Index(usize),
+    Key(String),
+}
+
+/// Hook state by component position, with the type of the component's props.
+pub(crate) type HookEntries = HashMap<Vec<Segment>, (TypeId, Rc<Hooks>)>;
+
+/// The hook state of the components of a mounted tree, by position.
+#[derive(Default)]
+pub(crate) struct HookTree {
+    entries: RefCell<HookEntries>,
+}
+
+impl HookTree {
+    /// The hook state of the component at `position`, if the last commit rendered one
+    /// with the same props type there.
+    pub(crate) fn find(&self, position: &[Segment], props: TypeId) -> Option<Rc<Hooks>> {
+        match self.entries.borrow().get(position) {
+            Some((previous, hooks)) if *previous == props => Some(hooks.clone()),
+            _ => None,
+        }
+    }
+
+    /// Keeps only `entries`, the components rendered by the latest commit.
+    pub(crate) fn replace(&self, entries: HookEntries) {
+        *self.entries.borrow_mut() = entries;
+    }
+}
+
+/// The component currently rendering, its position, and the index of its next hook
+/// and of its next `use_id` call.
+struct Frame {
+    hooks: Rc<Hooks>,
+    position: Vec<Segment>,
+    next: usize,
+    next_id: usize,
+}
+
+thread_local! {
+    static FRAMES: RefCell<Vec<Frame>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Runs `render` with `hooks` as the state that hook calls read and write, for the
+/// component at `position`.
+pub(crate) fn render_with_hooks(
+    hooks: &Rc<Hooks>,
+    position: &[Segment],
+    render: impl FnOnce() -> Node,
+) -> Node {
+    FRAMES.with(|frames| {
+        frames.borrow_mut().push(Frame {
+            hooks: hooks.clone(),
+            position: position.to_vec(),
+            next: 0,
+            next_id: 0,
+        })
+    });
+    // Popped even if `render` panics, so later renders don't use this component's hooks.
+    let _frame = PopFrame;
+    render()
+}
+
+struct PopFrame;
+
+impl Drop for PopFrame {
+    fn drop(&mut self) {
+        FRAMES.with(|frames| frames.borrow_mut().pop());
+    }
+}
+
+/// Gives `use_slot` the current component's next hook slot, creating it with `init`
+/// on the first render. The slot is taken out while `use_slot` runs, so the closure
+/// may render other components.
+pub(crate) fn with_slot<T: 'static, R>(
+    init: impl FnOnce() -> T,
+    use_slot: impl FnOnce(&mut T) -> R,
+) -> R {
+    let (hooks, index) = FRAMES.with(|frames| {
+        let mut frames = frames.borrow_mut();
+        let frame = frames
+            .last_mut()
+            .expect("Hooks can only be called while a component renders");
+        frame.next += 1;
+        (frame.hooks.clone(), frame.next - 1)
+    });
+
+    let slot = {
+        let mut slots = hooks.slots.borrow_mut();
+        if slots.len() <= index {
+            slots.resize_with(index + 1, || None);
+        }
+        slots[index].take()
+    };
+    let mut value: Box<T> = match slot {
+        Some(slot) => slot
+            .downcast()
+            .unwrap_or_else(|_| panic!("Hook {} changed type between renders", index)),
+        None => Box::new(init()),
+    };
+    let result = use_slot(&mut value);
+    hooks.slots.borrow_mut()[index] = Some(value);
+    result
+}
+
+/// Returns the node built by `compute`, reusing the one from the previous render as
+/// long as `deps` compares equal to the deps it was built with.
+pub fn use_memo<D: PartialEq + 'static>(deps: D, compute: impl FnOnce() -> Node) -> Node {
+    with_slot(
+        || None::<(D, Node)>,
+        |memo| match memo {
+            Some((previous, node)) if *previous == deps => node.clone(),
+            _ => {
+                let node = compute();
+                *memo = Some((deps, node.clone()));
+                node
+            }
+        },
+    )
+}
+
+/// An id that is unique on the page and stays the same across re-renders of this
+/// component instance, e.g. to pair a `label` with its input via `for`/`id`.
+///
+/// The id is built from the component's position in the tree: `rr-id-0-1` for the
+/// second child of the first child of the root, with `-c1`, `-c2`, ... added for the
+/// component's further `use_id` calls. Every HTML renderer in the crate commits the
+/// tree before walking it, and a mounted root commits it the same way, so the server's
+/// markup and the hydrated client agree on every id, and re-rendering the same tree
+/// gives the same ids. A component rendered on its own with `VComponent::render`,
+/// outside a committed tree, counts as the root.
+pub fn use_id() -> String {
+    FRAMES.with(|frames| {
+        let mut frames = frames.borrow_mut();
+        let frame = frames
+            .last_mut()
+            .expect("Hooks can only be called while a component renders");
+        let mut id = "rr-id".to_string();
+        for segment in &frame.position {
+            match segment {
+                Segment::Index(index) => write!(id, "-{}", index).unwrap(),
+                Segment::Key(key) => {
+                    // Spelled out so that ids stay valid and different keys stay apart.
+                    id.push_str("-k");
+                    for c in key.chars() {
+                        if c.is_ascii_alphanumeric() {
+                            id.push(c);
+                        } else {
+                            write!(id, "_{:x}", c as u32).unwrap();
+                        }
+                    }
+                }
+            }
+        }
+        if frame.next_id > 0 {
+            write!(id, "-c{}", frame.next_id).unwrap();
+        }
+        frame.next_id += 1;
+        id
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commit::commit_in;
+    use crate::{Component, Element, VComponent, component, diff, render_node_to_string};
+    use std::cell::Cell;
+
+    #[derive(Debug, Clone)]
+    struct Report {
+        year: Rc<Cell<u32>>,
+        builds: Rc<Cell<usize>>,
+    }
+
+    impl Component for Report {
+        fn render(&self) -> Node {
+            let builds = self.builds.clone();
+            let year = self.year.get();
+            use_memo(year, move || {
+                builds.set(builds.get() + 1);
+                Node::Text(format!("Report {}", year))
+            })
+        }
+
+        fn clone_rc(&self) -> Rc<dyn Component> {
+            Rc::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_use_memo_recomputes_only_when_deps_change() {
+        let year = Rc::new(Cell::new(2023));
+        let builds = Rc::new(Cell::new(0));
+        let report: VComponent = component(Report {
+            year: year.clone(),
+            builds: builds.clone(),
+        });
+        let render = |v_component: &VComponent| match v_component.render() {
+            Node::Text(text) => text,
+            other => panic!("unexpected node {:?}", other),
+        };
+
+        assert_eq!(render(&report), "Report 2023");
+        assert_eq!(render(&report.clone()), "Report 2023");
+        assert_eq!(builds.get(), 1);
+
+        year.set(2024);
+        assert_eq!(render(&report), "Report 2024");
+        assert_eq!(builds.get(), 2);
+    }
+
+    #[derive(Debug, Clone)]
+    struct Field {
+        label: &'static str,
+    }
+
+    impl Component for Field {
+        fn render(&self) -> Node {
+            let id = use_id();
+            Node::Element(
+                Element::new("p")
+                    .child(
+                        Element::new("label")
+                            .attr("for", id.as_str())
+                            .child(self.label),
+                    )
+                    .child(Element::new("textarea").attr("id", id.as_str())),
+            )
+        }
+
+        fn clone_rc(&self) -> Rc<dyn Component> {
+            Rc::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_use_id_sequence_is_identical_across_renders() {
+        let form = || {
+            Node::Element(
+                Element::new("form")
+                    .child(component(Field { label: "Name" }))
+                    .child(component(Field { label: "Bio" })),
+            )
+        };
+        let first = render_node_to_string(&form());
+        let second = render_node_to_string(&form());
+
+        assert_eq!(first, second);
+        assert!(first.contains("<label for=\"rr-id-0\">Name</label><textarea id=\"rr-id-0\">"));
+        assert!(first.contains("<label for=\"rr-id-1\">Bio</label>"));
+
+        let hooks = HookTree::default();
+        let mounted = commit_in(&form(), &hooks);
+        assert_eq!(render_node_to_string(&mounted), first);
+        assert!(diff(&mounted, &commit_in(&form(), &hooks)).is_empty());
+    }
+
+    #[derive(Debug, Clone)]
+    struct Pair;
+
+    impl Component for Pair {
+        fn render(&self) -> Node {
+            Node::Text(format!("{} {}", use_id(), use_id()))
+        }
+
+        fn clone_rc(&self) -> Rc<dyn Component> {
+            Rc::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_use_id_spells_out_keys_and_numbers_further_calls() {
+        let list = Node::Element(
+            Element::new("ul")
+                .child(component(Pair).key("a b"))
+                .child(component(Pair)),
+        );
+
+        assert_eq!(
+            render_node_to_string(&list),
+            "<ul>rr-id-ka_20b rr-id-ka_20b-c1rr-id-1 rr-id-1-c1</ul>"
+        );
+    }
+
+    #[derive(Debug, Clone)]
+    struct Dashboard {
+        report: Report,
+    }
+
+    impl Component for Dashboard {
+        fn render(&self) -> Node {
+            Node::Element(Element::new("main").child(component(self.report.clone())))
+        }
+
+        fn clone_rc(&self) -> Rc<dyn Component> {
+            Rc::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_nested_component_keeps_its_memo_across_commits() {
+        let year = Rc::new(Cell::new(2023));
+        let builds = Rc::new(Cell::new(0));
+        let dashboard = || {
+            Node::Component(component(Dashboard {
+                report: Report {
+                    year: year.clone(),
+                    builds: builds.clone(),
+                },
+            }))
+        };
+        let hooks = HookTree::default();
+
+        commit_in(&dashboard(), &hooks);
+        let second = commit_in(&dashboard(), &hooks);
+        assert_eq!(builds.get(), 1);
+        assert_eq!(render_node_to_string(&second), "<main>Report 2023</main>");
+
+        year.set(2024);
+        commit_in(&dashboard(), &hooks);
+        assert_eq!(builds.get(), 2);
+    }
+
+    #[derive(Debug, Clone)]
+    struct Broken;
+
+    impl Component for Broken {
+        fn render(&self) -> Node {
+            panic!("render failed")
+        }
+
+        fn clone_rc(&self) -> Rc<dyn Component> {
+            Rc::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_panicking_render_leaves_no_hook_frame_behind() {
+        let result = std::panic::catch_unwind(|| component(Broken).render());
+
+        assert!(result.is_err());
+        assert!(FRAMES.with(|frames| frames.borrow().is_empty()));
+    }
+
+    #[derive(Debug, Clone)]
+    struct Layer;
+
+    impl Component for Layer {
+        fn render(&self) -> Node {
+            Node::Element(Element::new("g").attr("id", use_id()))
+        }
+
+        fn clone_rc(&self) -> Rc<dyn Component> {
+            Rc::new(self.clone())
+        }
+    }
+
+    /// Polls a future that never actually waits until it completes.
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+        loop {
+            if let std::task::Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_html_renderer_gives_components_their_own_ids() {
+        use crate::*;
+
+        let icon = Node::Element(
+            Element::new("svg")
+                .child(component(Layer))
+                .child(component(Layer)),
+        );
+        let renders = [
+            ("string", render_node_to_string(&icon)),
+            (
+                "bytes",
+                String::from_utf8(render_node_to_bytes(&icon)).unwrap(),
+            ),
+            ("document", render_document(&icon)),
+            ("svg", render_svg_document(&icon).unwrap()),
+            ("manifest", render_node_to_string_with_manifest(&icon).0),
+            ("nonce", render_node_to_string_with_nonce(&icon, "r4nd0m")),
+            ("transform", render_with_transform(&icon, |_| None)),
+            (
+                "critical css",
+                render_node_to_string_with_critical_css(&icon),
+            ),
+            ("locale", render_node_to_string(&with_locale(&icon, "en"))),
+            (
+                "cached",
+                render_node_to_string_cached(&icon, &mut RenderCache::new()),
+            ),
+            ("truncated", render_node_to_string_truncated(&icon, 1000)),
+            ("pretty", render_node_to_pretty_string(&icon)),
+            ("async", block_on(render_node_to_string_async(&icon))),
+            (
+                "stream",
+                block_on(render_node_to_stream(&icon).next_chunk()).unwrap(),
+            ),
+        ];
+
+        for (renderer, html) in renders {
+            assert!(
+                html.contains("<g id=\"rr-id-0\">") && html.contains("<g id=\"rr-id-1\">"),
+                "{}: {}",
+                renderer,
+                html
+            );
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! A visual diff between two trees, for debugging and visual regression reports.
+
+use crate::diff::slots;
+use crate::{Element, Fragment, Node, render_node_to_string};
+
+/// Renders `new` as HTML with the differences from `old` marked up: content that is
+/// gone is wrapped in `<del>`, content that is new in `<ins>`, and an element whose
+/// attributes changed gets a `data-diff-attrs` attribute listing their names.
+///
+/// Children are compared by position after components are rendered and fragments
+/// flattened, the way the DOM sees them.
+pub fn html_diff(old: &Node, new: &Node) -> String {
+    let marked = diff_children(std::slice::from_ref(old), std::slice::from_ref(new));
+    render_node_to_string(&Node::Fragment(Fragment {
+        children: marked,
+        key: None,
+    }))
+}
+
+fn flat(children: &[Node]) -> Vec<Node> {
+    slots(children)
+        .into_iter()
+        .flat_map(|slot| slot.nodes)
+        .collect()
+}
+
+fn diff_children(old: &[Node], new: &[Node]) -> Vec<Node> {
+    let old = flat(old);
+    let new = flat(new);
+    let mut marked = Vec::with_capacity(new.len().max(old.len()));
+    for i in 0..new.len().max(old.len()) {
+        match (old.get(i), new.get(i)) {
+            (Some(old), Some(new)) => marked.extend(diff_node(old, new)),
+            (Some(old), None) => marked.push(wrap("del", old)),
+            (None, Some(new)) => marked.push(wrap("ins", new)),
+            (None, None) => unreachable!("index is below the longer length"),
+        }
+    }
+    marked
+}
+
+fn diff_node(old: &Node, new: &Node) -> Vec<Node> {
+    match (old, new) {
+        (Node::Text(old_text), Node::Text(new_text)) if old_text == new_text => vec![new.clone()],
+        (Node::RawHtml(old_raw), Node::RawHtml(new_raw))
+            if old_raw.to_html() == new_raw.to_html() =>
+        {
+            vec![new.clone()]
+        }
+        (Node::Element(old_element), Node::Element(new_element))
+            if old_element.tag_name == new_element.tag_name
+                && old_element.key == new_element.key =>
+        {
+            let mut changed: Vec<&str> = new_element
+                .props
+                .iter()
+                .filter(|(name, value)| old_element.props.get(name) != Some(*value))
+                .map(|(name, _)| name.as_str())
+                .collect();
+            changed.extend(
+                old_element
+                    .props
+                    .keys()
+                    .filter(|name| !new_element.props.contains_key(name))
+                    .map(String::as_str),
+            );
+            let mut marked = Element {
+                children: diff_children(&old_element.children, &new_element.children),
+                ..new_element.clone()
+            };
+            if !changed.is_empty() {
+                marked = marked.attr("data-diff-attrs", changed.join(" "));
+            }
+            vec![Node::Element(marked)]
+        }
+        _ => vec![wrap("del", old), wrap("ins", new)],
+    }
+}
+
+fn wrap(marker: &str, node: &Node) -> Node {
+    Node::Element(Element::new(marker).child(node.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_text_is_wrapped_in_markers() {
+        let old = Node::Element(
+            Element::new("ul")
+                .child(Element::new("li").child("Milk"))
+                .child(Element::new("li").attr("class", "done").child("Eggs")),
+        );
+        let new = Node::Element(
+            Element::new("ul")
+                .child(Element::new("li").child("Oat milk"))
+                .child(Element::new("li").child("Eggs"))
+                .child(Element::new("li").child("Bread")),
+        );
+
+        assert_eq!(
+            html_diff(&old, &new),
+            "<ul><li><del>Milk</del><ins>Oat milk</ins></li>\
+             <li data-diff-attrs=\"class\">Eggs</li>\
+             <ins><li>Bread</li></ins></ul>"
+        );
+    }
+}
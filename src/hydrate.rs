@@ -0,0 +1,265 @@
+//! Adopting server-rendered markup instead of rendering it again.
+
+use wasm_bindgen::JsCast;
+use web_sys::{Document, Element as DomElement, HtmlTemplateElement, Node as DomNode, Text};
+
+use crate::commit::commit_in;
+use crate::event::bind_listeners;
+use crate::hooks::HookTree;
+use crate::root::{RootHandle, find_mount_point};
+use crate::warnings::{WarningKind, warn};
+use crate::{
+    CLIENT_ONLY_PLACEHOLDER, EVENT_ID_ATTRIBUTE, Element, Listener, Node, OnCreate, coalesce_text,
+    render_node_to_dom,
+};
+
+/// Takes over the markup already inside the element with id `mount_point_id` (usually
+/// produced by `render_node_to_string` on the server) as the rendering of `node`,
+/// without rebuilding it. Listeners are bound and `on_create` callbacks run against the
+/// existing elements.
+///
+/// Browsers merge adjacent text when parsing HTML, while the DOM renderer keeps text
+/// from different components apart, so merged text nodes are split to line up with
+/// the VDOM again. If the markup does not match, a `HydrationMismatch` warning is
+/// emitted and the content is rendered from scratch. An adopted element whose
+/// attributes differ from the VDOM's gets the VDOM's attributes instead, with a
+/// `HydrationAttributeMismatch` warning.
+///
+/// Markup from `render_node_to_string_with_manifest` hydrates the same way: elements
+/// are found by walking the markup, not through the manifest, and their `data-rr-id`
+/// attributes are left in place.
+///
+/// `Node::ClientOnly` content is rendered where the server left its placeholder, and
+/// `Node::ServerOnly` markup is adopted without binding its listeners or running its
+/// `on_create` callbacks.
+pub fn hydrate(node: Node, mount_point_id: &str) -> RootHandle {
+    let (document, container) = find_mount_point(mount_point_id);
+    // Committed the way the string renderer commits, so `use_id` gives the same ids.
+    let hooks = HookTree::default();
+    let node = commit_in(&node, &hooks);
+
+    let mut hydration = Hydration {
+        document: &document,
+        created: Vec::new(),
+        listened: Vec::new(),
+    };
+    let matched = hydration.hydrate_children(std::slice::from_ref(&node), &container, true);
+    match matched {
+        Ok(()) => {
+            for (listeners, element) in hydration.listened {
+                bind_listeners(&element, &listeners);
+            }
+            for (OnCreate(callback), element) in hydration.created {
+                callback(&element);
+            }
+        }
+        Err(mismatch) => {
+            warn(
+                WarningKind::HydrationMismatch,
+                format!(
+                    "Hydrating '{}' failed: {}; rendering on the client instead",
+                    mount_point_id, mismatch
+                ),
+            );
+            container.set_inner_html("");
+            render_node_to_dom(&node, &document, &container);
+        }
+    }
+
+    RootHandle::new(document, container, node, hooks)
+}
+
+struct Hydration<'a> {
+    document: &'a Document,
+    /// `on_create` callbacks of matched elements, children first. They only run once
+    /// the whole tree is known to match.
+    created: Vec<(OnCreate, DomElement)>,
+    /// Listeners of matched elements, bound under the same condition.
+    listened: Vec<(Vec<Listener>, DomElement)>,
+}
+
+impl Hydration<'_> {
+    /// Matches all DOM children of `parent` against `children`.
+    fn hydrate_children(
+        &mut self,
+        children: &[Node],
+        parent: &DomNode,
+        interactive: bool,
+    ) -> Result<(), String> {
+        // The renderer's markers (component boundaries, client-only placeholders) have
+        // no VDOM counterpart. Dropping them keeps DOM child indices in line with the
+        // diff. Other comments come from raw HTML, which counts them as its nodes.
+        let child_nodes = parent.child_nodes();
+        let comments: Vec<DomNode> = (0..child_nodes.length())
+            .filter_map(|i| child_nodes.item(i))
+            .filter(is_marker)
+            .collect();
+        for comment in comments {
+            parent
+                .remove_child(&comment)
+                .expect("Failed to remove comment");
+        }
+
+        let mut cursor = parent.first_child();
+        self.hydrate_nodes(&expected_nodes(children), parent, &mut cursor, interactive)?;
+        match cursor {
+            Some(extra) => Err(format!("unexpected node {:?}", extra.node_name())),
+            None => Ok(()),
+        }
+    }
+
+    /// Matches `expected` against the DOM nodes starting at `cursor`, leaving `cursor`
+    /// on the first node after them.
+    fn hydrate_nodes(
+        &mut self,
+        expected: &[Node],
+        parent: &DomNode,
+        cursor: &mut Option<DomNode>,
+        interactive: bool,
+    ) -> Result<(), String> {
+        for node in expected {
+            match node {
+                Node::Text(text) if text.is_empty() => {
+                    // The HTML parser never produces empty text nodes, so recreate it.
+                    let empty = self.document.create_text_node("");
+                    parent
+                        .insert_before(&empty, cursor.as_ref())
+                        .expect("Failed to insert text node");
+                }
+                Node::Text(text) => {
+                    let dom = cursor
+                        .clone()
+                        .filter(|dom| dom.node_type() == DomNode::TEXT_NODE)
+                        .ok_or_else(|| format!("expected text {:?}", text))?;
+                    let content = dom.text_content().unwrap_or_default();
+                    if !content.starts_with(text.as_str()) {
+                        return Err(format!("expected text {:?}, found {:?}", text, content));
+                    }
+                    if content.len() > text.len() {
+                        // `splitText` counts UTF-16 code units.
+                        let offset = text.encode_utf16().count() as u32;
+                        dom.unchecked_ref::<Text>()
+                            .split_text(offset)
+                            .expect("Failed to split text node");
+                    }
+                    *cursor = dom.next_sibling();
+                }
+                Node::Element(element) => {
+                    let dom = cursor
+                        .clone()
+                        .filter(|dom| {
+                            dom.dyn_ref::<DomElement>().is_some_and(|dom| {
+                                dom.local_name().eq_ignore_ascii_case(&element.tag_name)
+                            })
+                        })
+                        .ok_or_else(|| format!("expected <{}>", element.tag_name))?;
+                    adopt_attributes(element, dom.unchecked_ref());
+                    self.hydrate_children(&element.children, &dom, interactive)?;
+                    if interactive && !element.listeners.is_empty() {
+                        self.listened
+                            .push((element.listeners.clone(), dom.clone().unchecked_into()));
+                    }
+                    if let Some(on_create) = element.on_create.as_ref().filter(|_| interactive) {
+                        self.created
+                            .push((on_create.clone(), dom.clone().unchecked_into()));
+                    }
+                    *cursor = dom.next_sibling();
+                }
+                Node::RawHtml(raw) => {
+                    // Parse the markup the same way the DOM renderer would to learn how
+                    // many nodes it occupies, and skip over them.
+                    let template: HtmlTemplateElement = self
+                        .document
+                        .create_element("template")
+                        .expect("Failed to create template")
+                        .unchecked_into();
+                    template.set_inner_html(&raw.to_html());
+                    for _ in 0..template.content().child_nodes().length() {
+                        let dom = cursor.clone().ok_or("raw HTML is missing nodes")?;
+                        *cursor = dom.next_sibling();
+                    }
+                }
+                Node::ClientOnly(inner) => {
+                    let fragment = self.document.create_document_fragment();
+                    render_node_to_dom(inner, self.document, &fragment);
+                    parent
+                        .insert_before(&fragment, cursor.as_ref())
+                        .expect("Failed to insert client-only content");
+                }
+                Node::ServerOnly(inner) => {
+                    let inner = expected_nodes(std::slice::from_ref(inner.as_ref()));
+                    self.hydrate_nodes(&inner, parent, cursor, false)?;
+                }
+                _ => unreachable!("expected_nodes expands components, lazy nodes and fragments"),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether `node` is a comment the string renderer writes around or in place of VDOM
+/// content.
+fn is_marker(node: &DomNode) -> bool {
+    let placeholder = CLIENT_ONLY_PLACEHOLDER
+        .trim_start_matches("<!--")
+        .trim_end_matches("-->");
+    node.node_type() == DomNode::COMMENT_NODE
+        && matches!(node.text_content().as_deref(), Some(text)
+            if ["$", "$?", "/$", placeholder].contains(&text))
+}
+
+/// Gives the adopted `dom` the attributes of `element` where they differ, so later
+/// diffs start from what the VDOM describes, and warns about each difference.
+fn adopt_attributes(element: &Element, dom: &DomElement) {
+    for (name, value) in &element.props {
+        let found = dom.get_attribute(name);
+        if found.as_deref() != Some(value.as_str()) {
+            warn(
+                WarningKind::HydrationAttributeMismatch,
+                format!(
+                    "<{}> has {}={:?} in the server markup but {:?} in the VDOM",
+                    element.tag_name, name, found, value
+                ),
+            );
+            dom.set_attribute(name, value)
+                .expect("Failed to set attribute");
+        }
+    }
+    let names = dom.get_attribute_names();
+    for name in names.iter().filter_map(|name| name.as_string()) {
+        // Manifest ids are only in the markup, and the HTML parser lowercases names.
+        let known = name == EVENT_ID_ATTRIBUTE
+            || element
+                .props
+                .keys()
+                .any(|prop| prop.eq_ignore_ascii_case(&name));
+        if !known {
+            warn(
+                WarningKind::HydrationAttributeMismatch,
+                format!(
+                    "<{}> has {:?} in the server markup but not in the VDOM",
+                    element.tag_name, name
+                ),
+            );
+            dom.remove_attribute(&name)
+                .expect("Failed to remove attribute");
+        }
+    }
+}
+
+/// `children` as the DOM renderer lays them out, one entry per DOM node, except
+/// that client- and server-only subtrees are kept whole. This mirrors how the diff
+/// flattens children, so the adopted DOM lines up with its indices.
+fn expected_nodes(children: &[Node]) -> Vec<Node> {
+    let mut nodes = Vec::with_capacity(children.len());
+    for child in coalesce_text(children).iter() {
+        match child {
+            Node::Component(v_component) => nodes.extend(expected_nodes(&[v_component.render()])),
+            Node::Lazy(lazy) => nodes.extend(expected_nodes(&[lazy.render()])),
+            Node::Async(v_async) => nodes.extend(expected_nodes(&[v_async.fallback()])),
+            Node::Fragment(fragment) => nodes.extend(expected_nodes(&fragment.children)),
+            _ => nodes.push(child.clone()),
+        }
+    }
+    nodes
+}
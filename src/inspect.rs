@@ -0,0 +1,144 @@
+//! A JSON view of VDOM trees for devtools and debugging. It describes the tree for a
+//! human or an inspector UI; it is not meant to be parsed back into a `Node`.
+
+use serde_json::{Map, Value, json};
+
+use crate::Node;
+
+impl Node {
+    /// Describes the tree as JSON. Every node is an object with a `type` (`element`,
+    /// `text`, `component`, ...) and the fields that matter for it. Components, lazy
+    /// nodes and async components are expanded, with their output under `rendered`
+    /// (the fallback, for async components). Components also carry their `debug_name`
+    /// as `name` and their `debug_props` as `props`.
+    pub fn to_debug_json(&self) -> Value {
+        match self {
+            Node::Element(element) => {
+                let props: Map<String, Value> = element
+                    .props
+                    .iter()
+                    .map(|(name, value)| (name.clone(), Value::String(value.clone())))
+                    .collect();
+                let mut object = json!({
+                    "type": "element",
+                    "tag": element.tag_name,
+                    "props": props,
+                    "children": children_json(&element.children),
+                });
+                with_key(&mut object, element.key.as_deref());
+                object
+            }
+            Node::Text(text) => json!({ "type": "text", "text": text }),
+            Node::RawHtml(raw) => json!({ "type": "raw_html", "html": raw.to_html() }),
+            Node::Component(v_component) => {
+                let mut object = json!({
+                    "type": "component",
+                    "name": v_component.debug_name(),
+                    "props": v_component.debug_props(),
+                    "rendered": v_component.render().to_debug_json(),
+                });
+                with_key(&mut object, v_component.key.as_deref());
+                object
+            }
+            Node::Lazy(lazy) => {
+                json!({ "type": "lazy", "rendered": lazy.render().to_debug_json() })
+            }
+            Node::Async(v_async) => {
+                json!({ "type": "async", "rendered": v_async.fallback().to_debug_json() })
+            }
+            Node::Fragment(fragment) => {
+                let mut object = json!({
+                    "type": "fragment",
+                    "children": children_json(&fragment.children),
+                });
+                with_key(&mut object, fragment.key.as_deref());
+                object
+            }
+            Node::ClientOnly(inner) => {
+                json!({ "type": "client_only", "content": inner.to_debug_json() })
+            }
+            Node::ServerOnly(inner) => {
+                json!({ "type": "server_only", "content": inner.to_debug_json() })
+            }
+        }
+    }
+}
+
+fn children_json(children: &[Node]) -> Value {
+    Value::Array(children.iter().map(Node::to_debug_json).collect())
+}
+
+/// Adds a `key` field only for keyed nodes, to keep the output small.
+fn with_key(object: &mut Value, key: Option<&str>) {
+    if let (Some(key), Value::Object(map)) = (key, object) {
+        map.insert("key".to_string(), Value::String(key.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{App, Component, component};
+
+    #[test]
+    fn test_app_debug_json() {
+        let tree = Node::Component(component(App).key("root"));
+
+        assert_eq!(
+            tree.to_debug_json(),
+            json!({
+                "type": "component",
+                "name": "App",
+                "props": "App",
+                "key": "root",
+                "rendered": {
+                    "type": "element",
+                    "tag": "div",
+                    "props": { "class": "app-container", "data-rendered-by": "rusty-react" },
+                    "children": [
+                        {
+                            "type": "element",
+                            "tag": "h1",
+                            "props": {},
+                            "children": [{ "type": "text", "text": "Hello from a Rusty Component!" }],
+                        },
+                        {
+                            "type": "element",
+                            "tag": "p",
+                            "props": {},
+                            "children": [
+                                { "type": "text", "text": "This was rendered via a component trait." }
+                            ],
+                        },
+                    ],
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_component_debug_name_can_be_overridden() {
+        #[derive(Debug, Clone)]
+        struct Avatar {
+            user: &'static str,
+        }
+        impl Component for Avatar {
+            fn render(&self) -> Node {
+                Node::Text(self.user.to_string())
+            }
+            fn clone_rc(&self) -> Rc<dyn Component> {
+                Rc::new(self.clone())
+            }
+            fn debug_name(&self) -> String {
+                format!("Avatar({})", self.user)
+            }
+        }
+
+        assert_eq!(component(App).debug_name(), "App");
+        let avatar = component(Avatar { user: "ada" });
+        assert_eq!(avatar.debug_name(), "Avatar(ada)");
+        assert_eq!(avatar.debug_props(), "Avatar { user: \"ada\" }");
+    }
+}
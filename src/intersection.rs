@@ -0,0 +1,181 @@
+//! Deferring content until it scrolls into view.
+
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::rc::{Rc, Weak};
+
+use js_sys::Array;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::closure::Closure;
+use web_sys::{IntersectionObserver, IntersectionObserverEntry, IntersectionObserverInit};
+
+use crate::store::{Store, SubscriptionId, create_store};
+use crate::{Component, Element, IntoNode, Node, OnCreate};
+
+/// A component that renders `placeholder` until it comes within `root_margin` of the
+/// viewport, then renders its real child instead, e.g. a heavy chart or an async
+/// component further down a long page. `subscribe` to re-render the root when it
+/// flips. The `IntersectionObserver` stops once the content is shown and is
+/// disconnected when the last clone of the component is dropped.
+#[derive(Clone)]
+pub struct IntersectionLazy {
+    child: Node,
+    placeholder: Element,
+    root_margin: String,
+    visible: Store<bool>,
+    observer: Rc<RefCell<Option<Observer>>>,
+}
+
+/// Keeps the observer running for as long as it is alive.
+struct Observer {
+    observer: IntersectionObserver,
+    _callback: Closure<dyn FnMut(Array, IntersectionObserver)>,
+}
+
+impl Drop for Observer {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+    }
+}
+
+impl IntersectionLazy {
+    pub fn new(child: impl IntoNode) -> Self {
+        IntersectionLazy {
+            child: child.into_node(),
+            placeholder: Element::new("div"),
+            root_margin: "200px".to_string(),
+            visible: create_store(false),
+            observer: Rc::default(),
+        }
+    }
+
+    /// What to show until the content is due, ideally sized like it so the page
+    /// doesn't jump. Defaults to an empty `div`.
+    pub fn placeholder(mut self, placeholder: Element) -> Self {
+        self.placeholder = placeholder;
+        self
+    }
+
+    /// How far from the viewport loading starts, as a CSS margin. Defaults to `200px`.
+    pub fn root_margin(mut self, margin: impl Into<String>) -> Self {
+        self.root_margin = margin.into();
+        self
+    }
+
+    /// Whether the real content is shown.
+    pub fn is_visible(&self) -> bool {
+        self.visible.get()
+    }
+
+    /// Calls `callback` once the content is due to be shown.
+    pub fn subscribe(&self, callback: impl Fn(&bool) + 'static) -> SubscriptionId {
+        self.visible.subscribe(callback)
+    }
+
+    pub fn unsubscribe(&self, id: SubscriptionId) {
+        self.visible.unsubscribe(id)
+    }
+
+    fn observe(&self, placeholder: &web_sys::Element) {
+        // The observer owns the callback, so the callback only holds on to the observer
+        // weakly; otherwise neither would ever be dropped.
+        let visible = self.visible.clone();
+        let observer: Weak<RefCell<Option<Observer>>> = Rc::downgrade(&self.observer);
+        let callback = Closure::<dyn FnMut(Array, IntersectionObserver)>::new(
+            move |entries: Array, _: IntersectionObserver| {
+                let intersecting = entries.iter().any(|entry| {
+                    entry
+                        .unchecked_into::<IntersectionObserverEntry>()
+                        .is_intersecting()
+                });
+                if let Some(observer) = observer.upgrade() {
+                    show_when_intersecting(&visible, &observer, intersecting);
+                }
+            },
+        );
+        let options = IntersectionObserverInit::new();
+        options.set_root_margin(&self.root_margin);
+        let observer =
+            IntersectionObserver::new_with_options(callback.as_ref().unchecked_ref(), &options)
+                .expect("Failed to create IntersectionObserver");
+        observer.observe(placeholder);
+        *self.observer.borrow_mut() = Some(Observer {
+            observer,
+            _callback: callback,
+        });
+    }
+}
+
+/// What the observer callback does with a batch of entries.
+fn show_when_intersecting(
+    visible: &Store<bool>,
+    observer: &RefCell<Option<Observer>>,
+    intersecting: bool,
+) {
+    if intersecting && !visible.get() {
+        if let Some(observer) = observer.borrow().as_ref() {
+            observer.observer.disconnect();
+        }
+        visible.update(|visible| *visible = true);
+    }
+}
+
+impl Component for IntersectionLazy {
+    fn render(&self) -> Node {
+        if self.visible.get() {
+            return self.child.clone();
+        }
+        let previous = self.placeholder.on_create.clone();
+        let this = self.clone();
+        Node::Element(self.placeholder.clone().on_create(move |dom_element| {
+            if let Some(OnCreate(callback)) = &previous {
+                callback(dom_element);
+            }
+            this.observe(dom_element);
+        }))
+    }
+
+    fn clone_rc(&self) -> Rc<dyn Component> {
+        Rc::new(self.clone())
+    }
+}
+
+impl Debug for IntersectionLazy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IntersectionLazy")
+            .field("visible", &self.is_visible())
+            .field("root_margin", &self.root_margin)
+            .field("child", &self.child)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::{component, render_node_to_string};
+
+    #[test]
+    fn test_intersection_swaps_placeholder_for_content() {
+        let chart = IntersectionLazy::new(Element::new("figure").child("Chart"))
+            .placeholder(Element::new("div").attr("class", "chart-placeholder"));
+        let swaps = Rc::new(Cell::new(0));
+        let counter = swaps.clone();
+        chart.subscribe(move |_| counter.set(counter.get() + 1));
+        let page = Node::Component(component(chart.clone()));
+
+        assert_eq!(
+            render_node_to_string(&page),
+            "<div class=\"chart-placeholder\"></div>"
+        );
+        show_when_intersecting(&chart.visible, &chart.observer, false);
+        assert!(!chart.is_visible());
+
+        show_when_intersecting(&chart.visible, &chart.observer, true);
+        show_when_intersecting(&chart.visible, &chart.observer, true);
+        assert_eq!(render_node_to_string(&page), "<figure>Chart</figure>");
+        assert_eq!(swaps.get(), 1);
+    }
+}
@@ -1,38 +1,732 @@
-use std::collections::HashMap;
+use std::any::Any;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::BTreeMap;
 use std::fmt::Debug;
+use std::future::Future;
+use std::io::{self, Write};
+use std::pin::Pin;
 use std::rc::Rc;
+use wasm_bindgen::JsCast;
 use wasm_bindgen::prelude::*;
 
 // Aliasing the `web_sys` types to avoid naming collisions with our VDOM types.
-use web_sys::{Document, Element as DomElement, Node as DomNode, Window};
+use web_sys::{Document, Element as DomElement, Event, HtmlTemplateElement, Node as DomNode};
+
+use commit::commit_on_server;
+use escape::{escape_attr, escape_raw_text, escape_text, is_raw_text_element, is_void_element};
+use hooks::{Hooks, Segment, render_with_hooks};
+use svg::SVG_NAMESPACE;
+
+mod animation;
+mod auth;
+mod cache;
+mod canvas;
+mod commit;
+mod critical_css;
+mod dialog;
+mod document;
+mod diff;
+mod escape;
+mod event;
+mod excerpt;
+mod fingerprint;
+mod flags;
+mod hooks;
+mod html_diff;
+mod hydrate;
+mod inspect;
+mod intersection;
+mod locale;
+mod manifest;
+mod media;
+mod parse;
+mod patch;
+mod pretty;
+mod profile;
+mod props;
+mod renderer;
+mod resources;
+mod root;
+mod sanitize;
+mod scheduler;
+mod shortcut;
+mod signal;
+mod skeleton;
+mod source;
+mod ssr;
+mod stats;
+mod store;
+mod style;
+mod svg;
+mod tag;
+mod template;
+mod text;
+mod transform;
+mod transition;
+mod units;
+mod variant;
+mod warnings;
+mod worker;
+
+pub use animation::use_animation_frame;
+pub use auth::{
+    Authenticated, Guest, current_user, set_current_user, subscribe_auth, unsubscribe_auth,
+};
+pub use cache::{RenderCache, render_node_to_string_cached};
+pub use canvas::CanvasRenderer;
+pub use critical_css::render_node_to_string_with_critical_css;
+pub use dialog::{Dialog, DialogHandle};
+pub use document::{DocumentOptions, render_document, render_document_with};
+pub use diff::{Patch, describe_patches, diff};
+pub use event::{EventError, debounce, set_event_error_handler, target_value};
+pub use excerpt::render_node_to_string_truncated;
+pub use flags::{flag, flagged, set_flag, subscribe_flags, unsubscribe_flags};
+pub use hooks::{use_id, use_memo};
+pub use html_diff::html_diff;
+pub use hydrate::hydrate;
+pub use intersection::IntersectionLazy;
+pub use locale::{Direction, text_direction, with_locale};
+pub use manifest::{
+    EVENT_ID_ATTRIBUTE, EventManifest, HydrationPriority, render_node_to_string_with_manifest,
+};
+pub use media::MediaQuery;
+pub use parse::{ParseError, html_semantically_equal, parse_html};
+pub use patch::{apply_patches, insert_node_at};
+pub use profile::{render_counts, reset_render_counts};
+pub use props::Props;
+pub use pretty::{PrettyOptions, render_node_to_pretty_string, render_node_to_pretty_string_with};
+pub use renderer::{DomRenderer, Renderer};
+pub use resources::{Resources, collect_resources};
+pub use root::{RootHandle, mount, mount_point_exists, render_into_iframe, render_into_window};
+pub use sanitize::{SanitizePolicy, sanitize_html};
+pub use scheduler::{DomReads, DomWrites, batch};
+pub use shortcut::{KeyCombo, Shortcut, register_shortcut};
+pub use signal::Signal;
+pub use skeleton::{Skeleton, SkeletonShape};
+pub use source::{SOURCE_ATTRIBUTE, set_source_locations};
+pub use ssr::{
+    HtmlStream, render_node_to_stream, render_node_to_stream_with_nonce,
+    render_node_to_string_async,
+};
+pub use stats::RenderStats;
+pub use store::{Store, SubscriptionId, create_store};
+pub use style::Style;
+pub use svg::render_svg_document;
+pub use tag::{Tag, caption, colgroup, form, table, tbody, td, tfoot, th, thead, tr};
+pub use template::{CompiledTemplate, compile};
+pub use text::{collect_text, render_node_to_text};
+pub use transform::{
+    render_node_to_string_with_nonce, render_with_transform, transform_node, with_aria_defaults,
+};
+pub use transition::{Transition, TransitionPhase};
+pub use units::{em, percent, px, rem};
+pub use variant::VariantClass;
+pub use warnings::{Warning, WarningKind, check_tree, set_warning_suppressed, take_warnings};
+pub use worker::{WirePatch, apply_wire_patches, decode_patches, encode_patches, to_wire_patches};
 
 // Our Virtual DOM Element structure. This is our blueprint.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Element {
     pub tag_name: String,
-    pub props: HashMap<String, String>,
+    pub props: Props,
     pub children: Vec<Node>,
+    /// Identifies the element among its siblings so reordering can be reconciled.
+    pub key: Option<String>,
+    /// Called with the real DOM element once it is created; ignored by the string renderer.
+    pub on_create: Option<OnCreate>,
+    /// Event listeners attached to the DOM element; ignored by the string renderer.
+    pub listeners: Vec<Listener>,
+    /// The class names set through `class_list`, kept in order so the diff can add and
+    /// remove single classes. `props["class"]` always holds them joined for output.
+    pub class_list: Option<Vec<String>>,
+}
+
+/// Newtype over the `on_create` callback so `Element` can keep deriving `Debug` and `Clone`.
+#[derive(Clone)]
+pub struct OnCreate(Rc<dyn Fn(&DomElement)>);
+
+impl Debug for OnCreate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("OnCreate(..)")
+    }
+}
+
+/// An event listener: the DOM event name (`"click"`, `"input"`, ...) and its handler.
+#[derive(Debug, Clone)]
+pub struct Listener {
+    pub event: String,
+    pub handler: EventHandler,
+}
+
+/// Newtype over an event handler, for the same reason as `OnCreate`.
+#[derive(Clone)]
+pub struct EventHandler(pub(crate) Rc<dyn Fn(&Event)>);
+
+impl Debug for EventHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EventHandler(..)")
+    }
+}
+
+/// Builder-style helpers, so trees can be written as chains instead of struct literals.
+impl Element {
+    /// Starts an element with no attributes or children. With `set_source_locations`
+    /// turned on, the caller's file and line are recorded in `data-rr-source`.
+    #[track_caller]
+    pub fn new(tag_name: impl Into<String>) -> Self {
+        let mut element = Element {
+            tag_name: tag_name.into(),
+            ..Default::default()
+        };
+        if let Some(location) = source::source_location(std::panic::Location::caller()) {
+            element.props.insert(SOURCE_ATTRIBUTE, location);
+        }
+        element
+    }
+
+    /// Like `new`, but with a typed tag name that can't be misspelled.
+    #[track_caller]
+    pub fn with_tag(tag: Tag) -> Self {
+        Element::new(tag)
+    }
+
+    pub fn attr(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let key = key.into();
+        if key == "class" {
+            self.class_list = None;
+        }
+        self.props.insert(key, value.into());
+        self
+    }
+
+    /// Sets the classes as a list instead of a string. When both the old and the new
+    /// element use a list, updates add and remove single classes through `classList`,
+    /// so classes added to the DOM element by other code are kept.
+    pub fn class_list<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        let mut list: Vec<String> = Vec::new();
+        for name in names {
+            let name = name.into();
+            if !name.is_empty() && !list.contains(&name) {
+                list.push(name);
+            }
+        }
+        self.props.insert("class", list.join(" "));
+        self.class_list = Some(list);
+        self
+    }
+
+    /// Adds every `(name, value)` pair when `condition` holds and none of them otherwise,
+    /// for attributes that only make sense together, like a link's `target` and `rel`.
+    pub fn attrs_if(mut self, condition: bool, attrs: &[(&str, &str)]) -> Self {
+        if condition {
+            for (name, value) in attrs {
+                self = self.attr(*name, *value);
+            }
+        }
+        self
+    }
+
+    /// Adds the block class and the modifier class of `variant` (see `VariantClass`) in
+    /// front of any classes already set.
+    pub fn variant<V: VariantClass>(self, variant: &V) -> Self {
+        let own = format!("{} {}", V::BLOCK, variant.variant_class());
+        let existing = self.props.get("class").cloned();
+        self.attr("class", merge_class(existing.as_deref(), &own))
+    }
+
+    /// Marks the element's children for `RenderOptions::max_children`.
+    pub fn paginate(self) -> Self {
+        self.attr(PAGINATE_ATTRIBUTE, "")
+    }
+
+    /// Sets the `class` attribute from conditional class names; see `classes`.
+    pub fn classes(self, pairs: &[(&str, bool)]) -> Self {
+        self.attr("class", classes(pairs))
+    }
+
+    /// Sets `class` from every source a component usually has: its `base` classes, the
+    /// enabled `conditional` ones and the `class` its parent passed in, in that order and
+    /// each name only once (see `classes` and `merge_class`).
+    pub fn merged_classes(self, base: &str, conditional: &[(&str, bool)], incoming: Option<&str>) -> Self {
+        let own = format!("{} {}", base, classes(conditional));
+        self.attr("class", merge_class(incoming, &own))
+    }
+
+    /// Sets the `style` attribute from a typed `Style`.
+    pub fn style_struct(self, style: Style) -> Self {
+        self.attr("style", style.to_css())
+    }
+
+    /// Like `attr`, but accepts React-style names such as `className` or `htmlFor` and
+    /// stores them under their HTML attribute name (see `html_attr_name`).
+    pub fn prop(self, name: &str, value: impl Into<String>) -> Self {
+        let name = html_attr_name(name).into_owned();
+        self.attr(name, value)
+    }
+
+    pub fn child(mut self, child: impl IntoNode) -> Self {
+        self.children.push(child.into_node());
+        self
+    }
+
+    /// Appends every item of `children`, e.g. `.children_from(items.iter().map(render_item))`.
+    pub fn children_from<I>(mut self, children: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: IntoNode,
+    {
+        self.children.extend(children.into_iter().map(IntoNode::into_node));
+        self
+    }
+
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// An escape hatch for imperative setup, e.g. handing the element to a chart library.
+    /// The callback runs once the element is in the DOM and its children are rendered.
+    pub fn on_create(mut self, callback: impl Fn(&DomElement) + 'static) -> Self {
+        self.on_create = Some(OnCreate(Rc::new(callback)));
+        self
+    }
+
+    /// Listens for `event` on the DOM element. Handlers are rebound on every update, so
+    /// they can freely capture the state they were built from.
+    pub fn on(mut self, event: impl Into<String>, handler: impl Fn(&Event) + 'static) -> Self {
+        self.listeners.push(Listener {
+            event: event.into(),
+            handler: EventHandler(Rc::new(handler)),
+        });
+        self
+    }
+
+    /// Like `on`, for handlers that can fail: an `Err` goes to the handler set with
+    /// `set_event_error_handler` (or the console). Unlike a panic, this also gets
+    /// reported in wasm builds, which abort on panic.
+    pub fn on_fallible<E: std::fmt::Display>(
+        self,
+        event: impl Into<String>,
+        handler: impl Fn(&Event) -> Result<(), E> + 'static,
+    ) -> Self {
+        let event = event.into();
+        let name = event.clone();
+        self.on(event, move |dom_event| event::report_failure(&name, handler(dom_event)))
+    }
+
+    pub fn on_click(self, handler: impl Fn(&Event) + 'static) -> Self {
+        self.on("click", handler)
+    }
+
+    pub fn on_input(self, handler: impl Fn(&Event) + 'static) -> Self {
+        self.on("input", handler)
+    }
+
+    /// Two-way binds the field's value to `signal`, like Vue's `v-model`: the field
+    /// starts out with the signal's value, typing updates the signal, and setting the
+    /// signal updates the field. An existing `on_create` callback still runs first.
+    /// Once the field has been removed from the page, the next change of the signal
+    /// drops its subscription instead of writing to the field.
+    pub fn bind(self, signal: &Signal<String>) -> Self {
+        let previous = self.on_create.clone();
+        let on_input = signal.clone();
+        let on_change = signal.clone();
+        self.attr("value", signal.get())
+            .on_input(move |event| {
+                if let Some(value) = target_value(event) {
+                    on_input.set(value);
+                }
+            })
+            .on_create(move |dom_element| {
+                if let Some(OnCreate(callback)) = &previous {
+                    callback(dom_element);
+                }
+                // Textareas and selects ignore the `value` attribute.
+                event::set_field_value(dom_element, &on_change.get());
+                let field = dom_element.clone();
+                let subscription = Rc::new(Cell::new(None));
+                let own_subscription = subscription.clone();
+                let signal = on_change.clone();
+                let id = on_change.subscribe(move |value: &String| {
+                    if !field.is_connected() {
+                        if let Some(id) = own_subscription.take() {
+                            signal.unsubscribe(id);
+                        }
+                        return;
+                    }
+                    if event::field_value(&field).as_ref() != Some(value) {
+                        event::set_field_value(&field, value);
+                    }
+                });
+                subscription.set(Some(id));
+            })
+    }
+
+    /// Like `on_input`, but `handler` only runs once the user has stopped typing for
+    /// `ms` milliseconds, with the last event. See `debounce`.
+    pub fn on_input_debounced(self, ms: i32, handler: impl Fn(&Event) + 'static) -> Self {
+        self.on("input", event::debounce(ms, handler))
+    }
+}
+
+/// Builds a class list from `(name, enabled)` pairs in a single pass, keeping only the
+/// enabled names: `classes(&[("btn", true), ("active", is_active)])`.
+pub fn classes(pairs: &[(&str, bool)]) -> String {
+    let enabled = pairs.iter().filter(|(_, on)| *on);
+    let mut class = String::with_capacity(enabled.clone().map(|(name, _)| name.len() + 1).sum());
+    for (name, _) in enabled {
+        if !class.is_empty() {
+            class.push(' ');
+        }
+        class.push_str(name);
+    }
+    class
+}
+
+/// Combines the `class` a parent passed in with a component's own classes: the own
+/// classes come first, then the parent's, each name only once. Whitespace between
+/// names is normalized, so `merge_class(Some("btn  wide"), "btn")` is `"btn wide"`.
+pub fn merge_class(parent: Option<&str>, own: &str) -> String {
+    let mut merged: Vec<&str> = Vec::new();
+    for name in own.split_whitespace().chain(parent.unwrap_or("").split_whitespace()) {
+        if !merged.contains(&name) {
+            merged.push(name);
+        }
+    }
+    merged.join(" ")
+}
+
+/// React prop names whose HTML attribute is spelled differently.
+const REACT_PROP_NAMES: &[(&str, &str)] = &[
+    ("acceptCharset", "accept-charset"),
+    ("autoComplete", "autocomplete"),
+    ("autoFocus", "autofocus"),
+    ("className", "class"),
+    ("colSpan", "colspan"),
+    ("contentEditable", "contenteditable"),
+    ("crossOrigin", "crossorigin"),
+    ("dateTime", "datetime"),
+    ("encType", "enctype"),
+    ("formAction", "formaction"),
+    ("htmlFor", "for"),
+    ("httpEquiv", "http-equiv"),
+    ("maxLength", "maxlength"),
+    ("minLength", "minlength"),
+    ("noValidate", "novalidate"),
+    ("readOnly", "readonly"),
+    ("rowSpan", "rowspan"),
+    ("spellCheck", "spellcheck"),
+    ("srcSet", "srcset"),
+    ("tabIndex", "tabindex"),
+];
+
+/// Maps a React-style prop name to the HTML attribute it stands for: the well-known
+/// renames (`className` -> `class`, `htmlFor` -> `for`, `tabIndex` -> `tabindex`, ...)
+/// and camelCase `aria`/`data` props to kebab-case (`ariaLabel` -> `aria-label`).
+/// Anything else, including SVG attributes like `viewBox`, is returned unchanged.
+pub fn html_attr_name(name: &str) -> Cow<'_, str> {
+    if let Some((_, html)) = REACT_PROP_NAMES.iter().find(|(react, _)| *react == name) {
+        return Cow::Borrowed(html);
+    }
+    let prefixed = ["aria", "data"].iter().any(|prefix| {
+        name.strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_uppercase()))
+    });
+    if !prefixed {
+        return Cow::Borrowed(name);
+    }
+    let mut kebab = String::with_capacity(name.len() + 2);
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            kebab.push('-');
+            kebab.push(c.to_ascii_lowercase());
+        } else {
+            kebab.push(c);
+        }
+    }
+    Cow::Owned(kebab)
 }
 
 /// The "newtype" pattern: a struct that wraps `Rc<dyn Component>`.
 /// Because `VComponent` is a type local to our crate, we can implement foreign
 /// traits like `Clone` for it, satisfying Rust's orphan rule.
+/// Next to the component it carries an optional reconciliation `key` and the state of
+/// the component's hooks, which all clones share.
 #[derive(Debug)]
-pub struct VComponent(Rc<dyn Component>);
+pub struct VComponent {
+    component: Rc<dyn Component>,
+    /// The same component, type-erased differently so `with_props` can get it back.
+    props: Rc<dyn Any>,
+    /// `std::any::type_name` of the component, which render counts are keyed by.
+    type_name: &'static str,
+    pub key: Option<String>,
+    hooks: Rc<Hooks>,
+    /// What the component rendered when its tree was committed (see `commit`), which
+    /// `render` returns from then on.
+    output: Option<Rc<Node>>,
+}
+
+impl VComponent {
+    pub fn new<C: Component + 'static>(component: C) -> Self {
+        let component = Rc::new(component);
+        VComponent {
+            props: component.clone(),
+            component,
+            type_name: std::any::type_name::<C>(),
+            key: None,
+            hooks: Rc::default(),
+            output: None,
+        }
+    }
+
+    /// A copy of this component whose props (the fields of the component value) have
+    /// been changed by `update`, e.g. to merge defaults into a child before rendering
+    /// it. The copy keeps the key but gets hook state of its own, so both can be
+    /// rendered side by side; `self` is left unchanged.
+    ///
+    /// Returns `None` if the wrapped component is not a `C`.
+    pub fn with_props<C: Component + Clone + 'static>(
+        &self,
+        update: impl FnOnce(&mut C),
+    ) -> Option<Self> {
+        let mut props = self.props.downcast_ref::<C>()?.clone();
+        update(&mut props);
+        Some(VComponent {
+            key: self.key.clone(),
+            ..VComponent::new(props)
+        })
+    }
+
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// See `Component::debug_name`.
+    pub fn debug_name(&self) -> String {
+        self.component.debug_name()
+    }
+
+    /// See `Component::debug_props`.
+    pub fn debug_props(&self) -> String {
+        self.component.debug_props()
+    }
+
+    /// See `Component::hydration_priority`.
+    pub fn hydration_priority(&self) -> Option<HydrationPriority> {
+        self.component.hydration_priority()
+    }
+
+    /// Renders the wrapped component, with its hooks bound to this instance. A
+    /// component taken from a mounted tree returns what it rendered when it was
+    /// mounted (or last updated) instead, i.e. what is on screen.
+    pub fn render(&self) -> Node {
+        if let Some(output) = &self.output {
+            return Node::clone(output);
+        }
+        self.render_with(&self.hooks, &[])
+    }
+
+    /// Renders the wrapped component at `position` with `hooks` as its hook state.
+    pub(crate) fn render_with(&self, hooks: &Rc<Hooks>, position: &[Segment]) -> Node {
+        render_with_hooks(hooks, position, || self.component.render())
+    }
+}
 
 impl Clone for VComponent {
     fn clone(&self) -> Self {
         // To clone our newtype, we call the cloning method defined on our component trait.
-        // `self.0` accesses the inner `Rc<dyn Component>`.
-        VComponent(self.0.clone_rc())
+        VComponent {
+            component: self.component.clone_rc(),
+            props: self.props.clone(),
+            type_name: self.type_name,
+            key: self.key.clone(),
+            hooks: self.hooks.clone(),
+            output: self.output.clone(),
+        }
     }
 }
 
+/// Wraps a component so it can be placed in a tree, e.g. `component(MyComp).key("row-3")`.
+pub fn component(component: impl Component + 'static) -> VComponent {
+    VComponent::new(component)
+}
+
 /// The contract for any reusable, renderable component.
 /// It must be clonable itself, and provide a way to be cloned into a smart pointer.
 pub trait Component: Debug {
     fn render(&self) -> Node;
     fn clone_rc(&self) -> Rc<dyn Component>;
+
+    /// The label inspectors show for this component. Defaults to the type name without
+    /// module paths, including those of generic arguments, e.g. `App` or `Table<Row>`.
+    fn debug_name(&self) -> String {
+        short_type_name(std::any::type_name::<Self>())
+    }
+
+    /// A one-line summary of the component's props for inspectors. Defaults to its
+    /// `Debug` output.
+    fn debug_props(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    /// How urgently the client should hydrate this component's interactive elements,
+    /// recorded in the manifest of `render_node_to_string_with_manifest`. `None`, the
+    /// default, inherits the priority of the closest component above that has one.
+    fn hydration_priority(&self) -> Option<HydrationPriority> {
+        None
+    }
+}
+
+/// `type_name` with the module path of every type in it removed:
+/// `app::Table<app::rows::Row>` becomes `Table<Row>`.
+fn short_type_name(type_name: &str) -> String {
+    let mut short = String::with_capacity(type_name.len());
+    let mut path = String::new();
+    for c in type_name.chars() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            path.push(c);
+            continue;
+        }
+        short.push_str(path.rsplit("::").next().unwrap_or(&path));
+        path.clear();
+        short.push(c);
+    }
+    short.push_str(path.rsplit("::").next().unwrap_or(&path));
+    short
+}
+
+/// Markup inserted verbatim instead of being built from VDOM nodes, the equivalent of
+/// React's `dangerouslySetInnerHTML`. Sanitization is opt-in per node.
+#[derive(Debug, Clone)]
+pub struct RawHtml {
+    pub html: String,
+    pub sanitize: Option<SanitizePolicy>,
+}
+
+impl RawHtml {
+    pub fn new(html: impl Into<String>) -> Self {
+        RawHtml { html: html.into(), sanitize: None }
+    }
+
+    /// Runs the markup through `sanitize_html` with `policy` before it is inserted.
+    pub fn sanitized(mut self, policy: SanitizePolicy) -> Self {
+        self.sanitize = Some(policy);
+        self
+    }
+
+    /// The markup that actually gets inserted, after sanitization if it was requested.
+    pub fn to_html(&self) -> Cow<'_, str> {
+        match &self.sanitize {
+            Some(policy) => Cow::Owned(sanitize_html(&self.html, policy)),
+            None => Cow::Borrowed(&self.html),
+        }
+    }
+}
+
+/// A subtree that is only built when a renderer reaches it, so content behind a
+/// condition costs nothing until it is actually shown.
+#[derive(Clone)]
+pub struct Lazy(Rc<dyn Fn() -> Node>);
+
+impl Lazy {
+    pub fn new(build: impl Fn() -> Node + 'static) -> Self {
+        Lazy(Rc::new(build))
+    }
+
+    /// Builds the subtree. Every call runs the closure again.
+    pub fn render(&self) -> Node {
+        (self.0)()
+    }
+}
+
+impl Debug for Lazy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Lazy(..)")
+    }
+}
+
+/// A component whose output is only available after awaiting something, such as a
+/// data fetch. Synchronous renderers show `fallback` in its place;
+/// `render_node_to_string_async` awaits the real content.
+pub trait AsyncComponent: Debug {
+    fn render(&self) -> Pin<Box<dyn Future<Output = Node>>>;
+
+    /// What to show while the content is not available yet. Empty by default.
+    fn fallback(&self) -> Node {
+        Node::Fragment(Fragment::new())
+    }
+}
+
+/// Wraps an `AsyncComponent` so it can live in a `Node`, like `VComponent` does for
+/// synchronous components.
+#[derive(Debug, Clone)]
+pub struct VAsyncComponent(Rc<dyn AsyncComponent>);
+
+impl VAsyncComponent {
+    pub fn new(component: impl AsyncComponent + 'static) -> Self {
+        VAsyncComponent(Rc::new(component))
+    }
+
+    pub fn render(&self) -> Pin<Box<dyn Future<Output = Node>>> {
+        self.0.render()
+    }
+
+    pub fn fallback(&self) -> Node {
+        self.0.fallback()
+    }
+}
+
+/// A group of sibling nodes without a wrapping element. A key makes the whole group
+/// move as one unit when a keyed list is reordered.
+#[derive(Debug, Clone, Default)]
+pub struct Fragment {
+    pub children: Vec<Node>,
+    pub key: Option<String>,
+}
+
+impl Fragment {
+    pub fn new() -> Self {
+        Fragment::default()
+    }
+
+    pub fn child(mut self, child: impl IntoNode) -> Self {
+        self.children.push(child.into_node());
+        self
+    }
+
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+}
+
+/// Interleaves `separator` between `items` (but not after the last one) and returns
+/// them as a fragment, e.g. for `a · b · c` style lists.
+pub fn join_nodes(items: Vec<Node>, separator: Node) -> Node {
+    let mut children = Vec::with_capacity((items.len() * 2).saturating_sub(1));
+    for (i, item) in items.into_iter().enumerate() {
+        if i > 0 {
+            children.push(separator.clone());
+        }
+        children.push(item);
+    }
+    Node::Fragment(Fragment { children, key: None })
+}
+
+/// Renders each of `items` with `render` and returns the results as a fragment.
+pub fn list<T>(items: &[T], render: impl Fn(&T) -> Node) -> Node {
+    list_indexed(items, |_, item| render(item))
+}
+
+/// Like `list`, but `render` also gets the item's index, e.g. to derive a key or to
+/// alternate row classes.
+pub fn list_indexed<T>(items: &[T], render: impl Fn(usize, &T) -> Node) -> Node {
+    Node::Fragment(Fragment {
+        children: items.iter().enumerate().map(|(i, item)| render(i, item)).collect(),
+        key: None,
+    })
 }
 
 // Our Virtual DOM Node enum. It can now be cloned efficiently thanks to our VComponent newtype.
@@ -41,6 +735,92 @@ pub enum Node {
     Element(Element),
     Text(String),
     Component(VComponent),
+    RawHtml(RawHtml),
+    Lazy(Lazy),
+    Fragment(Fragment),
+    Async(VAsyncComponent),
+    /// Rendered only in the browser. The string renderer writes a placeholder comment
+    /// instead, and `hydrate` renders the content in its place.
+    ClientOnly(Box<Node>),
+    /// Rendered in full by the string renderer, but `hydrate` leaves it static: its
+    /// `on_create` callbacks are not run.
+    ServerOnly(Box<Node>),
+}
+
+impl Node {
+    /// The reconciliation key of an element or component, if it has one.
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            Node::Element(element) => element.key.as_deref(),
+            Node::Component(v_component) => v_component.key.as_deref(),
+            Node::Fragment(fragment) => fragment.key.as_deref(),
+            Node::Text(_)
+            | Node::RawHtml(_)
+            | Node::Lazy(_)
+            | Node::Async(_)
+            | Node::ClientOnly(_)
+            | Node::ServerOnly(_) => None,
+        }
+    }
+}
+
+/// Anything that can be placed in an element's children.
+pub trait IntoNode {
+    fn into_node(self) -> Node;
+}
+
+impl IntoNode for Node {
+    fn into_node(self) -> Node {
+        self
+    }
+}
+
+impl IntoNode for Element {
+    fn into_node(self) -> Node {
+        Node::Element(self)
+    }
+}
+
+impl IntoNode for VComponent {
+    fn into_node(self) -> Node {
+        Node::Component(self)
+    }
+}
+
+impl IntoNode for RawHtml {
+    fn into_node(self) -> Node {
+        Node::RawHtml(self)
+    }
+}
+
+impl IntoNode for Fragment {
+    fn into_node(self) -> Node {
+        Node::Fragment(self)
+    }
+}
+
+impl IntoNode for Lazy {
+    fn into_node(self) -> Node {
+        Node::Lazy(self)
+    }
+}
+
+impl IntoNode for VAsyncComponent {
+    fn into_node(self) -> Node {
+        Node::Async(self)
+    }
+}
+
+impl IntoNode for String {
+    fn into_node(self) -> Node {
+        Node::Text(self)
+    }
+}
+
+impl IntoNode for &str {
+    fn into_node(self) -> Node {
+        Node::Text(self.to_string())
+    }
 }
 
 /// Our first component. We `derive(Clone)` so we can call `self.clone()` inside `clone_rc`.
@@ -53,23 +833,26 @@ impl Component for App {
         Node::Element(Element {
             tag_name: "div".to_string(),
             props: {
-                let mut props = HashMap::new();
-                props.insert("class".to_string(), "app-container".to_string());
-                props.insert("data-rendered-by".to_string(), "rusty-react".to_string());
+                let mut props = Props::new();
+                props.insert("class", "app-container");
+                props.insert("data-rendered-by", "rusty-react");
                 props
             },
             children: vec![
                 Node::Element(Element {
                     tag_name: "h1".to_string(),
-                    props: HashMap::new(),
+                    props: Props::new(),
                     children: vec![Node::Text("Hello from a Rusty Component!".to_string())],
+                    ..Default::default()
                 }),
                 Node::Element(Element {
                     tag_name: "p".to_string(),
-                    props: HashMap::new(),
+                    props: Props::new(),
                     children: vec![Node::Text("This was rendered via a component trait.".to_string())],
+                    ..Default::default()
                 }),
             ],
+            ..Default::default()
         })
     }
 
@@ -80,8 +863,21 @@ impl Component for App {
 }
 
 
-/// The private, recursive function that renders VDOM into real DOM nodes.
-fn render_node_to_dom(v_node: &Node, document: &Document, parent: &DomNode) {
+/// The recursive function that renders VDOM into real DOM nodes, appending them to `parent`.
+///
+/// `<svg>` and `<math>` subtrees are created in the SVG and MathML namespaces, as the
+/// HTML parser would, and so is anything appended to an element already in one of them.
+pub fn render_node_to_dom(v_node: &Node, document: &Document, parent: &DomNode) {
+    render_node_to_dom_in(v_node, document, parent, inherited_namespace(parent));
+}
+
+/// `render_node_to_dom` with the namespace of the surrounding element already known.
+pub(crate) fn render_node_to_dom_in(
+    v_node: &Node,
+    document: &Document,
+    parent: &DomNode,
+    namespace: Option<&'static str>,
+) {
     match v_node {
         Node::Text(text) => {
             let text_node = document.create_text_node(text);
@@ -90,9 +886,12 @@ fn render_node_to_dom(v_node: &Node, document: &Document, parent: &DomNode) {
                 .expect("Failed to append text node");
         }
         Node::Element(element) => {
-            let dom_element = document
-                .create_element(&element.tag_name)
-                .expect("Failed to create element");
+            let namespace = element_namespace(&element.tag_name, namespace);
+            let dom_element = match namespace {
+                Some(namespace) => document.create_element_ns(Some(namespace), &element.tag_name),
+                None => document.create_element(&element.tag_name),
+            }
+            .expect("Failed to create element");
 
             for (key, value) in &element.props {
                 dom_element
@@ -104,38 +903,199 @@ fn render_node_to_dom(v_node: &Node, document: &Document, parent: &DomNode) {
                 .append_child(&dom_element)
                 .expect("Failed to append element");
 
-            for child in &element.children {
-                render_node_to_dom(child, document, &dom_element);
+            let inner = children_namespace(&element.tag_name, namespace);
+            render_children(&element.children, document, &dom_element, inner);
+            event::bind_listeners(&dom_element, &element.listeners);
+
+            if let Some(OnCreate(callback)) = &element.on_create {
+                callback(&dom_element);
             }
         }
         Node::Component(v_component) => {
-            // Access the inner component via `.0` and render it.
-            let rendered_node = v_component.0.render();
-            render_node_to_dom(&rendered_node, document, parent);
+            let rendered_node = v_component.render();
+            render_node_to_dom_in(&rendered_node, document, parent, namespace);
+        }
+        Node::RawHtml(raw) => {
+            // A detached <template> parses the markup without running scripts or loading
+            // resources, then we move its content into place.
+            let template: HtmlTemplateElement = document
+                .create_element("template")
+                .expect("Failed to create template")
+                .unchecked_into();
+            template.set_inner_html(&raw.to_html());
+            parent
+                .append_child(&template.content())
+                .expect("Failed to append raw HTML");
+        }
+        Node::Lazy(lazy) => render_node_to_dom_in(&lazy.render(), document, parent, namespace),
+        Node::Async(v_async) => render_node_to_dom_in(&v_async.fallback(), document, parent, namespace),
+        Node::ClientOnly(inner) | Node::ServerOnly(inner) => {
+            render_node_to_dom_in(inner, document, parent, namespace)
+        }
+        Node::Fragment(fragment) => {
+            render_children(&fragment.children, document, parent, namespace)
         }
     }
 }
 
-/// The public API function exported to JavaScript.
-#[wasm_bindgen]
-pub fn render(mount_point_id: String) {
-    let root_component = App;
-    // We wrap our component instance first in an `Rc`, then in our `VComponent` newtype.
-    let app_vdom = Node::Component(VComponent(Rc::new(root_component)));
+const MATHML_NAMESPACE: &str = "http://www.w3.org/1998/Math/MathML";
+
+/// The namespace a child of `parent` is created in when it isn't an `<svg>` or
+/// `<math>` root itself: `None` for HTML, including inside an SVG `<foreignObject>`.
+pub(crate) fn inherited_namespace(parent: &DomNode) -> Option<&'static str> {
+    let element = parent.dyn_ref::<DomElement>()?;
+    let namespace = element.namespace_uri()?;
+    let known = [SVG_NAMESPACE, MATHML_NAMESPACE]
+        .into_iter()
+        .find(|known| *known == namespace);
+    children_namespace(&element.local_name(), known)
+}
+
+fn element_namespace(tag_name: &str, inherited: Option<&'static str>) -> Option<&'static str> {
+    if tag_name.eq_ignore_ascii_case("svg") {
+        Some(SVG_NAMESPACE)
+    } else if tag_name.eq_ignore_ascii_case("math") {
+        Some(MATHML_NAMESPACE)
+    } else {
+        inherited
+    }
+}
+
+fn children_namespace(tag_name: &str, namespace: Option<&'static str>) -> Option<&'static str> {
+    if namespace == Some(SVG_NAMESPACE) && tag_name == "foreignObject" {
+        None
+    } else {
+        namespace
+    }
+}
+
+/// Lists shorter than this are built node by node; fingerprinting them isn't worth it.
+const CLONE_MIN_CHILDREN: usize = 3;
+
+/// Appends `children` to `parent`. In longer lists, a static element that is identical
+/// to the element before it (same fingerprint) is deep-cloned from the DOM node just
+/// built instead of being created again, which makes repeated rows much cheaper.
+fn render_children(
+    children: &[Node],
+    document: &Document,
+    parent: &DomNode,
+    namespace: Option<&'static str>,
+) {
+    let children = coalesce_text(children);
+    let mut previous: Option<(u64, DomNode)> = None;
+    for child in children.iter() {
+        let clonable = children.len() >= CLONE_MIN_CHILDREN
+            && matches!(child, Node::Element(_))
+            && is_static(child);
+        if !clonable {
+            render_node_to_dom_in(child, document, parent, namespace);
+            previous = None;
+            continue;
+        }
+
+        let fingerprint = child.fingerprint();
+        let node = match &previous {
+            Some((last, prototype)) if *last == fingerprint => {
+                let copy = prototype.clone_node_with_deep(true).expect("Failed to clone node");
+                parent.append_child(&copy).expect("Failed to append cloned node")
+            }
+            _ => {
+                render_node_to_dom_in(child, document, parent, namespace);
+                parent.last_child().expect("Element was just appended")
+            }
+        };
+        previous = Some((fingerprint, node));
+    }
+}
 
-    let window: Window = web_sys::window().expect("no global `window` exists");
-    let document: Document = window.document().expect("should have a document on window");
+/// Whether the subtree turns into DOM without running any user code, so a copy of
+/// its output is as good as rendering it again.
+fn is_static(node: &Node) -> bool {
+    match node {
+        Node::Element(element) => {
+            element.on_create.is_none()
+                && element.listeners.is_empty()
+                && element.children.iter().all(is_static)
+        }
+        Node::Fragment(fragment) => fragment.children.iter().all(is_static),
+        Node::Text(_) | Node::RawHtml(_) => true,
+        Node::ClientOnly(inner) | Node::ServerOnly(inner) => is_static(inner),
+        Node::Component(_) | Node::Lazy(_) | Node::Async(_) => false,
+    }
+}
 
-    let mount_element: DomElement = document
-        .get_element_by_id(&mount_point_id)
-        .expect(&format!(
-            "Mount point with id '{}' not found",
-            mount_point_id
-        ));
+/// Merges runs of adjacent `Node::Text` siblings, so that the DOM renderer creates one
+/// text node per run. Only direct text children are merged; text produced by a
+/// component or fragment stays separate. The diff applies the same rule to keep its
+/// child indices in line with the DOM.
+pub(crate) fn coalesce_text(children: &[Node]) -> Cow<'_, [Node]> {
+    let adjacent = children
+        .windows(2)
+        .any(|pair| matches!(pair, [Node::Text(_), Node::Text(_)]));
+    if !adjacent {
+        return Cow::Borrowed(children);
+    }
+    let mut merged: Vec<Node> = Vec::with_capacity(children.len());
+    for child in children {
+        match (merged.last_mut(), child) {
+            (Some(Node::Text(run)), Node::Text(text)) => run.push_str(text),
+            _ => merged.push(child.clone()),
+        }
+    }
+    Cow::Owned(merged)
+}
+
+/// Walks an existing DOM subtree and rebuilds the equivalent VDOM: attributes become
+/// props and text nodes become `Node::Text`. Components cannot be recovered, only the
+/// markup they rendered. Nodes with no VDOM counterpart (comments, processing
+/// instructions) are skipped, or become empty text if passed in as the root.
+pub fn dom_to_vdom(node: &DomNode) -> Node {
+    dom_node_to_vdom(node).unwrap_or_else(|| Node::Text(String::new()))
+}
+
+fn dom_node_to_vdom(node: &DomNode) -> Option<Node> {
+    match node.node_type() {
+        DomNode::TEXT_NODE => Some(Node::Text(node.text_content().unwrap_or_default())),
+        DomNode::ELEMENT_NODE => {
+            let dom_element: &DomElement = node.unchecked_ref();
+
+            let attributes = dom_element.attributes();
+            let mut props = Props::new();
+            for i in 0..attributes.length() {
+                if let Some(attr) = attributes.item(i) {
+                    props.insert(attr.name(), attr.value());
+                }
+            }
+
+            let child_nodes = node.child_nodes();
+            let children = (0..child_nodes.length())
+                .filter_map(|i| child_nodes.item(i))
+                .filter_map(|child| dom_node_to_vdom(&child))
+                .collect();
+
+            Some(Node::Element(Element {
+                tag_name: dom_element.local_name(),
+                props,
+                children,
+                ..Default::default()
+            }))
+        }
+        _ => None,
+    }
+}
 
-    mount_element.set_inner_html("");
+/// The public API function exported to JavaScript. Returns the app's root element, the
+/// first child of the mount point, for imperative work such as focusing or scrolling.
+#[wasm_bindgen]
+pub fn render(mount_point_id: String) -> DomElement {
+    let root_component = App;
+    // `VComponent::new` wraps our component instance in an `Rc` inside the newtype.
+    let app_vdom = Node::Component(VComponent::new(root_component));
 
-    render_node_to_dom(&app_vdom, &document, &mount_element);
+    mount(app_vdom, &mount_point_id)
+        .container()
+        .first_element_child()
+        .expect("App renders a root element")
 }
 
 
@@ -143,29 +1103,256 @@ pub fn render(mount_point_id: String) {
 // --- TEST SECTION: THIS IS SUPER IMPORTANT FOR UNDERSTANDING ----------------------
 // ----------------------------------------------------------------------------------
 
+/// Options for the string renderer. `RenderOptions::default()` produces plain HTML.
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// Wrap each component's output in `<!--$-->` ... `<!--/$-->` comments, like React's
+    /// server renderer, so hydration can find component boundaries even when a
+    /// component renders several sibling nodes.
+    pub hydration_markers: bool,
+    /// How tag and attribute names are cased. Defaults to `CasePolicy::Preserve`.
+    pub case: CasePolicy,
+    /// Render at most this many children of elements marked with `Element::paginate`,
+    /// followed by a `<rr-more>` marker saying how many were left out. Fragments count
+    /// as their children. Useful for previews of very long lists.
+    pub max_children: Option<usize>,
+    /// What goes between two components that are adjacent siblings. Defaults to
+    /// `ComponentSpacing::None`.
+    pub component_spacing: ComponentSpacing,
+}
+
+/// Whitespace the string renderer puts between adjacent sibling components, for
+/// components that render inline content such as badges or links inside a sentence.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ComponentSpacing {
+    /// Nothing: the output of one component directly follows the other's.
+    #[default]
+    None,
+    /// A single space. The DOM renderer doesn't add it, so don't combine this with
+    /// hydration.
+    Space,
+}
+
+/// The attribute `Element::paginate` sets, which makes `RenderOptions::max_children`
+/// apply to the element.
+pub const PAGINATE_ATTRIBUTE: &str = "data-rr-paginate";
+
+/// How the string renderer treats the case of tag and attribute names.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CasePolicy {
+    /// Names are written exactly as they appear in the VDOM.
+    #[default]
+    Preserve,
+    /// HTML tag and attribute names are lowercased (`Div` becomes `div`), while names
+    /// inside `<svg>` and `<math>` keep their case, since SVG and MathML have
+    /// case-sensitive names such as `viewBox` and `foreignObject`.
+    LowercaseHtml,
+}
+
 /// A recursive function that renders our VDOM to an HTML String.
-fn render_node_to_string(v_node: &Node) -> String {
+///
+/// A fragment, including one at the top level, renders as its children one after
+/// another with nothing around them, so a page can be a list of sections.
+pub fn render_node_to_string(v_node: &Node) -> String {
+    render_node_to_string_with(v_node, &RenderOptions::default())
+}
+
+/// Renders our VDOM to an HTML String, honouring `options`.
+pub fn render_node_to_string_with(v_node: &Node, options: &RenderOptions) -> String {
+    let mut bytes = Vec::new();
+    render_node_to_writer_with(v_node, &mut bytes, options)
+        .expect("writing to a Vec<u8> cannot fail");
+    String::from_utf8(bytes).expect("the HTML writer only emits UTF-8")
+}
+
+/// Renders our VDOM directly to UTF-8 bytes, ready to be sent as a server response.
+pub fn render_node_to_bytes(v_node: &Node) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    render_node_to_writer(v_node, &mut bytes).expect("writing to a Vec<u8> cannot fail");
+    bytes
+}
+
+/// Streams the HTML for `v_node` into any writer, so a server can write into a socket
+/// or response body without building a `String`.
+pub fn render_node_to_writer(v_node: &Node, writer: &mut impl Write) -> io::Result<()> {
+    render_node_to_writer_with(v_node, writer, &RenderOptions::default())
+}
+
+/// What the string renderer writes in place of a `Node::ClientOnly` subtree.
+pub(crate) const CLIENT_ONLY_PLACEHOLDER: &str = "<!--client-only-->";
+
+/// The core of the string renderer that every `render_node_to_*` function goes through.
+pub fn render_node_to_writer_with(
+    v_node: &Node,
+    writer: &mut impl Write,
+    options: &RenderOptions,
+) -> io::Result<()> {
+    // Committing gives components their positions, which `use_id` builds ids from.
+    write_node(&commit_on_server(v_node), writer, options, Context::default())
+}
+
+/// Where in the document the string renderer is.
+#[derive(Clone, Copy, Default)]
+struct Context<'a> {
+    /// Inside `<svg>` and `<math>`, where names are case-sensitive.
+    foreign: bool,
+    /// The tag of the enclosing `<script>` or `<style>`, whose text is written as is.
+    raw_text: Option<&'a str>,
+}
+
+fn write_node(
+    v_node: &Node,
+    writer: &mut impl Write,
+    options: &RenderOptions,
+    context: Context<'_>,
+) -> io::Result<()> {
+    let foreign = context.foreign;
     match v_node {
-        Node::Text(text) => text.clone(),
+        Node::Text(text) => match context.raw_text {
+            Some(tag_name) => writer.write_all(escape_raw_text(text, tag_name).as_bytes()),
+            None => writer.write_all(escape_text(text).as_bytes()),
+        },
         Node::Element(element) => {
-            let mut props_string = String::new();
-            for (key, value) in &element.props {
-                props_string.push_str(&format!(" {}=\"{}\"", key, value));
+            let foreign = foreign
+                || element.tag_name.eq_ignore_ascii_case("svg")
+                || element.tag_name.eq_ignore_ascii_case("math");
+            // In SVG and MathML a `<script>` or `<style>` is parsed like any element.
+            let raw_text = (!foreign && is_raw_text_element(&element.tag_name))
+                .then_some(element.tag_name.as_str());
+            let inner = Context { foreign, raw_text };
+            let lowercase = options.case == CasePolicy::LowercaseHtml && !foreign;
+            write_start_tag_with(element, lowercase, writer)?;
+            if !foreign && is_void_element(&element.tag_name) {
+                // `<br>` has no end tag and can't have children.
+                return Ok(());
             }
-            let children_string: String = element.children.iter().map(render_node_to_string).collect();
-            format!(
-                "<{}{}>{}</{}>",
-                element.tag_name, props_string, children_string, element.tag_name
-            )
+            // Other empty elements still get an explicit end tag: `<div/>` does not close
+            // a `div` in HTML, and strict XML consumers expect `<div></div>` as well.
+            match options.max_children {
+                Some(max) if element.props.get(PAGINATE_ATTRIBUTE).is_some() => {
+                    let mut children = Vec::with_capacity(element.children.len());
+                    splice_fragments(&element.children, &mut children);
+                    write_children(children.iter().copied().take(max), writer, options, inner)?;
+                    if children.len() > max {
+                        let hidden = children.len() - max;
+                        let marker = format!("<rr-more data-count=\"{0}\">{0} more</rr-more>", hidden);
+                        writer.write_all(marker.as_bytes())?;
+                    }
+                }
+                _ => write_children(&element.children, writer, options, inner)?,
+            }
+            write!(writer, "</{}>", cased(&element.tag_name, lowercase))
         }
         Node::Component(v_component) => {
-            // Access the inner component via `.0` to render it to a string.
-            let rendered_node = v_component.0.render();
-            render_node_to_string(&rendered_node)
+            let rendered_node = v_component.render();
+            if options.hydration_markers {
+                writer.write_all(b"<!--$-->")?;
+                write_node(&rendered_node, writer, options, context)?;
+                writer.write_all(b"<!--/$-->")
+            } else {
+                write_node(&rendered_node, writer, options, context)
+            }
+        }
+        Node::RawHtml(raw) => writer.write_all(raw.to_html().as_bytes()),
+        Node::Lazy(lazy) => write_node(&lazy.render(), writer, options, context),
+        Node::Async(v_async) => write_node(&v_async.fallback(), writer, options, context),
+        Node::ClientOnly(_) => writer.write_all(CLIENT_ONLY_PLACEHOLDER.as_bytes()),
+        Node::ServerOnly(inner) => write_node(inner, writer, options, context),
+        Node::Fragment(fragment) => write_children(&fragment.children, writer, options, context),
+    }
+}
+
+fn write_children<'a>(
+    children: impl IntoIterator<Item = &'a Node>,
+    writer: &mut impl Write,
+    options: &RenderOptions,
+    context: Context<'_>,
+) -> io::Result<()> {
+    let mut after_component = false;
+    for child in children {
+        let component = matches!(child, Node::Component(_));
+        if component && after_component && options.component_spacing == ComponentSpacing::Space {
+            writer.write_all(b" ")?;
+        }
+        write_node(child, writer, options, context)?;
+        after_component = component;
+    }
+    Ok(())
+}
+
+fn splice_fragments<'a>(children: &'a [Node], spliced: &mut Vec<&'a Node>) {
+    for child in children {
+        match child {
+            Node::Fragment(fragment) => splice_fragments(&fragment.children, spliced),
+            _ => spliced.push(child),
         }
     }
 }
 
+fn cased(name: &str, lowercase: bool) -> Cow<'_, str> {
+    if lowercase && name.bytes().any(|b| b.is_ascii_uppercase()) {
+        Cow::Owned(name.to_ascii_lowercase())
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+/// `</tag>`, or nothing for a void element such as `<br>`, whose children the
+/// serializers leave out as well.
+pub(crate) fn end_tag(element: &Element) -> String {
+    if is_void_element(&element.tag_name) {
+        String::new()
+    } else {
+        format!("</{}>", element.tag_name)
+    }
+}
+
+/// Boolean attributes that are written bare (`disabled`) when their value is empty.
+const BOOLEAN_ATTRIBUTES: &[&str] = &[
+    "disabled", "checked", "selected", "readonly", "required", "hidden", "multiple",
+];
+
+/// Writes `<tag attr="value" ...>`, shared by every HTML serializer in the crate.
+pub(crate) fn write_start_tag(element: &Element, writer: &mut impl Write) -> io::Result<()> {
+    write_start_tag_with(element, false, writer)
+}
+
+fn write_start_tag_with(
+    element: &Element,
+    lowercase: bool,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    write!(writer, "<{}", cased(&element.tag_name, lowercase))?;
+    for (key, value) in &element.props {
+        writer.write_all(b" ")?;
+        write_attr(&cased(key, lowercase), value, writer)?;
+    }
+    writer.write_all(b">")
+}
+
+/// Writes one `name="value"` pair with the value escaped, or just the name for an
+/// empty boolean attribute.
+pub(crate) fn write_attr(key: &str, value: &str, writer: &mut impl Write) -> io::Result<()> {
+    if value.is_empty() && BOOLEAN_ATTRIBUTES.contains(&key) {
+        write!(writer, "{}", key)
+    } else {
+        write!(writer, "{}=\"{}\"", key, escape_attr(value))
+    }
+}
+
+/// Serializes `attrs` the way the string renderer writes attributes: sorted by name,
+/// separated by spaces, values escaped. Useful when building markup by hand.
+pub fn serialize_attrs(attrs: &BTreeMap<String, String>) -> String {
+    let mut buffer = Vec::new();
+    for (i, (key, value)) in attrs.iter().enumerate() {
+        if i > 0 {
+            buffer.push(b' ');
+        }
+        write_attr(key, value, &mut buffer).expect("Writing to a Vec never fails");
+    }
+    String::from_utf8(buffer).expect("Attributes are valid UTF-8")
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -187,8 +1374,8 @@ mod tests {
 
     #[test]
     fn test_render_to_string_with_component() {
-        // We now wrap our component in an Rc and then our VComponent newtype.
-        let vdom = Node::Component(VComponent(Rc::new(App)));
+        // We now wrap our component in our VComponent newtype.
+        let vdom = Node::Component(VComponent::new(App));
 
         let html_string = render_node_to_string(&vdom);
 
@@ -197,4 +1384,472 @@ mod tests {
 
         assert!(html_string == expected_html || html_string == expected_html_alt, "Rendered HTML string did not match expected output.");
     }
+
+    #[test]
+    fn test_raw_html_is_sanitized_when_enabled() {
+        let markup = r#"<b>bold</b><script>alert("xss")</script>"#;
+
+        let trusted = Node::RawHtml(RawHtml::new(markup));
+        assert_eq!(render_node_to_string(&trusted), markup);
+
+        let untrusted = Node::RawHtml(RawHtml::new(markup).sanitized(SanitizePolicy::default()));
+        assert_eq!(render_node_to_string(&untrusted), "<b>bold</b>");
+    }
+
+    #[test]
+    fn test_render_to_string_encodes_multi_line_attribute() {
+        let vdom = Node::Element(Element {
+            tag_name: "abbr".to_string(),
+            props: Props::from([("title".to_string(), "Line one\nLine \"two\"".to_string())]),
+            children: vec![Node::Text("a < b".to_string())],
+            ..Default::default()
+        });
+
+        assert_eq!(
+            render_node_to_string(&vdom),
+            r#"<abbr title="Line one&#10;Line &quot;two&quot;">a &lt; b</abbr>"#
+        );
+    }
+
+    #[test]
+    fn test_render_to_bytes_matches_string_output() {
+        let vdom = Node::Element(Element {
+            tag_name: "p".to_string(),
+            props: Props::from([("title".to_string(), "café \"crème\"".to_string())]),
+            children: vec![
+                Node::Text("Grüße & 👋".to_string()),
+                Node::Element(Element::new("br")),
+                Node::Element(Element::new("input").attr("value", "1")),
+            ],
+            ..Default::default()
+        });
+
+        let bytes = render_node_to_bytes(&vdom);
+
+        assert_eq!(
+            bytes,
+            b"<p title=\"caf\xc3\xa9 &quot;cr\xc3\xa8me&quot;\">\
+              Gr\xc3\xbc\xc3\x9fe &amp; \xf0\x9f\x91\x8b<br><input value=\"1\"></p>"
+        );
+        assert_eq!(
+            bytes,
+            r#"<p title="café &quot;crème&quot;">Grüße &amp; 👋<br><input value="1"></p>"#
+                .as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_on_create_is_ignored_by_string_renderer() {
+        let vdom = Node::Element(
+            Element::new("div").on_create(|_| panic!("on_create must not run during SSR")),
+        );
+
+        assert_eq!(render_node_to_string(&vdom), "<div></div>");
+    }
+
+    #[test]
+    fn test_lazy_child_is_built_only_when_rendered() {
+        use std::cell::Cell;
+
+        let builds = Rc::new(Cell::new(0));
+        let counter = builds.clone();
+        let details = Lazy::new(move || {
+            counter.set(counter.get() + 1);
+            Node::Text("expensive".to_string())
+        });
+
+        let collapsed = Node::Element(Element::new("section"));
+        let expanded = Node::Element(Element::new("section").child(details.clone()));
+        assert_eq!(builds.get(), 0, "constructing and cloning must not build the subtree");
+
+        assert_eq!(render_node_to_string(&collapsed), "<section></section>");
+        assert_eq!(builds.get(), 0);
+        assert_eq!(render_node_to_string(&expanded), "<section>expensive</section>");
+        assert_eq!(builds.get(), 1);
+    }
+
+    #[test]
+    fn test_children_from_maps_an_iterator_into_children() {
+        let list = Element::new("ol").children_from((0..3).map(|i| format!("item {}", i)));
+
+        assert_eq!(list.children.len(), 3);
+        assert!(list.children.iter().all(|child| matches!(child, Node::Text(_))));
+        assert_eq!(
+            render_node_to_string(&Node::Element(list)),
+            "<ol>item 0item 1item 2</ol>"
+        );
+    }
+
+    #[test]
+    fn test_hydration_markers_wrap_component_output() {
+        let vdom = Node::Element(Element::new("main").child(component(App)));
+
+        let plain = render_node_to_string(&vdom);
+        assert!(!plain.contains("<!--"));
+
+        let options = RenderOptions {
+            hydration_markers: true,
+            ..Default::default()
+        };
+        let marked = render_node_to_string_with(&vdom, &options);
+        assert!(marked.starts_with("<main><!--$--><div "), "{}", marked);
+        assert!(marked.ends_with("</p></div><!--/$--></main>"), "{}", marked);
+    }
+
+    #[test]
+    fn test_empty_boolean_attribute_renders_bare() {
+        let button = Node::Element(Element::new("button").attr("disabled", "").child("Save"));
+        assert_eq!(render_node_to_string(&button), "<button disabled>Save</button>");
+
+        // Only known boolean attributes are shortened.
+        let option = Node::Element(Element::new("option").attr("value", ""));
+        assert_eq!(render_node_to_string(&option), r#"<option value=""></option>"#);
+    }
+
+    #[test]
+    fn test_html_attr_name_maps_react_props() {
+        assert_eq!(html_attr_name("className"), "class");
+        assert_eq!(html_attr_name("htmlFor"), "for");
+        assert_eq!(html_attr_name("tabIndex"), "tabindex");
+        assert_eq!(html_attr_name("ariaLabel"), "aria-label");
+        assert_eq!(html_attr_name("dataUserId"), "data-user-id");
+        assert_eq!(html_attr_name("viewBox"), "viewBox");
+        assert_eq!(html_attr_name("id"), "id");
+    }
+
+    #[test]
+    fn test_prop_stores_html_attribute_names() {
+        let label = Element::new("label")
+            .prop("className", "field")
+            .prop("htmlFor", "email");
+
+        assert_eq!(label.props.get("class").unwrap(), "field");
+        assert_eq!(label.props.get("for").unwrap(), "email");
+        assert!(!label.props.contains_key("className"));
+    }
+
+    #[test]
+    fn test_attributes_render_in_insertion_order() {
+        let link = Element::new("a")
+            .attr("href", "/home")
+            .attr("id", "home")
+            .attr("class", "nav")
+            .attr("id", "home-link");
+
+        assert_eq!(
+            render_node_to_string(&Node::Element(link)),
+            "<a href=\"/home\" id=\"home-link\" class=\"nav\"></a>"
+        );
+    }
+
+    #[test]
+    fn test_join_nodes_interleaves_separators() {
+        let join = |items: &[&str]| {
+            let items = items.iter().map(|item| item.into_node()).collect();
+            render_node_to_string(&join_nodes(items, Node::Text(" · ".to_string())))
+        };
+
+        assert_eq!(join(&[]), "");
+        assert_eq!(join(&["a"]), "a");
+        assert_eq!(join(&["a", "b", "c"]), "a · b · c");
+    }
+
+    #[test]
+    fn test_classes_keeps_enabled_names() {
+        assert_eq!(classes(&[("a", true), ("b", false), ("c", true)]), "a c");
+        assert_eq!(classes(&[("a", false)]), "");
+
+        let button = Element::new("button").classes(&[("btn", true), ("active", false)]);
+        assert_eq!(button.props["class"], "btn");
+    }
+
+    #[test]
+    fn test_serialize_attrs_sorts_and_escapes() {
+        let attrs = BTreeMap::from([
+            ("title".to_string(), "Tom & \"Jerry\"".to_string()),
+            ("disabled".to_string(), String::new()),
+            ("alt".to_string(), "<logo>".to_string()),
+        ]);
+
+        assert_eq!(
+            serialize_attrs(&attrs),
+            r#"alt="&lt;logo&gt;" disabled title="Tom &amp; &quot;Jerry&quot;""#
+        );
+        assert_eq!(serialize_attrs(&BTreeMap::new()), "");
+    }
+
+    #[test]
+    fn test_client_only_renders_placeholder_on_the_server() {
+        let page = Element::new("main")
+            .child(Node::ClientOnly(Box::new(Element::new("canvas").into_node())))
+            .child(Node::ServerOnly(Box::new(Element::new("footer").child("© 2024").into_node())));
+
+        assert_eq!(
+            render_node_to_string(&Node::Element(page)),
+            "<main><!--client-only--><footer>© 2024</footer></main>"
+        );
+    }
+
+    #[test]
+    fn test_short_type_name_strips_paths_of_generic_arguments() {
+        assert_eq!(short_type_name("app::App"), "App");
+        assert_eq!(
+            short_type_name("app::Table<app::rows::Row, alloc::vec::Vec<(u8, &str)>>"),
+            "Table<Row, Vec<(u8, &str)>>"
+        );
+        assert_eq!(short_type_name("app::Grid<[app::Cell; 4]>"), "Grid<[Cell; 4]>");
+    }
+
+    #[test]
+    fn test_with_props_renders_clone_with_new_props() {
+        #[derive(Debug, Clone)]
+        struct Badge {
+            label: String,
+            tone: &'static str,
+        }
+        impl Component for Badge {
+            fn render(&self) -> Node {
+                Node::Element(Element::new("span").attr("class", self.tone).child(self.label.as_str()))
+            }
+            fn clone_rc(&self) -> Rc<dyn Component> {
+                Rc::new(self.clone())
+            }
+        }
+
+        let original = component(Badge { label: "New".to_string(), tone: "info" }).key("badge");
+        let warning = original.with_props(|badge: &mut Badge| badge.tone = "warning").unwrap();
+
+        assert_eq!(
+            render_node_to_string(&Node::Component(warning.clone())),
+            "<span class=\"warning\">New</span>"
+        );
+        assert_eq!(warning.key.as_deref(), Some("badge"));
+        assert!(!Rc::ptr_eq(&warning.hooks, &original.hooks));
+        assert!(original.with_props(|_: &mut App| {}).is_none());
+        assert_eq!(
+            render_node_to_string(&Node::Component(original)),
+            "<span class=\"info\">New</span>"
+        );
+    }
+
+    #[test]
+    fn test_empty_element_gets_explicit_end_tag() {
+        assert_eq!(render_node_to_string(&Node::Element(Element::new("div"))), "<div></div>");
+        assert_eq!(
+            render_node_to_string(&Node::Element(Element::new("span").attr("class", "icon"))),
+            "<span class=\"icon\"></span>"
+        );
+    }
+
+    #[test]
+    fn test_lowercase_html_policy_keeps_svg_case() {
+        let icon = Node::Element(
+            Element::new("Div").attr("CLASS", "icon").child(
+                Element::new("svg")
+                    .attr("viewBox", "0 0 10 10")
+                    .child(Element::new("foreignObject")),
+            ),
+        );
+
+        let lowercase = RenderOptions {
+            case: CasePolicy::LowercaseHtml,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_node_to_string_with(&icon, &lowercase),
+            "<div class=\"icon\"><svg viewBox=\"0 0 10 10\"><foreignObject></foreignObject></svg></div>"
+        );
+        assert_eq!(
+            render_node_to_string(&icon),
+            "<Div CLASS=\"icon\"><svg viewBox=\"0 0 10 10\"><foreignObject></foreignObject></svg></Div>"
+        );
+    }
+
+    #[test]
+    fn test_list_indexed_passes_index_to_builder() {
+        let rows = list_indexed(&["Ada", "Grace", "Edsger"], |i, name| {
+            let stripe = if i % 2 == 0 { "even" } else { "odd" };
+            Node::Element(Element::new("li").key(i.to_string()).attr("class", stripe).child(*name))
+        });
+
+        assert_eq!(
+            render_node_to_string(&rows),
+            "<li class=\"even\">Ada</li><li class=\"odd\">Grace</li><li class=\"even\">Edsger</li>"
+        );
+        let Node::Fragment(fragment) = rows else { panic!("expected a fragment") };
+        let keys: Vec<_> = fragment
+            .children
+            .iter()
+            .map(|child| match child {
+                Node::Element(element) => element.key.clone().unwrap(),
+                _ => panic!("expected an element"),
+            })
+            .collect();
+        assert_eq!(keys, ["0", "1", "2"]);
+    }
+
+    #[test]
+    fn test_merge_class_deduplicates_overlapping_lists() {
+        assert_eq!(
+            merge_class(Some("card  shadow active"), "card rounded card"),
+            "card rounded shadow active"
+        );
+        assert_eq!(merge_class(None, "btn"), "btn");
+        assert_eq!(merge_class(Some("wide"), ""), "wide");
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_source_locations_record_the_builder_call_site() {
+        assert!(!Element::new("div").props.contains_key(SOURCE_ATTRIBUTE));
+
+        set_source_locations(true);
+        let line = line!() + 1;
+        let card = Element::new("div").child(tag::table());
+        set_source_locations(false);
+
+        let expected = format!("{}:{}", file!(), line);
+        assert_eq!(card.props.get(SOURCE_ATTRIBUTE), Some(&expected));
+        let Node::Element(table) = &card.children[0] else { panic!("expected the table") };
+        assert_eq!(table.props.get(SOURCE_ATTRIBUTE), Some(&expected));
+    }
+
+    #[test]
+    fn test_top_level_fragment_renders_without_wrapper() {
+        let sections = Fragment::new()
+            .child(
+                Element::new("section")
+                    .attr("title", "Q&A")
+                    .child("Tom & Jerry <3"),
+            )
+            .child(Element::new("br"))
+            .child("1 < 2 & 3")
+            .child(Element::new("section").child(Element::new("div")));
+
+        assert_eq!(
+            render_node_to_string(&Node::Fragment(sections)),
+            "<section title=\"Q&amp;A\">Tom &amp; Jerry &lt;3</section><br>1 &lt; 2 &amp; 3\
+             <section><div></div></section>"
+        );
+    }
+
+    #[test]
+    fn test_component_spacing_between_adjacent_components() {
+        #[derive(Debug, Clone)]
+        struct Pill(&'static str);
+        impl Component for Pill {
+            fn render(&self) -> Node {
+                Node::Element(Element::new("span").child(self.0))
+            }
+            fn clone_rc(&self) -> Rc<dyn Component> {
+                Rc::new(self.clone())
+            }
+        }
+
+        let sentence = Node::Element(
+            Element::new("p")
+                .child(component(Pill("New")))
+                .child(component(Pill("Beta")))
+                .child("!"),
+        );
+
+        assert_eq!(
+            render_node_to_string(&sentence),
+            "<p><span>New</span><span>Beta</span>!</p>"
+        );
+        let spaced = RenderOptions {
+            component_spacing: ComponentSpacing::Space,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_node_to_string_with(&sentence, &spaced),
+            "<p><span>New</span> <span>Beta</span>!</p>"
+        );
+    }
+
+    #[test]
+    fn test_max_children_renders_first_children_and_marker() {
+        let items: Vec<u32> = (1..=100).collect();
+        let ul = Node::Element(
+            Element::new("ul")
+                .paginate()
+                .child(list(&items, |i| Node::Element(Element::new("li").child(i.to_string())))),
+        );
+        let options = RenderOptions {
+            max_children: Some(10),
+            ..Default::default()
+        };
+
+        let html = render_node_to_string_with(&ul, &options);
+        let first_ten: String = (1..=10).map(|i| format!("<li>{}</li>", i)).collect();
+        assert_eq!(
+            html,
+            format!(
+                "<ul data-rr-paginate=\"\">{}<rr-more data-count=\"90\">90 more</rr-more></ul>",
+                first_ten
+            )
+        );
+        assert_eq!(render_node_to_string(&ul).matches("<li>").count(), 100);
+    }
+
+    #[test]
+    fn test_merged_classes_combines_all_sources() {
+        let button = Element::new("button").merged_classes(
+            "btn btn-primary",
+            &[("active", true), ("btn", true), ("disabled", false)],
+            Some("btn  wide active"),
+        );
+        assert_eq!(button.props["class"], "btn btn-primary active wide");
+
+        let plain = Element::new("button").merged_classes("btn", &[], None);
+        assert_eq!(plain.props["class"], "btn");
+    }
+
+    #[test]
+    fn test_attrs_if_is_all_or_nothing() {
+        let link = |external: bool| {
+            render_node_to_string(&Node::Element(
+                Element::new("a")
+                    .attr("href", "/docs")
+                    .attrs_if(external, &[("target", "_blank"), ("rel", "noopener")]),
+            ))
+        };
+
+        assert_eq!(
+            link(true),
+            "<a href=\"/docs\" target=\"_blank\" rel=\"noopener\"></a>"
+        );
+        assert_eq!(link(false), "<a href=\"/docs\"></a>");
+    }
+
+    #[test]
+    fn test_script_and_style_text_is_not_entity_escaped() {
+        let page = Node::Element(
+            Element::new("head")
+                .child(Element::new("script").child("if (a < b && c) { s = '</script>'; }"))
+                .child(Element::new("style").child("a > b { content: \"&\" }"))
+                .child(Element::new("title").child("a < b")),
+        );
+
+        assert_eq!(
+            render_node_to_string(&page),
+            "<head><script>if (a < b && c) { s = '<\\/script>'; }</script>\
+             <style>a > b { content: \"&\" }</style><title>a &lt; b</title></head>"
+        );
+    }
+
+    #[test]
+    fn test_void_elements_have_no_end_tag_or_children() {
+        let form = Node::Element(
+            Element::new("p")
+                .child(Element::new("input").attr("name", "q"))
+                .child(Element::new("br").child("dropped"))
+                .child(Element::new("img").attr("src", "/a.png")),
+        );
+
+        assert_eq!(
+            render_node_to_string(&form),
+            "<p><input name=\"q\"><br><img src=\"/a.png\"></p>"
+        );
+    }
 }
@@ -0,0 +1,148 @@
+//! `lang` and `dir` attributes derived from a locale.
+
+use crate::commit::commit_on_server;
+use crate::{Element, Fragment, Node};
+
+/// Which way text in a language runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+impl Direction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Direction::Ltr => "ltr",
+            Direction::Rtl => "rtl",
+        }
+    }
+}
+
+/// Languages written right to left, by their primary subtag.
+const RTL_LANGUAGES: &[&str] = &[
+    "ar", "arc", "ckb", "dv", "fa", "he", "iw", "ks", "ps", "sd", "syr", "ug", "ur", "yi",
+];
+
+/// Scripts written right to left, for locales such as `az-Arab` or `pa-Arab`.
+const RTL_SCRIPTS: &[&str] = &["arab", "hebr", "nkoo", "syrc", "thaa"];
+
+/// The direction of a BCP 47 locale such as `"en-US"`, `"ar"` or `"pa-Arab-PK"`. A
+/// script subtag wins over the language's usual script, and unknown or empty locales
+/// are treated as left to right.
+pub fn text_direction(locale: &str) -> Direction {
+    let mut subtags = locale.split(['-', '_']).map(str::to_ascii_lowercase);
+    let language = subtags.next().unwrap_or_default();
+    let script = subtags.next().filter(|subtag| subtag.len() == 4);
+    let rtl = match script {
+        Some(script) => RTL_SCRIPTS.contains(&script.as_str()),
+        None => RTL_LANGUAGES.contains(&language.as_str()),
+    };
+    if rtl { Direction::Rtl } else { Direction::Ltr }
+}
+
+/// Returns `node` with `lang="{locale}"` and the matching `dir` set on its root element,
+/// e.g. the `<html>` of a server-rendered page. Descendants that declare a `lang` of
+/// their own, like a quote in another language, get a `dir` too when their direction
+/// differs from the one around them, unless they already set one.
+pub fn with_locale(node: &Node, locale: &str) -> Node {
+    let direction = text_direction(locale);
+    // Committed first, so the components' `use_id` calls see their positions.
+    localize_root(&commit_on_server(node), locale, direction)
+}
+
+fn localize_root(node: &Node, locale: &str, direction: Direction) -> Node {
+    match node {
+        Node::Element(element) => {
+            let element = element
+                .clone()
+                .attr("lang", locale)
+                .attr("dir", direction.as_str());
+            Node::Element(Element {
+                children: localize_children(&element.children, direction),
+                ..element
+            })
+        }
+        Node::Component(v_component) => localize_root(&v_component.render(), locale, direction),
+        Node::Lazy(lazy) => localize_root(&lazy.render(), locale, direction),
+        // Without a single root element every top-level element is a root.
+        Node::Fragment(fragment) => Node::Fragment(Fragment {
+            children: fragment
+                .children
+                .iter()
+                .map(|child| localize_root(child, locale, direction))
+                .collect(),
+            key: fragment.key.clone(),
+        }),
+        _ => node.clone(),
+    }
+}
+
+fn localize(node: &Node, inherited: Direction) -> Node {
+    match node {
+        Node::Element(element) => {
+            let mut element = element.clone();
+            let mut direction = inherited;
+            if let Some(lang) = element.props.get("lang") {
+                direction = text_direction(lang);
+                if direction != inherited && !element.props.contains_key("dir") {
+                    element = element.attr("dir", direction.as_str());
+                }
+            }
+            Node::Element(Element {
+                children: localize_children(&element.children, direction),
+                ..element
+            })
+        }
+        Node::Fragment(fragment) => Node::Fragment(Fragment {
+            children: localize_children(&fragment.children, inherited),
+            key: fragment.key.clone(),
+        }),
+        Node::Component(v_component) => localize(&v_component.render(), inherited),
+        Node::Lazy(lazy) => localize(&lazy.render(), inherited),
+        _ => node.clone(),
+    }
+}
+
+fn localize_children(children: &[Node], inherited: Direction) -> Vec<Node> {
+    children
+        .iter()
+        .map(|child| localize(child, inherited))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_node_to_string;
+
+    #[test]
+    fn test_rtl_locale_sets_dir_on_root() {
+        let page = Node::Element(
+            Element::new("html").child(
+                Element::new("body")
+                    .child(Element::new("h1").child("مرحبا"))
+                    .child(Element::new("q").attr("lang", "en").child("Hello")),
+            ),
+        );
+
+        assert_eq!(
+            render_node_to_string(&with_locale(&page, "ar-EG")),
+            "<html lang=\"ar-EG\" dir=\"rtl\"><body><h1>مرحبا</h1>\
+             <q lang=\"en\" dir=\"ltr\">Hello</q></body></html>"
+        );
+        assert_eq!(
+            render_node_to_string(&with_locale(&Node::Element(Element::new("html")), "en-US")),
+            "<html lang=\"en-US\" dir=\"ltr\"></html>"
+        );
+    }
+
+    #[test]
+    fn test_text_direction_reads_language_and_script() {
+        assert_eq!(text_direction("he"), Direction::Rtl);
+        assert_eq!(text_direction("fa_IR"), Direction::Rtl);
+        assert_eq!(text_direction("pa-Arab-PK"), Direction::Rtl);
+        assert_eq!(text_direction("az-Latn"), Direction::Ltr);
+        assert_eq!(text_direction(""), Direction::Ltr);
+    }
+}
@@ -0,0 +1,193 @@
+//! Telling the client which server-rendered elements have event handlers.
+
+use crate::commit::commit_on_server;
+use crate::{Element, Fragment, Node, render_node_to_string};
+
+/// The attribute that identifies an element with handlers in server-rendered markup.
+pub const EVENT_ID_ATTRIBUTE: &str = "data-rr-id";
+
+/// For every element with listeners, in document order: its `data-rr-id`, the names
+/// of the events it listens for, and the hydration priority of the component it
+/// belongs to, if one was declared.
+pub type EventManifest = Vec<(String, Vec<String>, Option<HydrationPriority>)>;
+
+/// When the client should hydrate a component, see `Component::hydration_priority`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HydrationPriority {
+    /// Right away, before anything else: the controls a user is likely to touch first.
+    High,
+    /// After the high-priority regions.
+    Low,
+    /// Whenever the browser is idle, e.g. from `requestIdleCallback`.
+    Idle,
+}
+
+/// Renders `node` to HTML like `render_node_to_string`, but gives every element that
+/// has listeners a stable `data-rr-id` attribute and lists it in the returned
+/// manifest, so client code can find the elements to re-attach listeners to with
+/// `[data-rr-id="..."]` instead of walking the whole tree. `hydrate` doesn't read the
+/// manifest; it walks the markup, so the manifest is for clients that attach
+/// listeners themselves, e.g. one region at a time.
+///
+/// Ids are assigned in document order, so the same tree always gets the same ids.
+/// Each entry carries the priority of the innermost component around the element that
+/// declares one, so the client can hydrate `High` regions first and leave `Idle` ones
+/// for later.
+/// `Node::ClientOnly` content is not part of the markup and `Node::ServerOnly`
+/// content is never made interactive, so neither shows up in the manifest.
+pub fn render_node_to_string_with_manifest(node: &Node) -> (String, EventManifest) {
+    let mut manifest = Vec::new();
+    // Committed first, so `use_id` gives the ids `hydrate` will work out.
+    let annotated = annotate(&commit_on_server(node), None, &mut manifest);
+    (render_node_to_string(&annotated), manifest)
+}
+
+fn annotate(
+    node: &Node,
+    priority: Option<HydrationPriority>,
+    manifest: &mut EventManifest,
+) -> Node {
+    match node {
+        Node::Element(element) => {
+            let mut element = element.clone();
+            if !element.listeners.is_empty() {
+                let id = manifest.len().to_string();
+                let mut events: Vec<String> = Vec::new();
+                for listener in &element.listeners {
+                    if !events.contains(&listener.event) {
+                        events.push(listener.event.clone());
+                    }
+                }
+                element.props.insert(EVENT_ID_ATTRIBUTE, id.clone());
+                manifest.push((id, events, priority));
+            }
+            Node::Element(Element {
+                children: annotate_children(&element.children, priority, manifest),
+                ..element
+            })
+        }
+        Node::Fragment(fragment) => Node::Fragment(Fragment {
+            children: annotate_children(&fragment.children, priority, manifest),
+            key: fragment.key.clone(),
+        }),
+        Node::Component(v_component) => {
+            let priority = v_component.hydration_priority().or(priority);
+            annotate(&v_component.render(), priority, manifest)
+        }
+        Node::Lazy(lazy) => annotate(&lazy.render(), priority, manifest),
+        Node::Async(v_async) => annotate(&v_async.fallback(), priority, manifest),
+        Node::Text(_) | Node::RawHtml(_) | Node::ClientOnly(_) | Node::ServerOnly(_) => {
+            node.clone()
+        }
+    }
+}
+
+fn annotate_children(
+    children: &[Node],
+    priority: Option<HydrationPriority>,
+    manifest: &mut EventManifest,
+) -> Vec<Node> {
+    children
+        .iter()
+        .map(|child| annotate(child, priority, manifest))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{Component, component, use_id};
+
+    #[test]
+    fn test_elements_with_handlers_get_ids_and_manifest_entries() {
+        let form = Node::Element(
+            Element::new("form")
+                .on("submit", |_| {})
+                .child(Element::new("h2").child("Sign up"))
+                .child(Element::new("button").on_click(|_| {}).on("focus", |_| {})),
+        );
+
+        let (html, manifest) = render_node_to_string_with_manifest(&form);
+
+        assert_eq!(
+            html,
+            "<form data-rr-id=\"0\"><h2>Sign up</h2><button data-rr-id=\"1\"></button></form>"
+        );
+        assert_eq!(
+            manifest,
+            vec![
+                ("0".to_string(), vec!["submit".to_string()], None),
+                (
+                    "1".to_string(),
+                    vec!["click".to_string(), "focus".to_string()],
+                    None
+                ),
+            ]
+        );
+    }
+
+    #[derive(Debug, Clone)]
+    struct SearchBox;
+
+    impl Component for SearchBox {
+        fn render(&self) -> Node {
+            Node::Element(Element::new("input").on_input(|_| {}))
+        }
+
+        fn clone_rc(&self) -> Rc<dyn Component> {
+            Rc::new(self.clone())
+        }
+
+        fn hydration_priority(&self) -> Option<HydrationPriority> {
+            Some(HydrationPriority::High)
+        }
+    }
+
+    #[test]
+    fn test_component_hydration_priority_is_recorded() {
+        let page = Node::Element(
+            Element::new("div")
+                .child(Element::new("header").child(component(SearchBox)))
+                .child(Element::new("footer").child(Element::new("a").on_click(|_| {}))),
+        );
+
+        let (_, manifest) = render_node_to_string_with_manifest(&page);
+
+        assert_eq!(manifest[0].1, ["input"]);
+        assert_eq!(manifest[0].2, Some(HydrationPriority::High));
+        assert_eq!(manifest[1].2, None);
+    }
+
+    #[derive(Debug, Clone)]
+    struct Field;
+
+    impl Component for Field {
+        fn render(&self) -> Node {
+            Node::Element(Element::new("input").attr("id", use_id()).on_input(|_| {}))
+        }
+
+        fn clone_rc(&self) -> Rc<dyn Component> {
+            Rc::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_manifest_markup_keeps_the_ids_of_plain_markup() {
+        let form = Node::Element(
+            Element::new("form")
+                .child(component(Field))
+                .child(component(Field)),
+        );
+
+        let (html, _) = render_node_to_string_with_manifest(&form);
+
+        assert_eq!(
+            html,
+            "<form><input id=\"rr-id-0\" data-rr-id=\"0\">\
+             <input id=\"rr-id-1\" data-rr-id=\"1\"></form>"
+        );
+        assert!(render_node_to_string(&form).contains("<input id=\"rr-id-1\">"));
+    }
+}
@@ -0,0 +1,207 @@
+//! Rendering content only while a CSS media query matches.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::rc::{Rc, Weak};
+
+use wasm_bindgen::JsCast;
+use wasm_bindgen::closure::Closure;
+use web_sys::{Event, MediaQueryList};
+
+use crate::store::{Store, SubscriptionId, create_store};
+use crate::{Component, Fragment, IntoNode, Node};
+
+/// A component that renders its children only while `query` matches, e.g.
+/// `MediaQuery::new("(max-width: 600px)")`. The match state follows the browser's
+/// `change` events; `subscribe` to re-render the root when it flips. All components
+/// for the same query share one listener, which is removed once the last of them is
+/// dropped.
+#[derive(Clone)]
+pub struct MediaQuery {
+    children: Vec<Node>,
+    shared: Rc<SharedQuery>,
+}
+
+/// The state of one query, shared by every `MediaQuery` for it.
+struct SharedQuery {
+    query: String,
+    matches: Store<bool>,
+    _listener: Option<MediaListener>,
+}
+
+impl Drop for SharedQuery {
+    fn drop(&mut self) {
+        // `try_with`, since this may run while the thread's storage is torn down.
+        let _ = QUERIES.try_with(|queries| queries.borrow_mut().remove(&self.query));
+    }
+}
+
+thread_local! {
+    static QUERIES: RefCell<HashMap<String, Weak<SharedQuery>>> = RefCell::new(HashMap::new());
+}
+
+/// Keeps the `change` listener registered for as long as it is alive.
+struct MediaListener {
+    list: MediaQueryList,
+    callback: Closure<dyn FnMut(Event)>,
+}
+
+impl Drop for MediaListener {
+    fn drop(&mut self) {
+        let _ = self
+            .list
+            .remove_event_listener_with_callback("change", self.callback.as_ref().unchecked_ref());
+    }
+}
+
+impl MediaQuery {
+    pub fn new(query: &str) -> Self {
+        MediaQuery::shared(query, || {
+            let list = web_sys::window()
+                .expect("no global `window` exists")
+                .match_media(query)
+                .expect("Failed to evaluate media query")
+                .unwrap_or_else(|| panic!("Invalid media query '{}'", query));
+            let matches = create_store(list.matches());
+
+            let store = matches.clone();
+            let watched = list.clone();
+            let callback = Closure::<dyn FnMut(Event)>::new(move |_: Event| {
+                let now = watched.matches();
+                if store.get() != now {
+                    store.update(|matches| *matches = now);
+                }
+            });
+            list.add_event_listener_with_callback("change", callback.as_ref().unchecked_ref())
+                .expect("Failed to add media query listener");
+            (matches, Some(MediaListener { list, callback }))
+        })
+    }
+
+    /// A media query sharing the state of the live ones for `query`, or, if there are
+    /// none, with the state and listener `open` sets up.
+    fn shared(query: &str, open: impl FnOnce() -> (Store<bool>, Option<MediaListener>)) -> Self {
+        let existing = QUERIES.with(|queries| queries.borrow().get(query).and_then(Weak::upgrade));
+        let shared = existing.unwrap_or_else(|| {
+            let (matches, listener) = open();
+            let shared = Rc::new(SharedQuery {
+                query: query.to_string(),
+                matches,
+                _listener: listener,
+            });
+            QUERIES.with(|queries| {
+                queries
+                    .borrow_mut()
+                    .insert(query.to_string(), Rc::downgrade(&shared))
+            });
+            shared
+        });
+        MediaQuery {
+            children: Vec::new(),
+            shared,
+        }
+    }
+
+    pub fn child(mut self, child: impl IntoNode) -> Self {
+        self.children.push(child.into_node());
+        self
+    }
+
+    /// Whether the query currently matches.
+    pub fn matches(&self) -> bool {
+        self.shared.matches.get()
+    }
+
+    /// Calls `callback` with the new state whenever the match flips.
+    pub fn subscribe(&self, callback: impl Fn(&bool) + 'static) -> SubscriptionId {
+        self.shared.matches.subscribe(callback)
+    }
+
+    pub fn unsubscribe(&self, id: SubscriptionId) {
+        self.shared.matches.unsubscribe(id)
+    }
+}
+
+impl Debug for MediaQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MediaQuery")
+            .field("query", &self.shared.query)
+            .field("matches", &self.matches())
+            .field("children", &self.children)
+            .finish()
+    }
+}
+
+impl Component for MediaQuery {
+    fn render(&self) -> Node {
+        let children = if self.matches() {
+            self.children.clone()
+        } else {
+            Vec::new()
+        };
+        Node::Fragment(Fragment {
+            children,
+            key: None,
+        })
+    }
+
+    fn clone_rc(&self) -> Rc<dyn Component> {
+        Rc::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Element, component, render_node_to_string};
+    use std::cell::Cell;
+
+    #[test]
+    fn test_children_follow_match_state() {
+        let matches = create_store(false);
+        let opened = matches.clone();
+        let sidebar = MediaQuery::shared("(min-width: 800px)", move || (opened, None))
+            .child(Element::new("aside").child("Menu"));
+        let renders = Rc::new(Cell::new(0));
+        let counter = renders.clone();
+        sidebar.subscribe(move |_| counter.set(counter.get() + 1));
+        let page = Node::Component(component(sidebar));
+
+        assert_eq!(render_node_to_string(&page), "");
+        matches.update(|matches| *matches = true);
+        assert_eq!(render_node_to_string(&page), "<aside>Menu</aside>");
+        assert_eq!(renders.get(), 1);
+    }
+
+    #[test]
+    fn test_queries_share_state_until_the_last_one_is_dropped() {
+        let opens = Rc::new(Cell::new(0));
+        let open = |matches: bool| {
+            let opens = opens.clone();
+            move || {
+                opens.set(opens.get() + 1);
+                (create_store(matches), None)
+            }
+        };
+        let narrow = MediaQuery::shared("(max-width: 600px)", open(true));
+        let also_narrow = MediaQuery::shared("(max-width: 600px)", open(false));
+        assert_eq!(opens.get(), 1);
+        assert!(also_narrow.matches());
+
+        let flips = Rc::new(Cell::new(0));
+        let counter = flips.clone();
+        narrow.subscribe(move |_| counter.set(counter.get() + 1));
+        also_narrow
+            .shared
+            .matches
+            .update(|matches| *matches = false);
+        assert!(!narrow.matches());
+        assert_eq!(flips.get(), 1);
+
+        drop((narrow, also_narrow));
+        assert!(QUERIES.with(|queries| queries.borrow().is_empty()));
+        MediaQuery::shared("(max-width: 600px)", open(true));
+        assert_eq!(opens.get(), 2);
+    }
+}
@@ -0,0 +1,265 @@
+//! Building VDOM from HTML strings, for importing existing static markup, and comparing
+//! markup structurally.
+//!
+//! Only a simple subset is supported: elements, attributes, nesting and text, using the
+//! tokenizer the sanitizer is built on. There is no error recovery; markup a browser
+//! would repair (a stray end tag, a `<p>` that is never closed) is an error here.
+
+use std::fmt;
+
+use crate::escape::{is_void_element, unescape};
+use crate::sanitize::{Token, Tokenizer};
+use crate::{Element, Fragment, Node};
+
+/// Why `parse_html` rejected its input. Positions are byte offsets into it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// An end tag with no open element to close.
+    UnexpectedEndTag { name: String, position: usize },
+    /// An end tag that doesn't match the innermost open element.
+    MismatchedEndTag {
+        expected: String,
+        found: String,
+        position: usize,
+    },
+    /// The input ended while this element was still open.
+    UnclosedElement { name: String, position: usize },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedEndTag { name, position } => {
+                write!(f, "unexpected </{}> at byte {}", name, position)
+            }
+            ParseError::MismatchedEndTag {
+                expected,
+                found,
+                position,
+            } => write!(
+                f,
+                "expected </{}> but found </{}> at byte {}",
+                expected, found, position
+            ),
+            ParseError::UnclosedElement { name, position } => {
+                write!(f, "<{}> opened at byte {} is never closed", name, position)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses `html` into `Element` and `Text` nodes. Character references are decoded,
+/// comments and doctypes are dropped, and whitespace is kept as written. A single
+/// top-level node is returned as is; several are wrapped in a fragment.
+pub fn parse_html(html: &str) -> Result<Node, ParseError> {
+    // The open elements, each with the position of its start tag.
+    let mut open: Vec<(Element, usize)> = Vec::new();
+    let mut top_level: Vec<Node> = Vec::new();
+    let mut tokens = Tokenizer::new(html);
+
+    loop {
+        let position = tokens.position();
+        let Some(token) = tokens.next() else {
+            break;
+        };
+        let node = match token {
+            Token::Text(text) => Node::Text(unescape(text).into_owned()),
+            Token::Comment(_) | Token::Doctype(_) => continue,
+            Token::StartTag {
+                name,
+                attrs,
+                self_closing,
+            } => {
+                let mut element = Element::new(&name);
+                for (key, value) in attrs {
+                    element = element.attr(key, unescape(value.unwrap_or("")));
+                }
+                if !self_closing && !is_void_element(&name) {
+                    open.push((element, position));
+                    continue;
+                }
+                Node::Element(element)
+            }
+            Token::EndTag { name } => {
+                if is_void_element(&name) && open.last().is_none_or(|(e, _)| e.tag_name != name) {
+                    // `</br>` and friends close nothing.
+                    continue;
+                }
+                match open.pop() {
+                    Some((element, _)) if element.tag_name == name => Node::Element(element),
+                    Some((element, _)) => {
+                        return Err(ParseError::MismatchedEndTag {
+                            expected: element.tag_name,
+                            found: name,
+                            position,
+                        });
+                    }
+                    None => return Err(ParseError::UnexpectedEndTag { name, position }),
+                }
+            }
+        };
+        match open.last_mut() {
+            Some((parent, _)) => parent.children.push(node),
+            None => top_level.push(node),
+        }
+    }
+
+    if let Some((element, position)) = open.pop() {
+        return Err(ParseError::UnclosedElement {
+            name: element.tag_name,
+            position,
+        });
+    }
+    if top_level.len() == 1 {
+        return Ok(top_level.remove(0));
+    }
+    Ok(Node::Fragment(Fragment {
+        children: top_level,
+        key: None,
+    }))
+}
+
+/// Whether `a` and `b` are the same markup once parsed with `parse_html`, ignoring the
+/// order of attributes and insignificant whitespace: whitespace-only text between
+/// tags is dropped, other runs of whitespace count as a single space, and whitespace
+/// at the start and end of an element's content is ignored. Two inputs that don't
+/// both parse are never equal. Handy for asserting on server-rendered HTML.
+pub fn html_semantically_equal(a: &str, b: &str) -> bool {
+    match (parse_html(a), parse_html(b)) {
+        (Ok(a), Ok(b)) => canonical(&a) == canonical(&b),
+        _ => false,
+    }
+}
+
+/// Serializes a parsed tree in the normalized form `html_semantically_equal` compares.
+fn canonical(node: &Node) -> String {
+    let mut out = String::new();
+    write_canonical(std::slice::from_ref(node), &mut out);
+    out
+}
+
+fn write_canonical(nodes: &[Node], out: &mut String) {
+    let last = nodes.len().saturating_sub(1);
+    for (index, node) in nodes.iter().enumerate() {
+        match node {
+            Node::Element(element) => {
+                let mut attrs: Vec<_> = element.props.iter().collect();
+                attrs.sort();
+                out.push('<');
+                out.push_str(&element.tag_name);
+                for (name, value) in attrs {
+                    out.push_str(&format!(" {}={:?}", name, value));
+                }
+                out.push('>');
+                write_canonical(&element.children, out);
+                out.push_str(&format!("</{}>", element.tag_name));
+            }
+            Node::Text(text) => {
+                let words = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                if words.is_empty() {
+                    continue;
+                }
+                if index > 0 && text.starts_with(char::is_whitespace) {
+                    out.push(' ');
+                }
+                out.push_str(&words);
+                if index < last && text.ends_with(char::is_whitespace) {
+                    out.push(' ');
+                }
+            }
+            Node::Fragment(fragment) => write_canonical(&fragment.children, out),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_node_to_string;
+
+    #[test]
+    fn test_parse_html_builds_nested_elements() {
+        let node = parse_html(
+            r#"<div class="card" id="c1"><h2>Title &amp; more</h2><p>Hi <b>there</b><br>!</p></div>"#,
+        )
+        .unwrap();
+
+        let Node::Element(card) = &node else {
+            panic!("expected an element, got {:?}", node);
+        };
+        assert_eq!(card.tag_name, "div");
+        assert_eq!(card.props.get("class").unwrap(), "card");
+        assert_eq!(card.props.get("id").unwrap(), "c1");
+        assert_eq!(card.children.len(), 2);
+        let Node::Element(heading) = &card.children[0] else {
+            panic!("expected the heading");
+        };
+        assert!(matches!(&heading.children[..], [Node::Text(text)] if text == "Title & more"));
+        let Node::Element(paragraph) = &card.children[1] else {
+            panic!("expected the paragraph");
+        };
+        assert_eq!(paragraph.children.len(), 4);
+
+        assert_eq!(
+            render_node_to_string(&node),
+            "<div class=\"card\" id=\"c1\"><h2>Title &amp; more</h2><p>Hi <b>there</b><br>!</p></div>"
+        );
+    }
+
+    #[test]
+    fn test_parse_html_reports_malformed_markup() {
+        assert_eq!(
+            parse_html("<ul><li>One</ul>").unwrap_err(),
+            ParseError::MismatchedEndTag {
+                expected: "li".to_string(),
+                found: "ul".to_string(),
+                position: 11,
+            }
+        );
+        assert_eq!(
+            parse_html("text</p>").unwrap_err(),
+            ParseError::UnexpectedEndTag {
+                name: "p".to_string(),
+                position: 4,
+            }
+        );
+        let unclosed = parse_html("<p>One <em>two</p>").unwrap_err();
+        assert_eq!(
+            unclosed.to_string(),
+            "expected </em> but found </p> at byte 14"
+        );
+        assert_eq!(
+            parse_html("<section><p>x</p>").unwrap_err().to_string(),
+            "<section> opened at byte 0 is never closed"
+        );
+    }
+
+    #[test]
+    fn test_html_semantically_equal_ignores_attribute_order_and_whitespace() {
+        assert!(html_semantically_equal(
+            r#"<ul class="list" id="nav"><li>Home</li><li>About   us</li></ul>"#,
+            "<ul id=\"nav\" class=\"list\">\n  <li>Home</li>\n  <li>\n    About us\n  </li>\n</ul>",
+        ));
+        assert!(html_semantically_equal(
+            "<p>Hi <b>there</b></p>",
+            "<p>Hi  <b>there</b> </p>"
+        ));
+    }
+
+    #[test]
+    fn test_html_semantically_equal_detects_differences() {
+        assert!(!html_semantically_equal(
+            r#"<a href="/a">x</a>"#,
+            r#"<a href="/b">x</a>"#
+        ));
+        assert!(!html_semantically_equal(
+            "<p>Hi <b>there</b></p>",
+            "<p>Hi<b>there</b></p>"
+        ));
+        assert!(!html_semantically_equal("<p>One</p>", "<div>One</div>"));
+        assert!(!html_semantically_equal("<p>One</p>", "<p>One"));
+    }
+}
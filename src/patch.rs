@@ -0,0 +1,120 @@
+//! The DOM side of reconciliation: applies the patches produced by `diff` to a live
+//! DOM subtree.
+
+use wasm_bindgen::JsCast;
+use web_sys::{Document, DocumentFragment, Element as DomElement, Node as DomNode};
+
+use crate::event::{bind_listeners, cancel_detached_timers};
+use crate::{Node, Patch, inherited_namespace, render_node_to_dom_in};
+
+/// Applies `patches` in order to the DOM subtree rooted at `root`, which must be the
+/// DOM node the old VDOM root was rendered to. `RootHandle::update` is `diff` followed
+/// by this; calling the two separately lets tests check each half of reconciliation.
+pub fn apply_patches(patches: &[Patch], root: &DomNode, document: &Document) {
+    for patch in patches {
+        match patch {
+            Patch::Replace { path, node } => {
+                let target = resolve(root, path);
+                let parent = target
+                    .parent_node()
+                    .expect("Cannot replace a detached node");
+                parent
+                    .replace_child(&render_fragment(node, document, &parent), &target)
+                    .expect("Failed to replace node");
+            }
+            Patch::ReplaceText { path, text } => {
+                resolve(root, path).set_text_content(Some(text));
+            }
+            Patch::SetAttr { path, name, value } => {
+                as_element(&resolve(root, path))
+                    .set_attribute(name, value)
+                    .expect("Failed to set attribute");
+            }
+            Patch::RemoveAttr { path, name } => {
+                as_element(&resolve(root, path))
+                    .remove_attribute(name)
+                    .expect("Failed to remove attribute");
+            }
+            Patch::InsertChild { path, index, node } => {
+                insert_node_at(&resolve(root, path), node, *index, document);
+            }
+            Patch::RemoveChild { path, index } => {
+                let parent = resolve(root, path);
+                parent
+                    .remove_child(&child_at(&parent, *index))
+                    .expect("Failed to remove child");
+            }
+            Patch::MoveChild {
+                path,
+                from,
+                count,
+                to,
+            } => {
+                let parent = resolve(root, path);
+                let moved = document.create_document_fragment();
+                for _ in 0..*count {
+                    moved
+                        .append_child(&child_at(&parent, *from))
+                        .expect("Failed to detach moved child");
+                }
+                let reference = parent.child_nodes().item(*to as u32);
+                parent
+                    .insert_before(&moved, reference.as_ref())
+                    .expect("Failed to move child");
+            }
+            Patch::UpdateClasses { path, add, remove } => {
+                let classes = as_element(&resolve(root, path)).class_list();
+                for name in remove {
+                    classes.remove_1(name).expect("Failed to remove class");
+                }
+                for name in add {
+                    classes.add_1(name).expect("Failed to add class");
+                }
+            }
+            Patch::SetListeners { path, listeners } => {
+                bind_listeners(as_element(&resolve(root, path)), listeners);
+            }
+        }
+    }
+    let removes =
+        |patch: &Patch| matches!(patch, Patch::Replace { .. } | Patch::RemoveChild { .. });
+    if patches.iter().any(removes) {
+        cancel_detached_timers();
+    }
+}
+
+/// Renders `node` and inserts it so that its first DOM node becomes child `index` of
+/// `parent`, in front of the child currently at that position. An `index` past the
+/// last child appends.
+pub fn insert_node_at(parent: &DomNode, node: &Node, index: usize, document: &Document) {
+    let reference = parent.child_nodes().item(index as u32);
+    parent
+        .insert_before(&render_fragment(node, document, parent), reference.as_ref())
+        .expect("Failed to insert child");
+}
+
+/// Follows `path` from `root` down through child indices.
+pub(crate) fn resolve(root: &DomNode, path: &[usize]) -> DomNode {
+    path.iter()
+        .fold(root.clone(), |node, &index| child_at(&node, index))
+}
+
+fn child_at(parent: &DomNode, index: usize) -> DomNode {
+    parent
+        .child_nodes()
+        .item(index as u32)
+        .unwrap_or_else(|| panic!("Patch refers to missing child {}", index))
+}
+
+fn as_element(node: &DomNode) -> &DomElement {
+    node.dyn_ref::<DomElement>()
+        .expect("Attribute patch targets a non-element node")
+}
+
+/// Renders `node` off-document so it can be inserted into `parent` with a single DOM
+/// operation. Elements are created in the namespace `parent`'s children belong to.
+fn render_fragment(node: &Node, document: &Document, parent: &DomNode) -> DocumentFragment {
+    let fragment = document.create_document_fragment();
+    render_node_to_dom_in(node, document, &fragment, inherited_namespace(parent));
+    fragment
+}
@@ -0,0 +1,303 @@
+//! An indented HTML renderer for debugging output and readable snapshots.
+
+use crate::commit::commit_on_server;
+use crate::escape::{escape_text, is_void_element};
+use crate::{
+    CLIENT_ONLY_PLACEHOLDER, Element, Fragment, Node, RawHtml, end_tag, render_node_to_string,
+    write_attr, write_start_tag,
+};
+
+/// Controls the layout of `render_node_to_pretty_string_with`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrettyOptions {
+    /// Number of spaces per nesting level.
+    pub indent: usize,
+    /// Tags that flow with text. Adding whitespace around them would change the
+    /// rendered layout, so runs of them are always kept on a single line.
+    pub inline_tags: Vec<String>,
+    /// Put each attribute on its own line, aligned under the first one, when an element
+    /// has more than this many. `None` (the default) keeps start tags on one line.
+    pub wrap_attributes_over: Option<usize>,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> Self {
+        PrettyOptions {
+            indent: 2,
+            inline_tags: ["a", "span", "b", "i", "em", "strong", "code"]
+                .iter()
+                .map(|tag| tag.to_string())
+                .collect(),
+            wrap_attributes_over: None,
+        }
+    }
+}
+
+/// Renders `node` as indented HTML using the default `PrettyOptions`.
+pub fn render_node_to_pretty_string(node: &Node) -> String {
+    render_node_to_pretty_string_with(node, &PrettyOptions::default())
+}
+
+/// Renders `node` with one block element per line.
+///
+/// Text and inline elements are never separated by added whitespace, and `<pre>` and
+/// `<textarea>`, whose whitespace is content, are printed compactly, so the output
+/// displays exactly like the compact `render_node_to_string` output.
+pub fn render_node_to_pretty_string_with(node: &Node, options: &PrettyOptions) -> String {
+    let mut lines = Vec::new();
+    // Committed like `render_node_to_string` commits, so `use_id` gives the same ids.
+    write_pretty(&expand(&commit_on_server(node)), 0, options, &mut lines);
+    lines.join("\n")
+}
+
+/// Replaces components and lazy nodes by what they render and splices fragments into
+/// their parent, so every renderable node is inspected exactly once.
+fn expand(node: &Node) -> Node {
+    match node {
+        Node::Component(v_component) => expand(&v_component.render()),
+        Node::Lazy(lazy) => expand(&lazy.render()),
+        Node::Async(v_async) => expand(&v_async.fallback()),
+        Node::ClientOnly(_) => Node::RawHtml(RawHtml::new(CLIENT_ONLY_PLACEHOLDER)),
+        Node::ServerOnly(inner) => expand(inner),
+        Node::Element(element) => Node::Element(Element {
+            children: expand_children(&element.children),
+            ..element.clone()
+        }),
+        Node::Fragment(fragment) => Node::Fragment(Fragment {
+            children: expand_children(&fragment.children),
+            key: fragment.key.clone(),
+        }),
+        Node::Text(_) | Node::RawHtml(_) => node.clone(),
+    }
+}
+
+fn expand_children(children: &[Node]) -> Vec<Node> {
+    let mut expanded = Vec::with_capacity(children.len());
+    for child in children {
+        match expand(child) {
+            Node::Fragment(fragment) => expanded.extend(fragment.children),
+            child => expanded.push(child),
+        }
+    }
+    expanded
+}
+
+/// Elements whose whitespace is part of their content, so none can be added inside.
+const WHITESPACE_TAGS: &[&str] = &["pre", "textarea"];
+
+fn is_inline(node: &Node, options: &PrettyOptions) -> bool {
+    match node {
+        Node::Text(_) => true,
+        Node::Element(element) => options.inline_tags.contains(&element.tag_name),
+        _ => false,
+    }
+}
+
+fn write_pretty(node: &Node, depth: usize, options: &PrettyOptions, lines: &mut Vec<String>) {
+    let indent = " ".repeat(depth * options.indent);
+    let element = match node {
+        Node::Element(element)
+            if !is_void_element(&element.tag_name)
+                && !WHITESPACE_TAGS
+                    .iter()
+                    .any(|tag| element.tag_name.eq_ignore_ascii_case(tag))
+                && !element
+                    .children
+                    .iter()
+                    .all(|child| is_inline(child, options)) =>
+        {
+            element
+        }
+        Node::Text(text) => return lines.push(format!("{}{}", indent, escape_text(text))),
+        Node::Fragment(fragment) => {
+            for child in &fragment.children {
+                write_pretty(child, depth, options, lines);
+            }
+            return;
+        }
+        // An element containing only inline content stays compact, apart from its
+        // start tag if that gets wrapped.
+        Node::Element(element) if wraps_attributes(element, options) => {
+            let mut tag_lines = start_tag_lines(element, &indent);
+            let mut last = tag_lines.pop().expect("A start tag has at least one line");
+            if !is_void_element(&element.tag_name) {
+                for child in &element.children {
+                    last.push_str(&render_node_to_string(child));
+                }
+            }
+            last.push_str(&end_tag(element));
+            lines.extend(tag_lines);
+            return lines.push(last);
+        }
+        // Inline content, or an element containing only inline content, stays compact.
+        _ => return lines.push(format!("{}{}", indent, render_node_to_string(node))),
+    };
+
+    if wraps_attributes(element, options) {
+        lines.extend(start_tag_lines(element, &indent));
+    } else {
+        let mut start_tag = Vec::new();
+        write_start_tag(element, &mut start_tag).expect("writing to a Vec<u8> cannot fail");
+        lines.push(format!("{}{}", indent, String::from_utf8_lossy(&start_tag)));
+    }
+
+    let mut inline_run = String::new();
+    let child_indent = " ".repeat((depth + 1) * options.indent);
+    for child in &element.children {
+        if is_inline(child, options) {
+            inline_run.push_str(&render_node_to_string(child));
+            continue;
+        }
+        if !inline_run.is_empty() {
+            lines.push(format!(
+                "{}{}",
+                child_indent,
+                std::mem::take(&mut inline_run)
+            ));
+        }
+        write_pretty(child, depth + 1, options, lines);
+    }
+    if !inline_run.is_empty() {
+        lines.push(format!("{}{}", child_indent, inline_run));
+    }
+
+    lines.push(format!("{}</{}>", indent, element.tag_name));
+}
+
+fn wraps_attributes(element: &Element, options: &PrettyOptions) -> bool {
+    options
+        .wrap_attributes_over
+        .is_some_and(|limit| element.props.len() > limit)
+}
+
+/// The start tag with one attribute per line, each aligned under the first.
+fn start_tag_lines(element: &Element, indent: &str) -> Vec<String> {
+    let align = " ".repeat(element.tag_name.len() + 2);
+    let mut lines: Vec<String> = element
+        .props
+        .iter()
+        .enumerate()
+        .map(|(i, (name, value))| {
+            let mut attr = Vec::new();
+            write_attr(name, value, &mut attr).expect("writing to a Vec<u8> cannot fail");
+            let attr = String::from_utf8_lossy(&attr);
+            if i == 0 {
+                format!("{}<{} {}", indent, element.tag_name, attr)
+            } else {
+                format!("{}{}{}", indent, align, attr)
+            }
+        })
+        .collect();
+    if let Some(last) = lines.last_mut() {
+        last.push('>');
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Component, component, use_id};
+    use std::rc::Rc;
+
+    #[test]
+    fn test_block_children_are_indented() {
+        let tree = Node::Element(
+            Element::new("main")
+                .child(Element::new("section").child(Element::new("p").child("Hi"))),
+        );
+
+        assert_eq!(
+            render_node_to_pretty_string(&tree),
+            "<main>\n  <section>\n    <p>Hi</p>\n  </section>\n</main>"
+        );
+    }
+
+    #[test]
+    fn test_inline_elements_are_not_separated_by_whitespace() {
+        let tree = Node::Element(
+            Element::new("div")
+                .child(Element::new("b").child("Bold"))
+                .child(Element::new("em").child("and"))
+                .child("text")
+                .child(
+                    Element::new("p")
+                        .child("Read ")
+                        .child(Element::new("a").child("this")),
+                )
+                .child(Element::new("code").child("x")),
+        );
+
+        assert_eq!(
+            render_node_to_pretty_string(&tree),
+            "<div>\n  <b>Bold</b><em>and</em>text\n  <p>Read <a>this</a></p>\n  <code>x</code>\n</div>"
+        );
+    }
+
+    #[test]
+    fn test_long_attribute_lists_are_wrapped() {
+        let tree = Node::Element(
+            Element::new("form").child(
+                Element::new("button")
+                    .attr("type", "submit")
+                    .attr("class", "primary")
+                    .attr("name", "action")
+                    .attr("value", "save")
+                    .child("Save"),
+            ),
+        );
+        let options = PrettyOptions {
+            wrap_attributes_over: Some(3),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render_node_to_pretty_string_with(&tree, &options),
+            "<form>\n  <button type=\"submit\"\n          class=\"primary\"\n          \
+             name=\"action\"\n          value=\"save\">Save</button>\n</form>"
+        );
+    }
+
+    #[test]
+    fn test_preformatted_content_stays_compact() {
+        let tree = Node::Element(
+            Element::new("main").child(
+                Element::new("pre")
+                    .child(Element::new("div").child("fn main() {"))
+                    .child(Element::new("div").child("}")),
+            ),
+        );
+
+        assert_eq!(
+            render_node_to_pretty_string(&tree),
+            "<main>\n  <pre><div>fn main() {</div><div>}</div></pre>\n</main>"
+        );
+    }
+
+    #[derive(Debug, Clone)]
+    struct Field;
+
+    impl Component for Field {
+        fn render(&self) -> Node {
+            Node::Element(Element::new("input").attr("id", use_id()))
+        }
+
+        fn clone_rc(&self) -> Rc<dyn Component> {
+            Rc::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_pretty_output_keeps_use_id_ids_apart() {
+        let form = Node::Element(
+            Element::new("form")
+                .child(component(Field))
+                .child(component(Field)),
+        );
+
+        assert_eq!(
+            render_node_to_pretty_string(&form),
+            "<form>\n  <input id=\"rr-id-0\">\n  <input id=\"rr-id-1\">\n</form>"
+        );
+    }
+}
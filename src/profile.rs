@@ -0,0 +1,62 @@
+//! Render counts per component type, for spotting components that re-render too often.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+thread_local! {
+    static RENDER_COUNTS: RefCell<HashMap<&'static str, usize>> = RefCell::new(HashMap::new());
+}
+
+/// Called for every component rendered while committing a tree.
+pub(crate) fn record_render(type_name: &'static str) {
+    RENDER_COUNTS.with(|counts| *counts.borrow_mut().entry(type_name).or_insert(0) += 1);
+}
+
+/// How many times each component type has rendered since the page loaded (or since
+/// `reset_render_counts`), keyed by `std::any::type_name`. A mount, an update or a
+/// string render counts one render per component in the tree; the library's own
+/// passes over a rendered tree (the diff, warnings, fingerprints) don't count.
+pub fn render_counts() -> HashMap<String, usize> {
+    RENDER_COUNTS.with(|counts| {
+        let counts = counts.borrow();
+        counts
+            .iter()
+            .map(|(name, count)| (name.to_string(), *count))
+            .collect()
+    })
+}
+
+/// Starts counting from zero again, e.g. right before the interaction being measured.
+pub fn reset_render_counts() {
+    RENDER_COUNTS.with(|counts| counts.borrow_mut().clear());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commit::commit_in;
+    use crate::hooks::HookTree;
+    use crate::{App, Node, component, diff, render_node_to_string};
+
+    #[test]
+    fn test_render_counts_tracks_each_render() {
+        let app = Node::Component(component(App));
+        render_node_to_string(&app);
+        render_node_to_string(&app);
+        assert_eq!(render_counts().get(std::any::type_name::<App>()), Some(&2));
+
+        reset_render_counts();
+        assert!(render_counts().is_empty());
+    }
+
+    #[test]
+    fn test_passes_over_a_committed_tree_do_not_count() {
+        let app = Node::Component(component(App));
+        let committed = commit_in(&app, &HookTree::default());
+        render_node_to_string(&committed);
+        diff(&committed, &committed);
+        committed.fingerprint();
+
+        assert_eq!(render_counts().get(std::any::type_name::<App>()), Some(&1));
+    }
+}
@@ -0,0 +1,115 @@
+//! Element attributes, kept in the order they were added.
+
+use std::ops::Index;
+
+/// An element's props. It behaves like a small map, but remembers insertion order so
+/// that rendered attributes come out in the order the builder added them. Setting a
+/// prop that already exists replaces its value in place.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Props {
+    entries: Vec<(String, String)>,
+}
+
+impl Props {
+    pub fn new() -> Self {
+        Props::default()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&String> {
+        self.entries
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Sets `name` to `value`, returning the previous value if there was one. A new
+    /// prop goes to the end; an existing one keeps its position.
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) -> Option<String> {
+        let name = name.into();
+        let value = value.into();
+        match self.entries.iter_mut().find(|(key, _)| *key == name) {
+            Some((_, existing)) => Some(std::mem::replace(existing, value)),
+            None => {
+                self.entries.push((name, value));
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let index = self.entries.iter().position(|(key, _)| key == name)?;
+        Some(self.entries.remove(index).1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+}
+
+impl Index<&str> for Props {
+    type Output = String;
+
+    fn index(&self, name: &str) -> &String {
+        self.get(name)
+            .unwrap_or_else(|| panic!("No prop named '{}'", name))
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Props {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut props = Props::new();
+        for (name, value) in iter {
+            props.insert(name, value);
+        }
+        props
+    }
+}
+
+impl<K: Into<String>, V: Into<String>, const N: usize> From<[(K, V); N]> for Props {
+    fn from(entries: [(K, V); N]) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+impl<'a> IntoIterator for &'a Props {
+    type Item = (&'a String, &'a String);
+    type IntoIter = std::iter::Map<
+        std::slice::Iter<'a, (String, String)>,
+        fn(&'a (String, String)) -> (&'a String, &'a String),
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_keeps_first_position_when_replacing() {
+        let mut props = Props::from([("id", "a"), ("class", "b")]);
+        assert_eq!(props.insert("id", "c"), Some("a".to_string()));
+
+        let names: Vec<_> = props.keys().map(String::as_str).collect();
+        assert_eq!(names, ["id", "class"]);
+        assert_eq!(props["id"], "c");
+    }
+}
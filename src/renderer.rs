@@ -0,0 +1,47 @@
+//! Rendering backends. The VDOM itself knows nothing about where it ends up; a
+//! `Renderer` decides what a `Node` turns into.
+
+use web_sys::{Document, Node as DomNode};
+
+use crate::{Node, render_node_to_dom, with_aria_defaults};
+
+/// A target that VDOM trees can be rendered into.
+pub trait Renderer {
+    /// Renders `node` (and everything below it) into the target.
+    fn render(&mut self, node: &Node);
+}
+
+/// The default backend: appends real DOM nodes under `parent`.
+#[derive(Debug, Clone)]
+pub struct DomRenderer {
+    document: Document,
+    parent: DomNode,
+    aria_defaults: bool,
+}
+
+impl DomRenderer {
+    pub fn new(document: Document, parent: DomNode) -> Self {
+        DomRenderer {
+            document,
+            parent,
+            aria_defaults: false,
+        }
+    }
+
+    /// Opts into the ARIA defaults of `with_aria_defaults`, e.g. `role="button"` on
+    /// clickable `div`s. Off by default.
+    pub fn aria_defaults(mut self, enabled: bool) -> Self {
+        self.aria_defaults = enabled;
+        self
+    }
+}
+
+impl Renderer for DomRenderer {
+    fn render(&mut self, node: &Node) {
+        if self.aria_defaults {
+            render_node_to_dom(&with_aria_defaults(node), &self.document, &self.parent);
+        } else {
+            render_node_to_dom(node, &self.document, &self.parent);
+        }
+    }
+}
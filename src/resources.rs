@@ -0,0 +1,142 @@
+//! Finding the external resources a tree refers to, e.g. to emit preload hints.
+
+use crate::Node;
+
+/// The URLs in a tree's `src`, `href` and `srcset` attributes, grouped by what loads
+/// them. Each list is in document order with duplicates removed. Same-page anchors
+/// (`#top`) and inline `data:` and `javascript:` URLs are left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Resources {
+    /// `<img>` and `<source>` URLs, including every candidate of a `srcset`.
+    pub images: Vec<String>,
+    pub scripts: Vec<String>,
+    /// `<link rel="stylesheet">` URLs.
+    pub stylesheets: Vec<String>,
+    /// Navigation targets of `<a>` and `<area>`, which are not loaded with the page.
+    pub links: Vec<String>,
+    /// Everything else: other `<link>` relations (icons, preloads), iframes, media, ...
+    pub other: Vec<String>,
+}
+
+impl Resources {
+    /// Every URL that is fetched when the page loads, i.e. all of them but `links`.
+    pub fn loaded(&self) -> impl Iterator<Item = &str> {
+        self.images
+            .iter()
+            .chain(&self.scripts)
+            .chain(&self.stylesheets)
+            .chain(&self.other)
+            .map(String::as_str)
+    }
+}
+
+/// Walks `node`, rendering components along the way, and gathers the URLs it refers to.
+pub fn collect_resources(node: &Node) -> Resources {
+    let mut resources = Resources::default();
+    collect(node, &mut resources);
+    resources
+}
+
+fn collect(node: &Node, resources: &mut Resources) {
+    match node {
+        Node::Element(element) => {
+            let tag = element.tag_name.to_ascii_lowercase();
+            for (name, value) in &element.props {
+                let urls: Vec<&str> = match name.as_str() {
+                    "src" | "href" => vec![value.as_str()],
+                    // "a.png 1x, a@2x.png 2x": the URL is the first word of each candidate.
+                    "srcset" => value
+                        .split(',')
+                        .filter_map(|candidate| candidate.split_whitespace().next())
+                        .collect(),
+                    _ => continue,
+                };
+                let list = match tag.as_str() {
+                    "img" | "source" if name == "src" || name == "srcset" => &mut resources.images,
+                    "script" => &mut resources.scripts,
+                    "link" if is_stylesheet(element.props.get("rel")) => &mut resources.stylesheets,
+                    "a" | "area" => &mut resources.links,
+                    _ => &mut resources.other,
+                };
+                for url in urls.into_iter().map(str::trim) {
+                    if is_external(url) && !list.iter().any(|known| known == url) {
+                        list.push(url.to_string());
+                    }
+                }
+            }
+            for child in &element.children {
+                collect(child, resources);
+            }
+        }
+        Node::Component(v_component) => collect(&v_component.render(), resources),
+        Node::Lazy(lazy) => collect(&lazy.render(), resources),
+        Node::Async(v_async) => collect(&v_async.fallback(), resources),
+        Node::ClientOnly(inner) | Node::ServerOnly(inner) => collect(inner, resources),
+        Node::Fragment(fragment) => {
+            for child in &fragment.children {
+                collect(child, resources);
+            }
+        }
+        Node::Text(_) | Node::RawHtml(_) => {}
+    }
+}
+
+fn is_stylesheet(rel: Option<&String>) -> bool {
+    rel.is_some_and(|rel| {
+        rel.split_whitespace()
+            .any(|token| token.eq_ignore_ascii_case("stylesheet"))
+    })
+}
+
+fn is_external(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    !url.is_empty()
+        && !url.starts_with('#')
+        && !lower.starts_with("data:")
+        && !lower.starts_with("javascript:")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Element;
+
+    #[test]
+    fn test_collect_resources_groups_urls() {
+        let page = Node::Element(
+            Element::new("html")
+                .child(
+                    Element::new("head")
+                        .child(
+                            Element::new("link")
+                                .attr("rel", "stylesheet")
+                                .attr("href", "/app.css"),
+                        )
+                        .child(
+                            Element::new("link")
+                                .attr("rel", "icon")
+                                .attr("href", "/favicon.ico"),
+                        )
+                        .child(Element::new("script").attr("src", "/app.js")),
+                )
+                .child(
+                    Element::new("body")
+                        .child(
+                            Element::new("img")
+                                .attr("src", "/hero.png")
+                                .attr("srcset", "/hero.png 1x, /hero@2x.png 2x"),
+                        )
+                        .child(Element::new("a").attr("href", "/about").child("About"))
+                        .child(Element::new("a").attr("href", "#top").child("Top")),
+                ),
+        );
+
+        let resources = collect_resources(&page);
+        assert_eq!(resources.images, ["/hero.png", "/hero@2x.png"]);
+        assert_eq!(resources.stylesheets, ["/app.css"]);
+        assert_eq!(resources.scripts, ["/app.js"]);
+        assert_eq!(resources.links, ["/about"]);
+        assert_eq!(resources.other, ["/favicon.ico"]);
+        assert_eq!(resources.loaded().count(), 5);
+    }
+}
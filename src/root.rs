@@ -0,0 +1,313 @@
+//! Mounted roots: the handle a user keeps to update what is on screen.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use wasm_bindgen::JsCast;
+use wasm_bindgen::closure::Closure;
+use web_sys::{
+    Document, Element as DomElement, HtmlElement, HtmlIFrameElement, HtmlInputElement, Window,
+};
+
+use crate::commit::commit_in;
+use crate::diff::slots;
+use crate::event::{cancel_detached_timers, field_value};
+use crate::hooks::HookTree;
+use crate::patch::{apply_patches, resolve};
+use crate::scheduler::{UpdateQueue, queue_microtask};
+use crate::warnings::{Warning, check_tree_once};
+use crate::{Element, Node, Patch, diff, render_node_to_dom};
+
+/// A VDOM tree mounted into a DOM element. Cloning the handle is cheap; all clones
+/// refer to the same root.
+#[derive(Clone)]
+pub struct RootHandle {
+    inner: Rc<RootInner>,
+}
+
+struct RootInner {
+    document: Document,
+    container: DomElement,
+    /// The VDOM that is currently reflected in the DOM, committed so its components
+    /// keep the output they were rendered with.
+    current: RefCell<Node>,
+    /// The hook state of the components in `current`.
+    hooks: HookTree,
+    /// `current.fingerprint()`, kept so an unchanged update can skip the diff.
+    fingerprint: Cell<u64>,
+    /// The warnings `check_tree` has found in this root so far.
+    reported: RefCell<HashSet<Warning>>,
+    /// The latest VDOM passed to `schedule_update` that has not been reconciled yet.
+    queued: UpdateQueue<Node>,
+}
+
+/// Whether the document has an element with id `id`, so callers can check before
+/// `mount` panics on a missing mount point.
+pub fn mount_point_exists(id: &str) -> bool {
+    web_sys::window()
+        .and_then(|window| window.document())
+        .is_some_and(|document| document.get_element_by_id(id).is_some())
+}
+
+/// Renders `node` into the element with id `mount_point_id`, replacing its content,
+/// and returns a handle for later updates.
+pub fn mount(node: Node, mount_point_id: &str) -> RootHandle {
+    let (document, container) = find_mount_point(mount_point_id);
+
+    let hooks = HookTree::default();
+    let node = commit_in(&node, &hooks);
+    container.set_inner_html("");
+    render_node_to_dom(&node, &document, &container);
+
+    RootHandle::new(document, container, node, hooks)
+}
+
+/// Renders `node` into the body of the iframe with id `iframe_id`, using the iframe's
+/// own document, for isolated previews. If the iframe is still loading its `src`, the
+/// render happens once it has loaded, since loading replaces the document.
+pub fn render_into_iframe(node: Node, iframe_id: &str) {
+    let (_, iframe) = find_mount_point(iframe_id);
+    let iframe: HtmlIFrameElement = iframe
+        .dyn_into()
+        .unwrap_or_else(|_| panic!("Element with id '{}' is not an iframe", iframe_id));
+
+    match loaded_iframe_body(&iframe) {
+        Some((document, body)) => {
+            body.set_inner_html("");
+            render_node_to_dom(&node, &document, &body);
+        }
+        None => {
+            let target = iframe.clone();
+            let on_load = Closure::once_into_js(move || {
+                target.set_onload(None);
+                let (document, body) = loaded_iframe_body(&target)
+                    .expect("iframe has no accessible document after loading");
+                body.set_inner_html("");
+                render_node_to_dom(&node, &document, &body);
+            });
+            iframe.set_onload(Some(on_load.unchecked_ref()));
+        }
+    }
+}
+
+/// Renders `node` into the element with id `mount_id` in another window's document,
+/// e.g. a popup from `window.open`, and returns a handle for later updates. The DOM
+/// nodes are created by that document, so they belong to the popup rather than to
+/// the window running the app.
+pub fn render_into_window(node: Node, other_window: &Window, mount_id: &str) -> RootHandle {
+    let document = other_window
+        .document()
+        .expect("The other window has no accessible document");
+    let container = document.get_element_by_id(mount_id).unwrap_or_else(|| {
+        panic!(
+            "Mount point with id '{}' not found in the other window",
+            mount_id
+        )
+    });
+
+    let hooks = HookTree::default();
+    let node = commit_in(&node, &hooks);
+    container.set_inner_html("");
+    render_node_to_dom(&node, &document, &container);
+
+    RootHandle::new(document, container, node, hooks)
+}
+
+/// The iframe's document and body, unless it is still showing the initial
+/// `about:blank` document while its `src` loads (or is cross-origin).
+fn loaded_iframe_body(iframe: &HtmlIFrameElement) -> Option<(Document, HtmlElement)> {
+    let document = iframe.content_document()?;
+    let src = iframe.get_attribute("src").unwrap_or_default();
+    let initial = document.url().is_ok_and(|url| url == "about:blank");
+    if !src.is_empty() && src != "about:blank" && initial {
+        return None;
+    }
+    let body = document.body()?;
+    Some((document, body))
+}
+
+/// Looks up the mount point the same way `mount` does, panicking if it is missing.
+pub(crate) fn find_mount_point(mount_point_id: &str) -> (Document, DomElement) {
+    let window: Window = web_sys::window().expect("no global `window` exists");
+    let document: Document = window.document().expect("should have a document on window");
+
+    let container: DomElement = document
+        .get_element_by_id(mount_point_id)
+        .unwrap_or_else(|| panic!("Mount point with id '{}' not found", mount_point_id));
+    (document, container)
+}
+
+impl RootHandle {
+    /// Wraps a container whose DOM already reflects `node`, which has been committed
+    /// with `hooks`.
+    pub(crate) fn new(
+        document: Document,
+        container: DomElement,
+        node: Node,
+        hooks: HookTree,
+    ) -> Self {
+        let handle = RootHandle {
+            inner: Rc::new(RootInner {
+                document,
+                container,
+                fingerprint: Cell::new(node.fingerprint()),
+                current: RefCell::new(node),
+                hooks,
+                reported: RefCell::default(),
+                queued: UpdateQueue::new(),
+            }),
+        };
+        handle.check(&handle.inner.current.borrow());
+        handle
+    }
+
+    /// Runs `check_tree` on a committed tree in debug builds, reporting each problem
+    /// once per root rather than on every update.
+    fn check(&self, node: &Node) {
+        if cfg!(debug_assertions) {
+            check_tree_once(node, &self.inner.reported);
+        }
+    }
+
+    /// The element the tree is mounted into.
+    pub fn container(&self) -> &DomElement {
+        &self.inner.container
+    }
+
+    /// Collects the current values of the named fields inside the form with id
+    /// `form_id`, keyed by their `name` attribute. Unchecked checkboxes and radio buttons
+    /// are left out, like in a real form submission. A missing form gives an empty map.
+    pub fn form_values(&self, form_id: &str) -> HashMap<String, String> {
+        let mut values = HashMap::new();
+        let Some(form) = self
+            .inner
+            .document
+            .get_element_by_id(form_id)
+            .filter(|form| self.inner.container.contains(Some(form)))
+        else {
+            return values;
+        };
+
+        let fields = form
+            .query_selector_all("input[name], textarea[name], select[name]")
+            .expect("Field selector is valid");
+        for field in (0..fields.length()).filter_map(|i| fields.item(i)) {
+            if let Some(input) = field.dyn_ref::<HtmlInputElement>() {
+                let toggle = matches!(input.type_().as_str(), "checkbox" | "radio");
+                if toggle && !input.checked() {
+                    continue;
+                }
+            }
+            let Some(value) = field_value(&field) else {
+                continue;
+            };
+            let name = field.unchecked_ref::<DomElement>().get_attribute("name");
+            values.insert(name.unwrap_or_default(), value);
+        }
+        values
+    }
+
+    /// The live DOM element rendered for the element (or component) with reconciliation
+    /// key `key`, for focusing or measuring it. The position is worked out from the
+    /// current VDOM the same way the diff does, so it stays right across updates.
+    pub fn get_element_by_key(&self, key: &str) -> Option<DomElement> {
+        let current = self.inner.current.borrow();
+        let path = find_key_path(std::slice::from_ref(&*current), key, &mut Vec::new())?;
+        resolve(&self.inner.container, &path).dyn_into().ok()
+    }
+
+    /// Diffs `new_node` against the VDOM rendered last time and patches only what
+    /// changed, then remembers `new_node` for the next update.
+    pub fn update(&self, new_node: Node) {
+        // An immediate update supersedes anything still waiting in the queue.
+        self.inner.queued.take();
+        self.reconcile(new_node);
+    }
+
+    /// Like `update`, but deferred to the end of the current tick. When several updates
+    /// are scheduled in the same tick only the last one is reconciled.
+    pub fn schedule_update(&self, new_node: Node) {
+        if self.inner.queued.enqueue(new_node) {
+            let handle = self.clone();
+            queue_microtask(move || {
+                handle.flush();
+            });
+        }
+    }
+
+    /// Reconciles a scheduled update right away instead of waiting for the end of the
+    /// tick. Returns `false` if nothing was scheduled.
+    pub fn flush(&self) -> bool {
+        match self.inner.queued.take() {
+            Some(new_node) => {
+                self.reconcile(new_node);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn reconcile(&self, new_node: Node) {
+        // The old tree is committed, so rendering it again gives what is on screen
+        // rather than what its components would render from the current state.
+        let new_node = commit_in(&new_node, &self.inner.hooks);
+        self.check(&new_node);
+        // Hashing one tree is much cheaper than diffing two, and re-rendering the same
+        // state is common (e.g. a store notifying about an unrelated field).
+        let fingerprint = new_node.fingerprint();
+        if fingerprint == self.inner.fingerprint.get() {
+            *self.inner.current.borrow_mut() = new_node;
+            return;
+        }
+        // Diffing the container's children rather than the root node itself keeps the
+        // paths valid even when the root renders a fragment.
+        let old_root = in_container(self.inner.current.borrow().clone());
+        let patches = diff(&old_root, &in_container(new_node.clone()));
+        let replaces_root = patches
+            .iter()
+            .any(|patch| matches!(patch, Patch::Replace { path, .. } if path.is_empty()));
+        if replaces_root {
+            // Raw markup makes the diff replace the whole stand-in container, which is
+            // not a real element; render the new content into the container instead.
+            self.inner.container.set_inner_html("");
+            render_node_to_dom(&new_node, &self.inner.document, &self.inner.container);
+            cancel_detached_timers();
+        } else if !patches.is_empty() {
+            apply_patches(&patches, &self.inner.container, &self.inner.document);
+        }
+        *self.inner.current.borrow_mut() = new_node;
+        self.inner.fingerprint.set(fingerprint);
+    }
+}
+
+/// The DOM path, relative to the parent of `children`, of the first element keyed `key`,
+/// or rendered at the top of a component keyed `key`.
+fn find_key_path(children: &[Node], key: &str, path: &mut Vec<usize>) -> Option<Vec<usize>> {
+    let mut index = 0;
+    for slot in slots(children) {
+        for (offset, node) in slot.nodes.iter().enumerate() {
+            if let Node::Element(element) = node {
+                path.push(index);
+                let slot_keyed = offset == 0 && slot.key.as_deref() == Some(key);
+                if slot_keyed || element.key.as_deref() == Some(key) {
+                    return Some(path.clone());
+                }
+                if let Some(found) = find_key_path(&element.children, key, path) {
+                    return Some(found);
+                }
+                path.pop();
+            }
+            index += 1;
+        }
+    }
+    None
+}
+
+/// Stands in for the container element so that `node` is diffed as its only child.
+fn in_container(node: Node) -> Node {
+    Node::Element(Element {
+        children: vec![node],
+        ..Default::default()
+    })
+}
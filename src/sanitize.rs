@@ -0,0 +1,367 @@
+//! A small, dependency-free HTML sanitizer for semi-trusted markup.
+//!
+//! It is not a full HTML5 parser. The tokenizer below understands just enough
+//! of the syntax (tags, attributes, comments, raw-text elements) to drop the
+//! parts of a document that can execute script, and re-serializes the rest.
+
+use crate::escape::{escape_attr, unescape};
+
+/// Describes what the sanitizer strips from raw HTML.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SanitizePolicy {
+    /// Elements that are removed together with everything inside them.
+    pub blocked_tags: Vec<String>,
+    /// Remove every `on*` attribute (`onclick`, `onerror`, ...).
+    pub strip_event_handlers: bool,
+    /// Remove URL attributes unless they are relative or use the `http:`, `https:` or
+    /// `mailto:` scheme, which rules out `javascript:` links however they are encoded.
+    pub strip_javascript_urls: bool,
+}
+
+impl Default for SanitizePolicy {
+    fn default() -> Self {
+        SanitizePolicy {
+            blocked_tags: ["script", "style", "iframe", "object", "embed", "link", "meta", "base"]
+                .iter()
+                .map(|tag| tag.to_string())
+                .collect(),
+            strip_event_handlers: true,
+            strip_javascript_urls: true,
+        }
+    }
+}
+
+/// Attributes that carry a URL and can therefore smuggle in a `javascript:` link.
+const URL_ATTRIBUTES: &[&str] = &["href", "src", "action", "formaction", "xlink:href"];
+
+/// The schemes a URL attribute may use when `strip_javascript_urls` is set.
+const ALLOWED_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// Cleans `html` according to `policy` and returns the re-serialized markup.
+pub fn sanitize_html(html: &str, policy: &SanitizePolicy) -> String {
+    let mut output = String::with_capacity(html.len());
+    // While inside a blocked element we track its name and nesting depth and emit nothing.
+    let mut skipping: Option<(String, usize)> = None;
+
+    for token in Tokenizer::new(html) {
+        if let Some((blocked, depth)) = &mut skipping {
+            match &token {
+                Token::StartTag { name, self_closing: false, .. } if name == blocked => *depth += 1,
+                Token::EndTag { name } if name == blocked => {
+                    *depth -= 1;
+                    if *depth == 0 {
+                        skipping = None;
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        match token {
+            Token::Text(text) => output.push_str(text),
+            // Comments are dropped: legacy conditional comments can hide markup.
+            Token::Comment(_) | Token::Doctype(_) => {}
+            Token::StartTag { name, attrs, self_closing } => {
+                if policy.blocked_tags.iter().any(|tag| tag.eq_ignore_ascii_case(&name)) {
+                    if !self_closing {
+                        skipping = Some((name, 1));
+                    }
+                    continue;
+                }
+                output.push('<');
+                output.push_str(&name);
+                for (key, value) in attrs {
+                    if !attribute_allowed(&key, value, policy) {
+                        continue;
+                    }
+                    output.push(' ');
+                    output.push_str(&key);
+                    if let Some(value) = value {
+                        // Decoded and re-escaped so no quote in the value can end the attribute.
+                        output.push_str("=\"");
+                        output.push_str(&escape_attr(&unescape(value)));
+                        output.push('"');
+                    }
+                }
+                if self_closing {
+                    output.push_str(" /");
+                }
+                output.push('>');
+            }
+            Token::EndTag { name } => {
+                if !policy.blocked_tags.iter().any(|tag| tag.eq_ignore_ascii_case(&name)) {
+                    output.push_str("</");
+                    output.push_str(&name);
+                    output.push('>');
+                }
+            }
+        }
+    }
+
+    output
+}
+
+fn attribute_allowed(key: &str, value: Option<&str>, policy: &SanitizePolicy) -> bool {
+    if policy.strip_event_handlers && key.len() > 2 && key.get(..2).is_some_and(|p| p.eq_ignore_ascii_case("on")) {
+        return false;
+    }
+    if policy.strip_javascript_urls
+        && URL_ATTRIBUTES.iter().any(|attr| attr.eq_ignore_ascii_case(key))
+    {
+        return url_allowed(value.unwrap_or(""));
+    }
+    true
+}
+
+/// Whether `url` is relative or uses one of the `ALLOWED_SCHEMES`, judged the way a
+/// browser reads it: after decoding character references (`&#106;avascript:`) and
+/// dropping the whitespace and control characters it ignores inside a scheme.
+fn url_allowed(url: &str) -> bool {
+    let url: String = unescape(url)
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect();
+    // The scheme, if any, ends at the first ':' before the path, query or fragment.
+    let head = &url[..url.find(['/', '?', '#']).unwrap_or(url.len())];
+    if head.contains('&') {
+        // A reference we could not decode (`&colon`, `&#58` without `;`, ...) might
+        // still hide a ':' from us.
+        return false;
+    }
+    match head.split_once(':') {
+        Some((scheme, _)) => ALLOWED_SCHEMES
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(scheme)),
+        None => true,
+    }
+}
+
+/// A single lexical unit of HTML. Text and attribute values borrow from the input
+/// and are left exactly as written (entities are not decoded).
+#[derive(Debug, PartialEq)]
+pub(crate) enum Token<'a> {
+    StartTag {
+        name: String,
+        attrs: Vec<(String, Option<&'a str>)>,
+        self_closing: bool,
+    },
+    EndTag {
+        name: String,
+    },
+    Text(&'a str),
+    Comment(&'a str),
+    Doctype(&'a str),
+}
+
+/// Elements whose content is raw text that must not be tokenized as markup.
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style"];
+
+pub(crate) struct Tokenizer<'a> {
+    input: &'a str,
+    pos: usize,
+    /// Set after a raw-text start tag; the next token is everything up to its end tag.
+    raw_text_end: Option<String>,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub(crate) fn new(input: &'a str) -> Self {
+        Tokenizer { input, pos: 0, raw_text_end: None }
+    }
+
+    /// How far into the input the next token starts, in bytes.
+    pub(crate) fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Consumes characters while `pred` holds and returns them.
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let end = rest.find(|c: char| !pred(c)).unwrap_or(rest.len());
+        self.pos += end;
+        &rest[..end]
+    }
+
+    fn read_tag(&mut self) -> Option<Token<'a>> {
+        let start = self.pos;
+        self.pos += 1; // '<'
+        let closing = self.rest().starts_with('/');
+        if closing {
+            self.pos += 1;
+        }
+        let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '-' || c == ':');
+        if name.is_empty() {
+            // Not a tag after all (e.g. "a < b"); rewind so it is treated as text.
+            self.pos = start;
+            return None;
+        }
+        let name = name.to_ascii_lowercase();
+
+        let mut attrs = Vec::new();
+        let mut self_closing = false;
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.is_empty() {
+                break;
+            } else if rest.starts_with('>') {
+                self.pos += 1;
+                break;
+            } else if rest.starts_with("/>") {
+                self.pos += 2;
+                self_closing = true;
+                break;
+            } else if rest.starts_with('/') {
+                self.pos += 1;
+                continue;
+            }
+
+            let key = self.take_while(|c| !c.is_whitespace() && !matches!(c, '=' | '>' | '/'));
+            if key.is_empty() {
+                // A stray '=' or similar; skip it so we always make progress.
+                self.pos += rest.chars().next().map_or(1, char::len_utf8);
+                continue;
+            }
+            self.skip_whitespace();
+            let value = if self.rest().starts_with('=') {
+                self.pos += 1;
+                self.skip_whitespace();
+                Some(self.read_attribute_value())
+            } else {
+                None
+            };
+            attrs.push((key.to_ascii_lowercase(), value));
+        }
+
+        if closing {
+            return Some(Token::EndTag { name });
+        }
+        if !self_closing && RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+            self.raw_text_end = Some(format!("</{}", name));
+        }
+        Some(Token::StartTag { name, attrs, self_closing })
+    }
+
+    fn read_attribute_value(&mut self) -> &'a str {
+        let rest = self.rest();
+        match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let body = &rest[1..];
+                let end = body.find(quote).unwrap_or(body.len());
+                self.pos += 1 + end + usize::from(end < body.len());
+                &body[..end]
+            }
+            _ => self.take_while(|c| !c.is_whitespace() && c != '>'),
+        }
+    }
+
+    /// Consumes up to and including `terminator`, returning what came before it.
+    fn read_until(&mut self, terminator: &str) -> &'a str {
+        let rest = self.rest();
+        match rest.find(terminator) {
+            Some(end) => {
+                self.pos += end + terminator.len();
+                &rest[..end]
+            }
+            None => {
+                self.pos = self.input.len();
+                rest
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let rest = self.rest();
+        if rest.is_empty() {
+            return None;
+        }
+
+        if let Some(end_tag) = self.raw_text_end.take() {
+            let lower = rest.to_ascii_lowercase();
+            let end = lower.find(&end_tag).unwrap_or(rest.len());
+            if end > 0 {
+                self.pos += end;
+                return Some(Token::Text(&rest[..end]));
+            }
+        }
+
+        if rest.starts_with("<!--") {
+            self.pos += 4;
+            return Some(Token::Comment(self.read_until("-->")));
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            self.pos += 2;
+            return Some(Token::Doctype(self.read_until(">")));
+        }
+        if rest.starts_with('<')
+            && let Some(tag) = self.read_tag()
+        {
+            return Some(tag);
+        }
+
+        // Plain text runs until the next '<' that is not the one we are standing on.
+        let first = rest.chars().next().map_or(1, char::len_utf8);
+        let end = rest[first..].find('<').map_or(rest.len(), |i| i + first);
+        self.pos += end;
+        Some(Token::Text(&rest[..end]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_strips_script_and_event_handlers() {
+        let dirty = r#"<p onclick="steal()">Hi<script>alert("x")</script></p><a href=" javascript:alert(1)">link</a>"#;
+        let clean = sanitize_html(dirty, &SanitizePolicy::default());
+        assert_eq!(clean, "<p>Hi</p><a>link</a>");
+    }
+
+    #[test]
+    fn test_sanitize_keeps_safe_markup() {
+        let html = r#"<ul class="list"><li>One &amp; two</li><li><img src="/a.png" alt="hi" /></li></ul>"#;
+        assert_eq!(sanitize_html(html, &SanitizePolicy::default()), html);
+    }
+
+    #[test]
+    fn test_sanitize_always_double_quotes_attribute_values() {
+        let dirty = r#"<img alt=x"'onerror=alert(1)//'>"#;
+        let clean = sanitize_html(dirty, &SanitizePolicy::default());
+        assert_eq!(clean, r#"<img alt="x&quot;'onerror=alert(1)//'">"#);
+        assert_eq!(
+            sanitize_html(r#"<p title='say "hi" &amp; bye'>x</p>"#, &SanitizePolicy::default()),
+            r#"<p title="say &quot;hi&quot; &amp; bye">x</p>"#
+        );
+    }
+
+    #[test]
+    fn test_sanitize_strips_encoded_javascript_urls() {
+        let policy = SanitizePolicy::default();
+        assert_eq!(sanitize_html(r#"<a href="&#106;avascript:alert(1)">x</a>"#, &policy), "<a>x</a>");
+        assert_eq!(sanitize_html(r#"<a href="javascript&colon;alert(1)">x</a>"#, &policy), "<a>x</a>");
+        assert_eq!(sanitize_html(r#"<a href="vbscript:run()">x</a>"#, &policy), "<a>x</a>");
+        let kept = r#"<a href="https://example.com/a?b=1&amp;c=2">x</a><a href="/docs#top">y</a><a href="mailto:a@b.c">z</a>"#;
+        assert_eq!(sanitize_html(kept, &policy), kept);
+    }
+
+    #[test]
+    fn test_tokenizer_treats_script_body_as_text() {
+        let tokens: Vec<_> = Tokenizer::new("<script>if (a < b) {}</script>").collect();
+        assert_eq!(tokens[1], Token::Text("if (a < b) {}"));
+        assert_eq!(tokens[2], Token::EndTag { name: "script".to_string() });
+    }
+}
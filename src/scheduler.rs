@@ -0,0 +1,164 @@
+//! Deferring work to the end of the current tick or to the next animation frame.
+
+use std::cell::RefCell;
+
+use wasm_bindgen::JsCast;
+use wasm_bindgen::closure::Closure;
+
+/// Holds at most one pending value; enqueueing again before it is taken replaces it.
+/// This is what lets several updates in one tick collapse into a single reconcile.
+#[derive(Debug)]
+pub(crate) struct UpdateQueue<T> {
+    pending: RefCell<Option<T>>,
+}
+
+impl<T> UpdateQueue<T> {
+    pub(crate) fn new() -> Self {
+        UpdateQueue {
+            pending: RefCell::new(None),
+        }
+    }
+
+    /// Stores `value`, dropping whatever was pending. Returns `true` when the queue was
+    /// empty, i.e. when the caller has to schedule a flush.
+    pub(crate) fn enqueue(&self, value: T) -> bool {
+        self.pending.borrow_mut().replace(value).is_none()
+    }
+
+    pub(crate) fn take(&self) -> Option<T> {
+        self.pending.borrow_mut().take()
+    }
+}
+
+/// Runs `task` once the current task and its microtasks finish, before the browser
+/// renders the next frame.
+pub(crate) fn queue_microtask(task: impl FnOnce() + 'static) {
+    let window = web_sys::window().expect("no global `window` exists");
+    let callback = Closure::once_into_js(task);
+    window.queue_microtask(callback.unchecked_ref());
+}
+
+type Task = Box<dyn FnOnce()>;
+
+/// The reads and writes collected for the next frame.
+#[derive(Default)]
+pub(crate) struct FrameQueue {
+    reads: RefCell<Vec<Task>>,
+    writes: RefCell<Vec<Task>>,
+}
+
+/// Collects the DOM reads of a `batch`, e.g. `getBoundingClientRect` calls.
+pub struct DomReads<'a>(&'a mut Vec<Task>);
+
+/// Collects the DOM writes of a `batch`, e.g. style or attribute changes.
+pub struct DomWrites<'a>(&'a mut Vec<Task>);
+
+impl DomReads<'_> {
+    pub fn read(&mut self, task: impl FnOnce() + 'static) {
+        self.0.push(Box::new(task));
+    }
+}
+
+impl DomWrites<'_> {
+    pub fn write(&mut self, task: impl FnOnce() + 'static) {
+        self.0.push(Box::new(task));
+    }
+}
+
+impl FrameQueue {
+    /// Adds the reads and writes `schedule` asks for. Returns `true` when the queue was
+    /// empty, i.e. when the caller has to schedule a flush.
+    pub(crate) fn batch(&self, schedule: impl FnOnce(&mut DomReads, &mut DomWrites)) -> bool {
+        let mut reads = Vec::new();
+        let mut writes = Vec::new();
+        schedule(&mut DomReads(&mut reads), &mut DomWrites(&mut writes));
+        let was_empty = self.reads.borrow().is_empty() && self.writes.borrow().is_empty();
+        self.reads.borrow_mut().extend(reads);
+        self.writes.borrow_mut().extend(writes);
+        was_empty && !(self.reads.borrow().is_empty() && self.writes.borrow().is_empty())
+    }
+
+    /// Runs every queued read, then every queued write. Work batched while this runs
+    /// waits for the next flush.
+    pub(crate) fn flush(&self) {
+        let reads = self.reads.take();
+        let writes = self.writes.take();
+        for read in reads {
+            read();
+        }
+        for write in writes {
+            write();
+        }
+    }
+}
+
+thread_local! {
+    static FRAME_QUEUE: FrameQueue = FrameQueue::default();
+}
+
+/// Runs DOM reads and writes without layout thrashing: the reads of every `batch`
+/// call made before the next animation frame run together in that frame, followed by
+/// all of their writes, so the browser computes layout once instead of after every
+/// write. Pass values from a read to a write through an `Rc<Cell<_>>`:
+/// `batch(|reader, writer| { reader.read(measure); writer.write(position) })`.
+pub fn batch(schedule: impl FnOnce(&mut DomReads, &mut DomWrites)) {
+    if FRAME_QUEUE.with(|queue| queue.batch(schedule)) {
+        let window = web_sys::window().expect("no global `window` exists");
+        let flush = Closure::once_into_js(|| FRAME_QUEUE.with(FrameQueue::flush));
+        window
+            .request_animation_frame(flush.unchecked_ref())
+            .expect("Failed to request an animation frame");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_queue_keeps_only_the_last_value() {
+        let queue = UpdateQueue::new();
+
+        assert!(queue.enqueue(1));
+        assert!(!queue.enqueue(2));
+        assert!(!queue.enqueue(3));
+
+        assert_eq!(queue.take(), Some(3));
+        assert_eq!(queue.take(), None);
+        assert!(queue.enqueue(4));
+    }
+
+    #[test]
+    fn test_frame_queue_runs_reads_before_writes() {
+        use std::rc::Rc;
+
+        let queue = FrameQueue::default();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let entry = |name: &'static str| {
+            let log = log.clone();
+            move || log.borrow_mut().push(name)
+        };
+
+        assert!(queue.batch(|reader, writer| {
+            writer.write(entry("write tooltip"));
+            reader.read(entry("read anchor"));
+        }));
+        assert!(!queue.batch(|reader, writer| {
+            reader.read(entry("read viewport"));
+            writer.write(entry("write arrow"));
+        }));
+        assert!(log.borrow().is_empty());
+
+        queue.flush();
+        assert_eq!(
+            *log.borrow(),
+            [
+                "read anchor",
+                "read viewport",
+                "write tooltip",
+                "write arrow"
+            ]
+        );
+        assert!(!queue.batch(|_, _| {}));
+    }
+}
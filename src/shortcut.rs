@@ -0,0 +1,137 @@
+//! App-wide keyboard shortcuts such as `Ctrl+K`, listened for on `document`.
+
+use wasm_bindgen::JsCast;
+use wasm_bindgen::closure::Closure;
+use web_sys::{Document, KeyboardEvent};
+
+/// A parsed shortcut like `"Ctrl+Shift+P"`: a set of modifiers plus one key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyCombo {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub meta: bool,
+    /// The non-modifier key, lowercased so it compares case-insensitively.
+    pub key: String,
+}
+
+impl KeyCombo {
+    /// Parses `+`-separated combos. Modifiers are case-insensitive and accept the usual
+    /// aliases (`Control`, `Option`, `Cmd`/`Command`/`Super`). Returns `None` unless
+    /// there is exactly one non-modifier key.
+    pub fn parse(combo: &str) -> Option<KeyCombo> {
+        let mut parsed = KeyCombo {
+            ctrl: false,
+            shift: false,
+            alt: false,
+            meta: false,
+            key: String::new(),
+        };
+        for part in combo.split('+').map(str::trim) {
+            match part.to_ascii_lowercase().as_str() {
+                "" => return None,
+                "ctrl" | "control" => parsed.ctrl = true,
+                "shift" => parsed.shift = true,
+                "alt" | "option" => parsed.alt = true,
+                "meta" | "cmd" | "command" | "super" => parsed.meta = true,
+                key if parsed.key.is_empty() => parsed.key = key.to_string(),
+                _ => return None,
+            }
+        }
+        (!parsed.key.is_empty()).then_some(parsed)
+    }
+
+    /// Whether a key press, described by its modifiers and `KeyboardEvent.key`, triggers
+    /// this combo. Modifiers must match exactly, so `Ctrl+K` doesn't fire on `Ctrl+Shift+K`.
+    pub fn matches_keys(&self, ctrl: bool, shift: bool, alt: bool, meta: bool, key: &str) -> bool {
+        self.ctrl == ctrl
+            && self.shift == shift
+            && self.alt == alt
+            && self.meta == meta
+            && self.key.eq_ignore_ascii_case(key)
+    }
+
+    pub fn matches(&self, event: &KeyboardEvent) -> bool {
+        self.matches_keys(
+            event.ctrl_key(),
+            event.shift_key(),
+            event.alt_key(),
+            event.meta_key(),
+            &event.key(),
+        )
+    }
+}
+
+/// A registered shortcut. The `keydown` listener is removed when this is dropped, so
+/// keep it alive for as long as the app that owns the shortcut is mounted.
+pub struct Shortcut {
+    document: Document,
+    listener: Closure<dyn FnMut(KeyboardEvent)>,
+}
+
+/// Calls `callback` whenever `key_combo` is pressed anywhere in the document.
+///
+/// # Panics
+///
+/// Panics if `key_combo` can't be parsed by `KeyCombo::parse`.
+pub fn register_shortcut(key_combo: &str, callback: impl Fn(&KeyboardEvent) + 'static) -> Shortcut {
+    let combo =
+        KeyCombo::parse(key_combo).unwrap_or_else(|| panic!("Invalid key combo '{}'", key_combo));
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .expect("should have a document on window");
+
+    let listener = Closure::<dyn FnMut(KeyboardEvent)>::new(move |event: KeyboardEvent| {
+        if combo.matches(&event) {
+            callback(&event);
+        }
+    });
+    document
+        .add_event_listener_with_callback("keydown", listener.as_ref().unchecked_ref())
+        .expect("Failed to add keydown listener");
+
+    Shortcut { document, listener }
+}
+
+impl Drop for Shortcut {
+    fn drop(&mut self) {
+        let _ = self
+            .document
+            .remove_event_listener_with_callback("keydown", self.listener.as_ref().unchecked_ref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ctrl_shift_p() {
+        assert_eq!(
+            KeyCombo::parse("Ctrl+Shift+P"),
+            Some(KeyCombo {
+                ctrl: true,
+                shift: true,
+                alt: false,
+                meta: false,
+                key: "p".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_combos() {
+        assert_eq!(KeyCombo::parse("Ctrl+Shift"), None);
+        assert_eq!(KeyCombo::parse("Ctrl+K+J"), None);
+        assert_eq!(KeyCombo::parse("Ctrl++"), None);
+    }
+
+    #[test]
+    fn test_modifiers_must_match_exactly() {
+        let combo = KeyCombo::parse("cmd+k").unwrap();
+
+        assert!(combo.matches_keys(false, false, false, true, "K"));
+        assert!(!combo.matches_keys(false, true, false, true, "K"));
+        assert!(!combo.matches_keys(false, false, false, false, "k"));
+    }
+}
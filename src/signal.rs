@@ -0,0 +1,67 @@
+//! A single reactive value. Where a `Store` holds app-wide state, a signal is small
+//! enough to create per field, e.g. for the text of one input.
+
+use crate::store::{Store, SubscriptionId, create_store};
+
+/// A value that notifies its subscribers when it changes. Clones share the value.
+pub struct Signal<T> {
+    store: Store<T>,
+}
+
+impl<T: Clone + PartialEq + 'static> Signal<T> {
+    pub fn new(initial: T) -> Self {
+        Signal {
+            store: create_store(initial),
+        }
+    }
+
+    pub fn get(&self) -> T {
+        self.store.get()
+    }
+
+    /// Replaces the value and notifies subscribers, unless it is equal to the current
+    /// one. That keeps two-way bindings from echoing a change back and forth.
+    pub fn set(&self, value: T) {
+        if self.store.with(|current| *current != value) {
+            self.store.update(|current| *current = value);
+        }
+    }
+
+    pub fn subscribe(&self, callback: impl Fn(&T) + 'static) -> SubscriptionId {
+        self.store.subscribe(callback)
+    }
+
+    pub fn unsubscribe(&self, id: SubscriptionId) {
+        self.store.unsubscribe(id);
+    }
+}
+
+impl<T> Clone for Signal<T> {
+    fn clone(&self) -> Self {
+        Signal {
+            store: self.store.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+
+    #[test]
+    fn test_set_notifies_only_on_change() {
+        let name = Signal::new(String::from("Ada"));
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let recorder = seen.clone();
+        name.subscribe(move |value: &String| recorder.borrow_mut().push(value.clone()));
+
+        name.set("Ada".to_string());
+        name.clone().set("Grace".to_string());
+
+        assert_eq!(name.get(), "Grace");
+        assert_eq!(*seen.borrow(), ["Grace"]);
+    }
+}
@@ -0,0 +1,120 @@
+//! Loading placeholders: gray boxes in the shape of content that is still on its way.
+
+use std::rc::Rc;
+
+use crate::{Component, Element, Node, Style, em};
+
+/// The background the boxes get, so a skeleton is visible without any stylesheet.
+const SKELETON_COLOR: &str = "#e0e0e0";
+
+/// One placeholder box. Sizes are CSS lengths such as `px(120)` or `percent(80)`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SkeletonShape {
+    /// A line of text, one `em` high.
+    Line {
+        width: String,
+    },
+    Block {
+        width: String,
+        height: String,
+    },
+    /// A round box, e.g. for an avatar.
+    Circle {
+        size: String,
+    },
+}
+
+/// Renders placeholder boxes that match the shape of the content being loaded, e.g.
+/// as the `fallback` of an `AsyncComponent`. Each box is a `div.skeleton-box` inside
+/// a `div.skeleton` marked `aria-busy`, so a stylesheet can add a shimmer animation.
+#[derive(Debug, Clone, Default)]
+pub struct Skeleton {
+    shapes: Vec<SkeletonShape>,
+}
+
+impl Skeleton {
+    pub fn new() -> Self {
+        Skeleton::default()
+    }
+
+    pub fn shape(mut self, shape: SkeletonShape) -> Self {
+        self.shapes.push(shape);
+        self
+    }
+
+    /// `count` full-width text lines, the last one shorter like the end of a paragraph.
+    pub fn lines(mut self, count: usize) -> Self {
+        for line in 0..count {
+            let width = if line + 1 == count && count > 1 {
+                "60%"
+            } else {
+                "100%"
+            };
+            self.shapes.push(SkeletonShape::Line {
+                width: width.to_string(),
+            });
+        }
+        self
+    }
+}
+
+fn render_shape(shape: &SkeletonShape) -> Element {
+    let mut style = Style::new();
+    style.background = Some(SKELETON_COLOR.to_string());
+    style.display = Some("block".to_string());
+    match shape {
+        SkeletonShape::Line { width } => {
+            style.width = Some(width.clone());
+            style.height = Some(em(1));
+            style.margin = Some(format!("{} 0", em(0.5)));
+        }
+        SkeletonShape::Block { width, height } => {
+            style.width = Some(width.clone());
+            style.height = Some(height.clone());
+        }
+        SkeletonShape::Circle { size } => {
+            style.width = Some(size.clone());
+            style.height = Some(size.clone());
+            style = style.custom("border-radius", "50%");
+        }
+    }
+    Element::new("div")
+        .attr("class", "skeleton-box")
+        .style_struct(style)
+}
+
+impl Component for Skeleton {
+    fn render(&self) -> Node {
+        Node::Element(
+            Element::new("div")
+                .attr("class", "skeleton")
+                .attr("aria-busy", "true")
+                .children_from(self.shapes.iter().map(render_shape)),
+        )
+    }
+
+    fn clone_rc(&self) -> Rc<dyn Component> {
+        Rc::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{px, render_node_to_string};
+
+    #[test]
+    fn test_skeleton_renders_one_box_per_shape() {
+        let card = Skeleton::new()
+            .shape(SkeletonShape::Circle { size: px(40) })
+            .lines(3);
+
+        let html = render_node_to_string(&card.render());
+
+        assert!(html.starts_with("<div class=\"skeleton\" aria-busy=\"true\">"));
+        assert_eq!(html.matches("class=\"skeleton-box\"").count(), 4);
+        assert!(html.contains("width: 40px; height: 40px"));
+        assert!(html.contains("border-radius: 50%"));
+        assert!(html.contains("width: 60%"));
+    }
+}
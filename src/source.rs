@@ -0,0 +1,26 @@
+//! Recording where elements were built, for devtools that jump from the DOM to the code.
+
+use std::cell::Cell;
+use std::panic::Location;
+
+/// The attribute `Element::new` records its caller in while source locations are on.
+pub const SOURCE_ATTRIBUTE: &str = "data-rr-source";
+
+thread_local! {
+    static ENABLED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Turns source locations on or off for this thread. While on, every element built
+/// with `Element::new` (or a tag builder such as `table()`) gets a
+/// `data-rr-source="src/view.rs:42"` attribute naming the line that built it. Debug
+/// builds only; in release builds this does nothing, so the attribute never ships.
+pub fn set_source_locations(enabled: bool) {
+    ENABLED.with(|flag| flag.set(enabled && cfg!(debug_assertions)));
+}
+
+/// The value for `SOURCE_ATTRIBUTE`, if source locations are on.
+pub(crate) fn source_location(location: &Location<'_>) -> Option<String> {
+    ENABLED
+        .with(Cell::get)
+        .then(|| format!("{}:{}", location.file(), location.line()))
+}
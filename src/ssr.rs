@@ -0,0 +1,380 @@
+//! Server-side rendering of trees that contain async components.
+
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::commit::{commit_on_server, commit_on_server_at};
+use crate::escape::escape_attr;
+use crate::hooks::Segment;
+use crate::{
+    Element, Fragment, Node, RawHtml, VAsyncComponent, render_node_to_string,
+    render_node_to_string_with_nonce,
+};
+
+/// Awaits every `AsyncComponent` in the tree, then renders the result to HTML. Async
+/// components are resolved one after another, in document order.
+///
+/// The tree is committed first and what each async component resolves to is committed
+/// in its place, so `use_id` gives every component its own id.
+pub async fn render_node_to_string_async(node: &Node) -> String {
+    let resolved = resolve(&commit_on_server(node), &mut Vec::new()).await;
+    render_node_to_string(&resolved)
+}
+
+/// Streams the HTML for `node` in chunks, like React's `renderToPipeableStream`: the
+/// first chunk is the whole page with each async component's fallback in place, so
+/// it can be sent right away. Every later chunk carries the content of one async
+/// component, in document order, with an inline script that swaps it in for the
+/// fallback. Async components nested inside streamed content are awaited as part of
+/// their boundary.
+pub fn render_node_to_stream(node: &Node) -> HtmlStream {
+    stream(node, None)
+}
+
+/// `render_node_to_stream` for pages served under a Content-Security-Policy that
+/// requires nonces: `nonce` goes on every `<script>` and `<style>` in the page and on
+/// the inline scripts that swap streamed content in, which the policy would block
+/// otherwise.
+pub fn render_node_to_stream_with_nonce(node: &Node, nonce: &str) -> HtmlStream {
+    stream(node, Some(nonce))
+}
+
+fn stream(node: &Node, nonce: Option<&str>) -> HtmlStream {
+    let mut boundaries = Vec::new();
+    let shell = shell(&commit_on_server(node), &mut Vec::new(), &mut boundaries);
+    let nonce = nonce.map(str::to_string);
+    HtmlStream {
+        shell: Some(render_with_nonce(&shell, nonce.as_deref())),
+        boundaries,
+        next_boundary: 0,
+        nonce,
+    }
+}
+
+/// The chunks of a streamed render; see `render_node_to_stream`.
+#[derive(Debug)]
+pub struct HtmlStream {
+    shell: Option<String>,
+    /// Every async component, with the position its content is committed at.
+    boundaries: Vec<(VAsyncComponent, Vec<Segment>)>,
+    next_boundary: usize,
+    nonce: Option<String>,
+}
+
+impl HtmlStream {
+    /// The next chunk, or `None` once everything has been sent. The first call returns
+    /// the shell without waiting for anything.
+    pub async fn next_chunk(&mut self) -> Option<String> {
+        if let Some(shell) = self.shell.take() {
+            return Some(shell);
+        }
+        let id = self.next_boundary;
+        let (boundary, mut position) = self.boundaries.get(id)?.clone();
+        self.next_boundary += 1;
+        let content = commit_on_server_at(&boundary.render().await, &position);
+        let content = resolve(&content, &mut position).await;
+        let nonce = match &self.nonce {
+            Some(nonce) => format!(" nonce=\"{}\"", escape_attr(nonce)),
+            None => String::new(),
+        };
+        Some(format!(
+            "<template id=\"rr-s:{id}\">{}</template><script{nonce}>{}</script>",
+            render_with_nonce(&content, self.nonce.as_deref()),
+            swap_script(id)
+        ))
+    }
+}
+
+fn render_with_nonce(node: &Node, nonce: Option<&str>) -> String {
+    match nonce {
+        Some(nonce) => render_node_to_string_with_nonce(node, nonce),
+        None => render_node_to_string(node),
+    }
+}
+
+/// Returns a copy of the committed `node`, which sits at `position`, with components
+/// replaced by their output and every async component by its fallback between
+/// boundary markers. The async components are added to `boundaries`.
+fn shell(
+    node: &Node,
+    position: &mut Vec<Segment>,
+    boundaries: &mut Vec<(VAsyncComponent, Vec<Segment>)>,
+) -> Node {
+    match node {
+        Node::Element(element) => Node::Element(Element {
+            children: shell_children(&element.children, position, boundaries),
+            ..element.clone()
+        }),
+        Node::Fragment(fragment) => Node::Fragment(Fragment {
+            children: shell_children(&fragment.children, position, boundaries),
+            key: fragment.key.clone(),
+        }),
+        Node::Component(v_component) => {
+            position.push(Segment::Index(0));
+            let output = shell(&v_component.render(), position, boundaries);
+            position.pop();
+            output
+        }
+        Node::Lazy(lazy) => shell(&lazy.render(), position, boundaries),
+        Node::Async(v_async) => {
+            let id = boundaries.len();
+            let mut content_position = position.clone();
+            content_position.push(Segment::Index(0));
+            boundaries.push((v_async.clone(), content_position));
+            Node::Fragment(Fragment {
+                children: vec![
+                    Node::RawHtml(RawHtml::new(format!(
+                        "<!--$?--><template id=\"rr-b:{}\"></template>",
+                        id
+                    ))),
+                    v_async.fallback(),
+                    Node::RawHtml(RawHtml::new("<!--/$-->")),
+                ],
+                key: None,
+            })
+        }
+        Node::ServerOnly(inner) => Node::ServerOnly(Box::new(shell(inner, position, boundaries))),
+        Node::Text(_) | Node::RawHtml(_) | Node::ClientOnly(_) => node.clone(),
+    }
+}
+
+fn shell_children(
+    children: &[Node],
+    position: &mut Vec<Segment>,
+    boundaries: &mut Vec<(VAsyncComponent, Vec<Segment>)>,
+) -> Vec<Node> {
+    children
+        .iter()
+        .enumerate()
+        .map(|(index, child)| {
+            position.push(child_segment(index, child));
+            let child = shell(child, position, boundaries);
+            position.pop();
+            child
+        })
+        .collect()
+}
+
+/// The segment `child`, the `index`th of its siblings, adds to a position.
+fn child_segment(index: usize, child: &Node) -> Segment {
+    match child.key() {
+        Some(key) => Segment::Key(key.to_string()),
+        None => Segment::Index(index),
+    }
+}
+
+/// Replaces everything between the boundary's placeholder and its `<!--/$-->` marker
+/// with the streamed content.
+fn swap_script(id: usize) -> String {
+    format!(
+        "(function(){{var b=document.getElementById(\"rr-b:{id}\"),\
+         s=document.getElementById(\"rr-s:{id}\"),n=b.nextSibling;\
+         while(n&&!(n.nodeType===8&&n.data===\"/$\")){{var x=n.nextSibling;n.remove();n=x}}\
+         b.replaceWith(s.content);s.remove()}})()"
+    )
+}
+
+/// Returns a copy of the committed `node`, which sits at `position`, with components
+/// replaced by their output and async components awaited, so the result can be
+/// rendered synchronously. An async component's content is committed where the
+/// component was, like a component's output.
+fn resolve<'a>(
+    node: &'a Node,
+    position: &'a mut Vec<Segment>,
+) -> Pin<Box<dyn Future<Output = Node> + 'a>> {
+    Box::pin(async move {
+        match node {
+            Node::Element(element) => Node::Element(Element {
+                children: resolve_all(&element.children, position).await,
+                ..element.clone()
+            }),
+            Node::Fragment(fragment) => Node::Fragment(Fragment {
+                children: resolve_all(&fragment.children, position).await,
+                key: fragment.key.clone(),
+            }),
+            Node::Component(v_component) => {
+                position.push(Segment::Index(0));
+                let output = resolve(&v_component.render(), position).await;
+                position.pop();
+                output
+            }
+            Node::Lazy(lazy) => resolve(&lazy.render(), position).await,
+            Node::Async(v_async) => {
+                position.push(Segment::Index(0));
+                let content = commit_on_server_at(&v_async.render().await, position);
+                let content = resolve(&content, position).await;
+                position.pop();
+                content
+            }
+            Node::ServerOnly(inner) => Node::ServerOnly(Box::new(resolve(inner, position).await)),
+            Node::Text(_) | Node::RawHtml(_) | Node::ClientOnly(_) => node.clone(),
+        }
+    })
+}
+
+async fn resolve_all(children: &[Node], position: &mut Vec<Segment>) -> Vec<Node> {
+    let mut resolved = Vec::with_capacity(children.len());
+    for (index, child) in children.iter().enumerate() {
+        position.push(child_segment(index, child));
+        resolved.push(resolve(child, position).await);
+        position.pop();
+    }
+    resolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AsyncComponent, Component, VAsyncComponent, component, use_id};
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::task::{Context, Poll, Waker};
+
+    #[derive(Debug)]
+    struct UserName;
+
+    impl AsyncComponent for UserName {
+        fn render(&self) -> Pin<Box<dyn Future<Output = Node>>> {
+            Box::pin(async { Node::Element(Element::new("b").child("Ada")) })
+        }
+
+        fn fallback(&self) -> Node {
+            Node::Text("Loading...".to_string())
+        }
+    }
+
+    /// Polls a future that never actually waits until it completes.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn test_async_components_are_awaited() {
+        let page = Node::Element(
+            Element::new("p")
+                .child("Hello, ")
+                .child(VAsyncComponent::new(UserName)),
+        );
+
+        assert_eq!(render_node_to_string(&page), "<p>Hello, Loading...</p>");
+        assert_eq!(
+            block_on(render_node_to_string_async(&page)),
+            "<p>Hello, <b>Ada</b></p>"
+        );
+    }
+
+    #[derive(Debug, Clone)]
+    struct Field;
+
+    impl Component for Field {
+        fn render(&self) -> Node {
+            Node::Element(Element::new("input").attr("id", use_id()))
+        }
+
+        fn clone_rc(&self) -> Rc<dyn Component> {
+            Rc::new(self.clone())
+        }
+    }
+
+    /// Resolves to a `Field`.
+    #[derive(Debug)]
+    struct LoadedField;
+
+    impl AsyncComponent for LoadedField {
+        fn render(&self) -> Pin<Box<dyn Future<Output = Node>>> {
+            Box::pin(async { Node::Component(component(Field)) })
+        }
+
+        fn fallback(&self) -> Node {
+            Node::Text("Loading...".to_string())
+        }
+    }
+
+    #[test]
+    fn test_async_content_gets_ids_from_its_position() {
+        let form = Node::Element(
+            Element::new("form")
+                .child(component(Field))
+                .child(VAsyncComponent::new(LoadedField))
+                .child(VAsyncComponent::new(LoadedField)),
+        );
+
+        assert_eq!(
+            block_on(render_node_to_string_async(&form)),
+            "<form><input id=\"rr-id-0\"><input id=\"rr-id-1-0\"><input id=\"rr-id-2-0\"></form>"
+        );
+    }
+
+    /// Stays pending until `ready` is set.
+    #[derive(Debug)]
+    struct Gated {
+        ready: Rc<Cell<bool>>,
+    }
+
+    impl AsyncComponent for Gated {
+        fn render(&self) -> Pin<Box<dyn Future<Output = Node>>> {
+            let ready = self.ready.clone();
+            Box::pin(std::future::poll_fn(move |_| {
+                if ready.get() {
+                    Poll::Ready(Node::Element(Element::new("b").child("Ada")))
+                } else {
+                    Poll::Pending
+                }
+            }))
+        }
+
+        fn fallback(&self) -> Node {
+            Node::Text("Loading...".to_string())
+        }
+    }
+
+    #[test]
+    fn test_shell_streams_before_async_boundary_resolves() {
+        let ready = Rc::new(Cell::new(false));
+        let page = Node::Element(Element::new("p").child(VAsyncComponent::new(Gated {
+            ready: ready.clone(),
+        })));
+        let mut stream = render_node_to_stream(&page);
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let shell = block_on(stream.next_chunk()).unwrap();
+        assert_eq!(
+            shell,
+            "<p><!--$?--><template id=\"rr-b:0\"></template>Loading...<!--/$--></p>"
+        );
+
+        let mut next = Box::pin(stream.next_chunk());
+        assert!(next.as_mut().poll(&mut cx).is_pending());
+        ready.set(true);
+        let Poll::Ready(Some(chunk)) = next.as_mut().poll(&mut cx) else {
+            panic!("boundary should resolve once ready");
+        };
+        assert!(chunk.starts_with("<template id=\"rr-s:0\"><b>Ada</b></template><script>"));
+        drop(next);
+        assert_eq!(block_on(stream.next_chunk()), None);
+    }
+
+    #[test]
+    fn test_streamed_scripts_carry_the_nonce() {
+        let page = Node::Element(
+            Element::new("form")
+                .child(Element::new("script").child("init()"))
+                .child(VAsyncComponent::new(LoadedField)),
+        );
+        let mut stream = render_node_to_stream_with_nonce(&page, "r4nd0m");
+
+        let shell = block_on(stream.next_chunk()).unwrap();
+        assert!(shell.starts_with("<form><script nonce=\"r4nd0m\">init()</script>"));
+        let chunk = block_on(stream.next_chunk()).unwrap();
+        assert!(chunk.starts_with(
+            "<template id=\"rr-s:0\"><input id=\"rr-id-1-0\"></template>\
+             <script nonce=\"r4nd0m\">"
+        ));
+    }
+}
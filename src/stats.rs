@@ -0,0 +1,89 @@
+//! Size statistics for a VDOM tree, for debugging and performance budgets.
+
+use crate::Node;
+
+/// What a tree renders to. Components are expanded, so the counts describe the
+/// actual output rather than the tree as it was written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    pub elements: usize,
+    pub text_nodes: usize,
+    pub components: usize,
+    /// Total number of props across all elements.
+    pub attributes: usize,
+    /// Length of the longest chain of nested output nodes (elements, text, raw HTML).
+    pub max_depth: usize,
+}
+
+impl Node {
+    /// Walks the tree, rendering components along the way, and counts what it finds.
+    pub fn stats(&self) -> RenderStats {
+        let mut stats = RenderStats::default();
+        collect(self, 1, &mut stats);
+        stats
+    }
+}
+
+fn collect(node: &Node, depth: usize, stats: &mut RenderStats) {
+    match node {
+        Node::Element(element) => {
+            stats.elements += 1;
+            stats.attributes += element.props.len();
+            stats.max_depth = stats.max_depth.max(depth);
+            for child in &element.children {
+                collect(child, depth + 1, stats);
+            }
+        }
+        Node::Text(_) => {
+            stats.text_nodes += 1;
+            stats.max_depth = stats.max_depth.max(depth);
+        }
+        Node::RawHtml(_) => stats.max_depth = stats.max_depth.max(depth),
+        Node::Component(v_component) => {
+            stats.components += 1;
+            collect(&v_component.render(), depth, stats);
+        }
+        Node::Lazy(lazy) => collect(&lazy.render(), depth, stats),
+        Node::Async(v_async) => collect(&v_async.fallback(), depth, stats),
+        Node::ClientOnly(inner) | Node::ServerOnly(inner) => collect(inner, depth, stats),
+        Node::Fragment(fragment) => {
+            for child in &fragment.children {
+                collect(child, depth, stats);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{App, Element, component};
+
+    #[test]
+    fn test_stats_for_app_tree() {
+        let stats = Node::Component(component(App)).stats();
+
+        assert_eq!(
+            stats,
+            RenderStats {
+                elements: 3,
+                text_nodes: 2,
+                components: 1,
+                attributes: 2,
+                max_depth: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn test_stats_depth_follows_deepest_branch() {
+        let tree = Node::Element(
+            Element::new("ul")
+                .child(Element::new("li"))
+                .child(Element::new("li").child(Element::new("b").child("deep"))),
+        );
+
+        assert_eq!(tree.stats().max_depth, 4);
+        assert_eq!(tree.stats().elements, 4);
+    }
+}
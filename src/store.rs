@@ -0,0 +1,150 @@
+//! A minimal app-wide store: one shared value plus a list of subscribers that are
+//! told whenever it changes. A lighter-weight alternative to something like Redux.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+/// Identifies a subscription so it can be removed again with `Store::unsubscribe`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscriptionId(usize);
+
+/// A handle to shared state. Cloning it is cheap and every clone sees the same value.
+pub struct Store<S> {
+    inner: Rc<StoreInner<S>>,
+}
+
+type Subscriber<S> = Rc<dyn Fn(&S)>;
+
+struct StoreInner<S> {
+    state: RefCell<S>,
+    subscribers: RefCell<Vec<(SubscriptionId, Subscriber<S>)>>,
+    next_id: Cell<usize>,
+}
+
+/// Creates a store holding `initial`.
+pub fn create_store<S: 'static>(initial: S) -> Store<S> {
+    Store {
+        inner: Rc::new(StoreInner {
+            state: RefCell::new(initial),
+            subscribers: RefCell::new(Vec::new()),
+            next_id: Cell::new(0),
+        }),
+    }
+}
+
+impl<S: 'static> Store<S> {
+    /// Returns a copy of the current state.
+    pub fn get(&self) -> S
+    where
+        S: Clone,
+    {
+        self.inner.state.borrow().clone()
+    }
+
+    /// Reads the current state without cloning it.
+    pub fn with<R>(&self, f: impl FnOnce(&S) -> R) -> R {
+        f(&self.inner.state.borrow())
+    }
+
+    /// Mutates the state, then calls every subscriber with the new value. This is where
+    /// a subscriber would re-render the roots that depend on the store.
+    ///
+    /// Subscribers get a copy of the new value, so they are free to read the store and
+    /// even to `update` it again, which notifies everyone once more.
+    pub fn update(&self, f: impl FnOnce(&mut S))
+    where
+        S: Clone,
+    {
+        let state = {
+            let mut state = self.inner.state.borrow_mut();
+            f(&mut state);
+            state.clone()
+        };
+
+        // Snapshot the list so callbacks are free to (un)subscribe while we iterate.
+        let subscribers: Vec<Subscriber<S>> = self
+            .inner
+            .subscribers
+            .borrow()
+            .iter()
+            .map(|(_, callback)| callback.clone())
+            .collect();
+        for callback in subscribers {
+            callback(&state);
+        }
+    }
+
+    pub fn subscribe(&self, callback: impl Fn(&S) + 'static) -> SubscriptionId {
+        let id = SubscriptionId(self.inner.next_id.get());
+        self.inner.next_id.set(id.0 + 1);
+        self.inner
+            .subscribers
+            .borrow_mut()
+            .push((id, Rc::new(callback)));
+        id
+    }
+
+    pub fn unsubscribe(&self, id: SubscriptionId) {
+        self.inner
+            .subscribers
+            .borrow_mut()
+            .retain(|(existing, _)| *existing != id);
+    }
+}
+
+impl<S> Clone for Store<S> {
+    fn clone(&self) -> Self {
+        Store {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_is_visible_through_get_and_clones() {
+        let store = create_store(1);
+        let other_handle = store.clone();
+
+        store.update(|count| *count += 41);
+
+        assert_eq!(store.get(), 42);
+        assert_eq!(other_handle.get(), 42);
+    }
+
+    #[test]
+    fn test_update_notifies_subscribers_until_unsubscribed() {
+        let store = create_store(String::from("draft"));
+        let seen = Rc::new(RefCell::new(Vec::new()));
+
+        let recorder = seen.clone();
+        let id = store.subscribe(move |state: &String| recorder.borrow_mut().push(state.clone()));
+        store.update(|state| state.push_str(" v2"));
+        store.unsubscribe(id);
+        store.update(|state| state.push_str(" v3"));
+
+        assert_eq!(*seen.borrow(), vec!["draft v2".to_string()]);
+        assert_eq!(store.get(), "draft v2 v3");
+    }
+
+    #[test]
+    fn test_subscribers_may_read_and_update_the_store() {
+        let store = create_store(0);
+        let seen = Rc::new(RefCell::new(Vec::new()));
+
+        let (reader, recorder) = (store.clone(), seen.clone());
+        store.subscribe(move |count: &i32| {
+            recorder.borrow_mut().push((*count, reader.get()));
+            if *count < 2 {
+                reader.update(|count| *count += 1);
+            }
+        });
+        store.update(|count| *count = 1);
+
+        assert_eq!(*seen.borrow(), [(1, 1), (2, 2)]);
+        assert_eq!(store.get(), 2);
+    }
+}
@@ -0,0 +1,79 @@
+//! Typed inline styles.
+
+/// Inline CSS with typed fields for the most common properties, so a typo in a
+/// property name is a compile error. Anything else goes through `custom`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Style {
+    pub color: Option<String>,
+    pub background: Option<String>,
+    pub width: Option<String>,
+    pub height: Option<String>,
+    pub margin: Option<String>,
+    pub padding: Option<String>,
+    pub display: Option<String>,
+    /// Extra `(property, value)` declarations, written after the typed fields.
+    pub custom: Vec<(String, String)>,
+}
+
+impl Style {
+    pub fn new() -> Self {
+        Style::default()
+    }
+
+    /// Adds a declaration for a property that has no typed field.
+    pub fn custom(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.custom.push((name.into(), value.into()));
+        self
+    }
+
+    /// Serializes to the value of a `style` attribute. Typed fields come first, in
+    /// declaration order, followed by custom declarations in the order they were added.
+    pub fn to_css(&self) -> String {
+        let typed = [
+            ("color", &self.color),
+            ("background", &self.background),
+            ("width", &self.width),
+            ("height", &self.height),
+            ("margin", &self.margin),
+            ("padding", &self.padding),
+            ("display", &self.display),
+        ];
+        typed
+            .into_iter()
+            .filter_map(|(name, value)| Some((name, value.as_deref()?)))
+            .chain(
+                self.custom
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.as_str())),
+            )
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Element, Node, render_node_to_string};
+
+    #[test]
+    fn test_style_serializes_in_field_order() {
+        let style = Style {
+            display: Some("flex".to_string()),
+            color: Some("#333".to_string()),
+            padding: Some("4px 8px".to_string()),
+            ..Default::default()
+        }
+        .custom("gap", "1rem");
+
+        assert_eq!(
+            style.to_css(),
+            "color: #333; padding: 4px 8px; display: flex; gap: 1rem"
+        );
+        assert_eq!(
+            render_node_to_string(&Node::Element(Element::new("div").style_struct(style))),
+            r#"<div style="color: #333; padding: 4px 8px; display: flex; gap: 1rem"></div>"#
+        );
+    }
+}
@@ -0,0 +1,104 @@
+//! Standalone SVG output.
+
+use crate::commit::commit_on_server;
+use crate::{Element, Node, Props, render_node_to_string};
+
+pub(crate) const SVG_NAMESPACE: &str = "http://www.w3.org/2000/svg";
+const XLINK_NAMESPACE: &str = "http://www.w3.org/1999/xlink";
+
+/// Renders a tree whose root is an `<svg>` element as a standalone SVG document, e.g.
+/// for an `.svg` file or an `image/svg+xml` response. Inline SVG in HTML gets its
+/// namespace from the parser, but a standalone document must declare it, so `xmlns`
+/// is added to the root when missing, and `xmlns:xlink` too when `xlink:` attributes
+/// are used. Returns `None` if the root does not render an `<svg>` element.
+pub fn render_svg_document(node: &Node) -> Option<String> {
+    // Committed first, so `use_id` gives the same ids as `render_node_to_string`.
+    let node = &commit_on_server(node);
+    let root = svg_root(node)?;
+
+    let mut props = Props::new();
+    if !root.props.contains_key("xmlns") {
+        props.insert("xmlns", SVG_NAMESPACE);
+    }
+    if !root.props.contains_key("xmlns:xlink") && uses_xlink(node) {
+        props.insert("xmlns:xlink", XLINK_NAMESPACE);
+    }
+    for (name, value) in &root.props {
+        props.insert(name.as_str(), value.as_str());
+    }
+
+    Some(render_node_to_string(&Node::Element(Element {
+        props,
+        ..root.clone()
+    })))
+}
+
+/// The `<svg>` element the committed `node` renders as, looking through components.
+fn svg_root(node: &Node) -> Option<&Element> {
+    match node {
+        Node::Element(element) if element.tag_name.eq_ignore_ascii_case("svg") => Some(element),
+        Node::Component(v_component) => v_component.output.as_deref().and_then(svg_root),
+        _ => None,
+    }
+}
+
+fn uses_xlink(node: &Node) -> bool {
+    match node {
+        Node::Element(element) => {
+            element.props.keys().any(|name| name.starts_with("xlink:"))
+                || element.children.iter().any(uses_xlink)
+        }
+        Node::Component(v_component) => uses_xlink(&v_component.render()),
+        Node::Lazy(lazy) => uses_xlink(&lazy.render()),
+        Node::Fragment(fragment) => fragment.children.iter().any(uses_xlink),
+        Node::ClientOnly(inner) | Node::ServerOnly(inner) => uses_xlink(inner),
+        Node::Text(_) | Node::RawHtml(_) | Node::Async(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_svg_document_root_declares_namespace() {
+        let icon = Node::Element(
+            Element::new("svg").attr("viewBox", "0 0 10 10").child(
+                Element::new("circle")
+                    .attr("cx", "5")
+                    .attr("cy", "5")
+                    .attr("r", "4"),
+            ),
+        );
+
+        assert_eq!(
+            render_svg_document(&icon).as_deref(),
+            Some(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10"><circle cx="5" cy="5" r="4"></circle></svg>"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_svg_document_keeps_existing_namespace_and_adds_xlink() {
+        let sprite = Node::Element(
+            Element::new("svg")
+                .attr("xmlns", SVG_NAMESPACE)
+                .child(Element::new("use").attr("xlink:href", "#icon")),
+        );
+
+        let svg = render_svg_document(&sprite).unwrap();
+
+        assert!(svg.starts_with(
+            r#"<svg xmlns:xlink="http://www.w3.org/1999/xlink" xmlns="http://www.w3.org/2000/svg">"#
+        ));
+        assert_eq!(svg.matches("xmlns=").count(), 1);
+    }
+
+    #[test]
+    fn test_svg_document_needs_an_svg_root() {
+        let page = Node::Element(Element::new("div").child(Element::new("svg")));
+
+        assert_eq!(render_svg_document(&page), None);
+    }
+}
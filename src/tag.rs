@@ -0,0 +1,221 @@
+//! Typed tag names, so a typo like `"divv"` becomes a compile error instead of a
+//! silently invalid element. `Element` still stores the plain string.
+
+use crate::Element;
+
+/// Common HTML tags, plus `Custom` for anything else (custom elements, SVG, ...).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Tag {
+    A,
+    Article,
+    Aside,
+    B,
+    Body,
+    Br,
+    Button,
+    Canvas,
+    Caption,
+    Code,
+    Colgroup,
+    Div,
+    Em,
+    Footer,
+    Form,
+    H1,
+    H2,
+    H3,
+    H4,
+    H5,
+    H6,
+    Head,
+    Header,
+    Hr,
+    Html,
+    I,
+    Img,
+    Input,
+    Label,
+    Li,
+    Link,
+    Main,
+    Meta,
+    Nav,
+    Ol,
+    Option,
+    P,
+    Pre,
+    Script,
+    Section,
+    Select,
+    Small,
+    Span,
+    Strong,
+    Style,
+    Table,
+    Tbody,
+    Td,
+    Textarea,
+    Tfoot,
+    Th,
+    Thead,
+    Title,
+    Tr,
+    Ul,
+    Custom(String),
+}
+
+impl Tag {
+    /// The tag name as it appears in markup.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Tag::A => "a",
+            Tag::Article => "article",
+            Tag::Aside => "aside",
+            Tag::B => "b",
+            Tag::Body => "body",
+            Tag::Br => "br",
+            Tag::Button => "button",
+            Tag::Canvas => "canvas",
+            Tag::Caption => "caption",
+            Tag::Code => "code",
+            Tag::Colgroup => "colgroup",
+            Tag::Div => "div",
+            Tag::Em => "em",
+            Tag::Footer => "footer",
+            Tag::Form => "form",
+            Tag::H1 => "h1",
+            Tag::H2 => "h2",
+            Tag::H3 => "h3",
+            Tag::H4 => "h4",
+            Tag::H5 => "h5",
+            Tag::H6 => "h6",
+            Tag::Head => "head",
+            Tag::Header => "header",
+            Tag::Hr => "hr",
+            Tag::Html => "html",
+            Tag::I => "i",
+            Tag::Img => "img",
+            Tag::Input => "input",
+            Tag::Label => "label",
+            Tag::Li => "li",
+            Tag::Link => "link",
+            Tag::Main => "main",
+            Tag::Meta => "meta",
+            Tag::Nav => "nav",
+            Tag::Ol => "ol",
+            Tag::Option => "option",
+            Tag::P => "p",
+            Tag::Pre => "pre",
+            Tag::Script => "script",
+            Tag::Section => "section",
+            Tag::Select => "select",
+            Tag::Small => "small",
+            Tag::Span => "span",
+            Tag::Strong => "strong",
+            Tag::Style => "style",
+            Tag::Table => "table",
+            Tag::Tbody => "tbody",
+            Tag::Td => "td",
+            Tag::Textarea => "textarea",
+            Tag::Tfoot => "tfoot",
+            Tag::Th => "th",
+            Tag::Thead => "thead",
+            Tag::Title => "title",
+            Tag::Tr => "tr",
+            Tag::Ul => "ul",
+            Tag::Custom(name) => name,
+        }
+    }
+}
+
+impl From<Tag> for String {
+    fn from(tag: Tag) -> String {
+        match tag {
+            Tag::Custom(name) => name,
+            tag => tag.as_str().to_string(),
+        }
+    }
+}
+
+/// A `<form>` builder. Give it an `id` and name its fields to read them back with
+/// `RootHandle::form_values`.
+#[track_caller]
+pub fn form() -> Element {
+    Element::with_tag(Tag::Form)
+}
+
+/// Table builders. Put rows in a `thead`, `tbody` or `tfoot` rather than directly in
+/// the `table`: browsers insert a `<tbody>` when parsing such markup, so the server
+/// output would no longer match the VDOM. `check_tree` warns about it.
+#[track_caller]
+pub fn table() -> Element {
+    Element::with_tag(Tag::Table)
+}
+
+#[track_caller]
+pub fn caption() -> Element {
+    Element::with_tag(Tag::Caption)
+}
+
+#[track_caller]
+pub fn colgroup() -> Element {
+    Element::with_tag(Tag::Colgroup)
+}
+
+#[track_caller]
+pub fn thead() -> Element {
+    Element::with_tag(Tag::Thead)
+}
+
+#[track_caller]
+pub fn tbody() -> Element {
+    Element::with_tag(Tag::Tbody)
+}
+
+#[track_caller]
+pub fn tfoot() -> Element {
+    Element::with_tag(Tag::Tfoot)
+}
+
+#[track_caller]
+pub fn tr() -> Element {
+    Element::with_tag(Tag::Tr)
+}
+
+#[track_caller]
+pub fn td() -> Element {
+    Element::with_tag(Tag::Td)
+}
+
+#[track_caller]
+pub fn th() -> Element {
+    Element::with_tag(Tag::Th)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_converts_to_tag_name() {
+        assert_eq!(Element::with_tag(Tag::Div).tag_name, "div");
+        assert_eq!(Element::with_tag(Tag::H2).tag_name, "h2");
+        assert_eq!(
+            Element::with_tag(Tag::Custom("my-widget".to_string())).tag_name,
+            "my-widget"
+        );
+    }
+
+    #[test]
+    fn test_table_builders_nest_rows_in_sections() {
+        let scores = table()
+            .child(thead().child(tr().child(th().child("Player")).child(th().child("Score"))))
+            .child(tbody().child(tr().child(td().child("Ada")).child(td().child("42"))));
+
+        assert_eq!(
+            crate::render_node_to_string(&crate::Node::Element(scores)),
+            "<table><thead><tr><th>Player</th><th>Score</th></tr></thead>\
+             <tbody><tr><td>Ada</td><td>42</td></tr></tbody></table>"
+        );
+    }
+}
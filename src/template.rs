@@ -0,0 +1,67 @@
+//! Compiled templates: render the static part of a tree once, then stamp out copies
+//! and fill in only the dynamic holes.
+
+use wasm_bindgen::JsCast;
+use web_sys::{Document, DocumentFragment, Element as DomElement};
+
+use crate::{Element, Node, render_node_to_dom};
+
+/// The attribute `Element::hole` marks dynamic elements with.
+const HOLE_ATTRIBUTE: &str = "data-hole";
+
+impl Element {
+    /// Marks this element's content as dynamic in a `compile`d template: each
+    /// instance can replace its children by passing a value for `name`.
+    pub fn hole(self, name: &str) -> Self {
+        self.attr(HOLE_ATTRIBUTE, name)
+    }
+}
+
+/// A tree pre-rendered into a `DocumentFragment`, see `compile`.
+#[derive(Debug, Clone)]
+pub struct CompiledTemplate {
+    document: Document,
+    prototype: DocumentFragment,
+}
+
+/// Renders `node` once into a detached fragment so that `instantiate` only has to
+/// clone it and render what goes into the holes.
+pub fn compile(node: &Node) -> CompiledTemplate {
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .expect("should have a document on window");
+    let prototype = document.create_document_fragment();
+    render_node_to_dom(node, &document, &prototype);
+    CompiledTemplate {
+        document,
+        prototype,
+    }
+}
+
+impl CompiledTemplate {
+    /// A fresh copy of the template with the children of each named hole replaced by
+    /// the matching value. Holes without a value keep the content they were compiled
+    /// with. The marker attribute is removed from the copy.
+    pub fn instantiate(&self, values: &[(&str, Node)]) -> DocumentFragment {
+        let copy: DocumentFragment = self
+            .prototype
+            .clone_node_with_deep(true)
+            .expect("Failed to clone template")
+            .unchecked_into();
+
+        let holes = copy
+            .query_selector_all(&format!("[{}]", HOLE_ATTRIBUTE))
+            .expect("Hole selector is valid");
+        for hole in (0..holes.length()).filter_map(|i| holes.item(i)) {
+            let hole: DomElement = hole.unchecked_into();
+            let name = hole.get_attribute(HOLE_ATTRIBUTE).unwrap_or_default();
+            hole.remove_attribute(HOLE_ATTRIBUTE)
+                .expect("Failed to remove hole marker");
+            if let Some((_, value)) = values.iter().find(|(hole_name, _)| *hole_name == name) {
+                hole.set_text_content(None);
+                render_node_to_dom(value, &self.document, &hole);
+            }
+        }
+        copy
+    }
+}
@@ -0,0 +1,193 @@
+//! Plain-text output: what a user would read, without any markup.
+
+use crate::Node;
+use crate::escape::{is_raw_text_element, unescape};
+use crate::sanitize::{Token, Tokenizer};
+
+/// Elements that start on a new line, so their text is separated from its neighbours.
+const BLOCK_TAGS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tr",
+    "ul",
+];
+
+/// Extracts the textual content of a tree, like the DOM's `textContent`, but with a
+/// newline around block elements and for `<br>`. Useful for plain-text email bodies,
+/// previews and accessibility fallbacks. Tags inside raw HTML are dropped as well.
+/// Script and style code is not text a user reads, so it is left out, and so is
+/// ClientOnly content, which the server never renders.
+pub fn render_node_to_text(node: &Node) -> String {
+    let mut text = String::new();
+    write_text(node, &mut text);
+    text.trim().to_string()
+}
+
+fn write_text(node: &Node, out: &mut String) {
+    match node {
+        Node::Text(text) => out.push_str(text),
+        Node::Element(element) if element.tag_name == "br" => out.push('\n'),
+        Node::Element(element) if is_raw_text_element(&element.tag_name) => {}
+        Node::Element(element) => {
+            let block = BLOCK_TAGS.contains(&element.tag_name.as_str());
+            if block {
+                start_line(out);
+            }
+            for child in &element.children {
+                write_text(child, out);
+            }
+            if block {
+                start_line(out);
+            }
+        }
+        Node::RawHtml(raw) => {
+            for text in raw_html_text(&raw.to_html()) {
+                out.push_str(&text);
+            }
+        }
+        Node::Component(v_component) => write_text(&v_component.render(), out),
+        Node::Lazy(lazy) => write_text(&lazy.render(), out),
+        Node::Async(v_async) => write_text(&v_async.fallback(), out),
+        Node::ClientOnly(_) => {}
+        Node::ServerOnly(inner) => write_text(inner, out),
+        Node::Fragment(fragment) => {
+            for child in &fragment.children {
+                write_text(child, out);
+            }
+        }
+    }
+}
+
+/// Every text fragment in the tree, in document order and each as its own entry, for
+/// feeding a search index. Components are expanded and the text inside raw HTML is
+/// included; the same content as in `render_node_to_text` is left out.
+pub fn collect_text(node: &Node) -> Vec<String> {
+    let mut fragments = Vec::new();
+    collect_into(node, &mut fragments);
+    fragments
+}
+
+fn collect_into(node: &Node, fragments: &mut Vec<String>) {
+    match node {
+        Node::Text(text) => fragments.push(text.clone()),
+        Node::Element(element) if is_raw_text_element(&element.tag_name) => {}
+        Node::Element(element) => {
+            for child in &element.children {
+                collect_into(child, fragments);
+            }
+        }
+        Node::RawHtml(raw) => fragments.extend(raw_html_text(&raw.to_html())),
+        Node::Component(v_component) => collect_into(&v_component.render(), fragments),
+        Node::Lazy(lazy) => collect_into(&lazy.render(), fragments),
+        Node::Async(v_async) => collect_into(&v_async.fallback(), fragments),
+        Node::ClientOnly(_) => {}
+        Node::ServerOnly(inner) => collect_into(inner, fragments),
+        Node::Fragment(fragment) => {
+            for child in &fragment.children {
+                collect_into(child, fragments);
+            }
+        }
+    }
+}
+
+/// The text in raw markup, with character references decoded and without the code of
+/// its scripts and styles.
+fn raw_html_text(html: &str) -> Vec<String> {
+    let mut fragments = Vec::new();
+    let mut in_raw_text = false;
+    for token in Tokenizer::new(html) {
+        match token {
+            Token::StartTag { name, .. } if is_raw_text_element(&name) => in_raw_text = true,
+            Token::EndTag { name } if is_raw_text_element(&name) => in_raw_text = false,
+            Token::Text(text) if !in_raw_text => fragments.push(unescape(text).into_owned()),
+            _ => {}
+        }
+    }
+    fragments
+}
+
+/// Ends the current line, unless we are already at the start of one.
+fn start_line(out: &mut String) {
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{App, Element, RawHtml, component};
+
+    #[test]
+    fn test_app_text_is_one_line_per_block() {
+        assert_eq!(
+            render_node_to_text(&Node::Component(component(App))),
+            "Hello from a Rusty Component!\nThis was rendered via a component trait."
+        );
+    }
+
+    #[test]
+    fn test_inline_text_is_concatenated() {
+        let tree = Node::Element(
+            Element::new("p")
+                .child("Hello, ")
+                .child(Element::new("b").child("world"))
+                .child(Element::new("br"))
+                .child(RawHtml::new("<i>again &amp; again&#33;</i>")),
+        );
+
+        assert_eq!(render_node_to_text(&tree), "Hello, world\nagain & again!");
+    }
+
+    #[test]
+    fn test_collect_text_keeps_fragments_separate() {
+        assert_eq!(
+            collect_text(&Node::Component(component(App))),
+            [
+                "Hello from a Rusty Component!",
+                "This was rendered via a component trait."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scripts_styles_and_client_only_content_are_not_text() {
+        let tree = Node::Element(
+            Element::new("main")
+                .child(Element::new("style").child("p { color: red }"))
+                .child(Element::new("p").child("Visible"))
+                .child(Node::ClientOnly(Box::new(Node::Text("Later".to_string()))))
+                .child(RawHtml::new("<script>track()</script><b>Bold</b>")),
+        );
+
+        assert_eq!(render_node_to_text(&tree), "Visible\nBold");
+        assert_eq!(collect_text(&tree), ["Visible", "Bold"]);
+    }
+}
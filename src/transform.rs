@@ -0,0 +1,195 @@
+//! Rewriting trees just before they are rendered.
+
+use crate::commit::commit_on_server;
+use crate::escape::is_raw_text_element;
+use crate::{Element, Fragment, Node, render_node_to_string};
+
+/// Rebuilds `node` with `transform` applied to every node, parents before children.
+/// Returning `Some` replaces the node (its children are then visited, but the
+/// replacement itself is not passed to `transform` again); `None` keeps it.
+/// Components and lazy nodes are rendered so the transform sees their output; async
+/// components are left alone. The tree is committed like `render_node_to_string`
+/// commits it, so `use_id` gives the same ids.
+pub fn transform_node(node: &Node, transform: &dyn Fn(&Node) -> Option<Node>) -> Node {
+    transform_committed(&commit_on_server(node), transform)
+}
+
+fn transform_committed(node: &Node, transform: &dyn Fn(&Node) -> Option<Node>) -> Node {
+    let node = transform(node).unwrap_or_else(|| node.clone());
+    match node {
+        Node::Element(element) => Node::Element(Element {
+            children: transform_children(&element.children, transform),
+            ..element
+        }),
+        Node::Fragment(fragment) => Node::Fragment(Fragment {
+            children: transform_children(&fragment.children, transform),
+            key: fragment.key,
+        }),
+        Node::Component(v_component) => transform_committed(&v_component.render(), transform),
+        Node::Lazy(lazy) => transform_committed(&lazy.render(), transform),
+        Node::ClientOnly(inner) => {
+            Node::ClientOnly(Box::new(transform_committed(&inner, transform)))
+        }
+        Node::ServerOnly(inner) => {
+            Node::ServerOnly(Box::new(transform_committed(&inner, transform)))
+        }
+        Node::Text(_) | Node::RawHtml(_) | Node::Async(_) => node,
+    }
+}
+
+fn transform_children(children: &[Node], transform: &dyn Fn(&Node) -> Option<Node>) -> Vec<Node> {
+    children
+        .iter()
+        .map(|child| transform_committed(child, transform))
+        .collect()
+}
+
+/// Renders `node` to HTML after running it through `transform_node`, e.g. to add a
+/// default class to every button or point image URLs at a CDN.
+pub fn render_with_transform(node: &Node, transform: impl Fn(&Node) -> Option<Node>) -> String {
+    render_node_to_string(&transform_node(node, &transform))
+}
+
+/// Renders `node` with `nonce` added to every `<script>` and `<style>` element, for
+/// serving inline code under a Content-Security-Policy that requires nonces.
+pub fn render_node_to_string_with_nonce(node: &Node, nonce: &str) -> String {
+    render_with_transform(node, |node| match node {
+        Node::Element(element) if is_raw_text_element(&element.tag_name) => {
+            Some(Node::Element(element.clone().attr("nonce", nonce)))
+        }
+        _ => None,
+    })
+}
+
+/// Elements that are focusable and keyboard-operable on their own.
+const INTERACTIVE_TAGS: &[&str] = &[
+    "a", "button", "details", "input", "label", "option", "select", "summary", "textarea",
+];
+
+/// Rebuilds `node` with ARIA defaults for elements that handle clicks but aren't
+/// interactive on their own: a clickable `div` gets `role="button"` and `tabindex="0"`,
+/// so it is announced as a button and can be reached with the keyboard. Attributes
+/// that are already set are left alone.
+pub fn with_aria_defaults(node: &Node) -> Node {
+    transform_node(node, &|node| match node {
+        Node::Element(element)
+            if !INTERACTIVE_TAGS.contains(&element.tag_name.as_str())
+                && element
+                    .listeners
+                    .iter()
+                    .any(|listener| listener.event == "click") =>
+        {
+            let mut element = element.clone();
+            if !element.props.contains_key("role") {
+                element.props.insert("role", "button");
+            }
+            if !element.props.contains_key("tabindex") {
+                element.props.insert("tabindex", "0");
+            }
+            Some(Node::Element(element))
+        }
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Component, component, use_id};
+    use std::rc::Rc;
+
+    #[test]
+    fn test_transform_adds_class_to_every_button() {
+        let toolbar = Node::Element(
+            Element::new("div")
+                .child(Element::new("button").child("Save"))
+                .child(
+                    Element::new("span")
+                        .child(Element::new("button").attr("id", "undo").child("Undo")),
+                ),
+        );
+
+        let html = render_with_transform(&toolbar, |node| match node {
+            Node::Element(element) if element.tag_name == "button" => {
+                Some(Node::Element(element.clone().attr("class", "btn")))
+            }
+            _ => None,
+        });
+
+        assert_eq!(
+            html,
+            r#"<div><button class="btn">Save</button><span><button id="undo" class="btn">Undo</button></span></div>"#
+        );
+    }
+
+    #[test]
+    fn test_nonce_is_added_to_script_and_style_only() {
+        let head = Node::Element(
+            Element::new("head")
+                .child(Element::new("style").child("p { color: red }"))
+                .child(Element::new("script").child("init()"))
+                .child(Element::new("title").child("Home")),
+        );
+
+        assert_eq!(
+            render_node_to_string_with_nonce(&head, "r4nd0m"),
+            r#"<head><style nonce="r4nd0m">p { color: red }</style><script nonce="r4nd0m">init()</script><title>Home</title></head>"#
+        );
+    }
+
+    #[test]
+    fn test_nonce_script_keeps_its_code_intact() {
+        let script =
+            Node::Element(Element::new("script").child("if (a < b && c > d) { start(); }"));
+
+        assert_eq!(
+            render_node_to_string_with_nonce(&script, "r4nd0m"),
+            "<script nonce=\"r4nd0m\">if (a < b && c > d) { start(); }</script>"
+        );
+    }
+
+    #[test]
+    fn test_aria_defaults_mark_clickable_div_as_button() {
+        let menu = Node::Element(
+            Element::new("nav")
+                .child(Element::new("div").on_click(|_| {}).child("Open"))
+                .child(Element::new("div").attr("role", "tab").on_click(|_| {}))
+                .child(Element::new("button").on_click(|_| {})),
+        );
+
+        assert_eq!(
+            render_node_to_string(&with_aria_defaults(&menu)),
+            "<nav><div role=\"button\" tabindex=\"0\">Open</div>\
+             <div role=\"tab\" tabindex=\"0\"></div><button></button></nav>"
+        );
+    }
+
+    #[derive(Debug, Clone)]
+    struct Field;
+
+    impl Component for Field {
+        fn render(&self) -> Node {
+            Node::Element(Element::new("input").attr("id", use_id()))
+        }
+
+        fn clone_rc(&self) -> Rc<dyn Component> {
+            Rc::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_nonce_render_matches_any_case_and_keeps_ids_apart() {
+        let form = Node::Element(
+            Element::new("form")
+                .child(Element::new("SCRIPT").child("init()"))
+                .child(component(Field))
+                .child(component(Field)),
+        );
+
+        assert_eq!(
+            render_node_to_string_with_nonce(&form, "r4nd0m"),
+            "<form><SCRIPT nonce=\"r4nd0m\">init()</SCRIPT>\
+             <input id=\"rr-id-1\"><input id=\"rr-id-2\"></form>"
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! CSS enter/leave transitions driven by class names, in the style of Vue's
+//! `<Transition>`.
+//!
+//! For a transition named `fade`, an element that is created goes through
+//! `fade-enter fade-enter-active` (first frame), `fade-enter-active` (while the CSS
+//! transition runs) and finally no transition classes. Leaving goes through
+//! `fade-leave fade-leave-active`, then `fade-leave-active`, after which the caller
+//! removes the element, typically by updating the root without it.
+
+use std::rc::Rc;
+
+use wasm_bindgen::JsCast;
+use wasm_bindgen::closure::Closure;
+use web_sys::Element as DomElement;
+
+use crate::{Element, OnCreate};
+
+/// The steps of a transition, in the order they are applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionPhase {
+    EnterStart,
+    EnterActive,
+    Entered,
+    LeaveStart,
+    LeaveActive,
+    Left,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    name: String,
+    /// How long the CSS transition takes; the final phase is applied after this.
+    duration_ms: i32,
+}
+
+impl Transition {
+    pub fn new(name: &str) -> Self {
+        Transition {
+            name: name.to_string(),
+            duration_ms: 300,
+        }
+    }
+
+    pub fn duration(mut self, duration_ms: i32) -> Self {
+        self.duration_ms = duration_ms;
+        self
+    }
+
+    /// The transition classes an element carries during `phase`.
+    pub fn classes(&self, phase: TransitionPhase) -> Vec<String> {
+        let suffixes: &[&str] = match phase {
+            TransitionPhase::EnterStart => &["enter", "enter-active"],
+            TransitionPhase::EnterActive => &["enter-active"],
+            TransitionPhase::LeaveStart => &["leave", "leave-active"],
+            TransitionPhase::LeaveActive => &["leave-active"],
+            TransitionPhase::Entered | TransitionPhase::Left => &[],
+        };
+        suffixes
+            .iter()
+            .map(|suffix| format!("{}-{}", self.name, suffix))
+            .collect()
+    }
+
+    /// Makes `element` play the enter transition when it is created. An existing
+    /// `on_create` callback still runs first.
+    pub fn wrap(&self, element: Element) -> Element {
+        let previous = element.on_create.clone();
+        let transition = self.clone();
+        element.on_create(move |dom_element| {
+            if let Some(OnCreate(callback)) = &previous {
+                callback(dom_element);
+            }
+            transition.run(
+                dom_element,
+                [
+                    TransitionPhase::EnterStart,
+                    TransitionPhase::EnterActive,
+                    TransitionPhase::Entered,
+                ],
+                || {},
+            );
+        })
+    }
+
+    /// Plays the leave transition on `element` and calls `done` once it has finished,
+    /// which is the moment to remove the element.
+    pub fn leave(&self, element: &DomElement, done: impl FnOnce() + 'static) {
+        self.run(
+            element,
+            [
+                TransitionPhase::LeaveStart,
+                TransitionPhase::LeaveActive,
+                TransitionPhase::Left,
+            ],
+            done,
+        );
+    }
+
+    /// Applies `start` now, `active` on the next frame and `end` after the duration.
+    fn run(
+        &self,
+        element: &DomElement,
+        [start, active, end]: [TransitionPhase; 3],
+        done: impl FnOnce() + 'static,
+    ) {
+        let window = web_sys::window().expect("no global `window` exists");
+        self.apply(element, start);
+
+        let transition = Rc::new(self.clone());
+        let (frame_transition, frame_element) = (transition.clone(), element.clone());
+        let next_frame =
+            Closure::once_into_js(move || frame_transition.apply(&frame_element, active));
+        window
+            .request_animation_frame(next_frame.unchecked_ref())
+            .expect("Failed to request animation frame");
+
+        let end_element = element.clone();
+        let finished = Closure::once_into_js(move || {
+            transition.apply(&end_element, end);
+            done();
+        });
+        window
+            .set_timeout_with_callback_and_timeout_and_arguments_0(
+                finished.unchecked_ref(),
+                self.duration_ms,
+            )
+            .expect("Failed to set transition timeout");
+    }
+
+    /// Replaces whatever transition classes `element` has with those of `phase`.
+    fn apply(&self, element: &DomElement, phase: TransitionPhase) {
+        let prefix = format!("{}-", self.name);
+        let mut class: Vec<String> = element
+            .get_attribute("class")
+            .unwrap_or_default()
+            .split_whitespace()
+            .filter(|name| {
+                !name
+                    .strip_prefix(&prefix)
+                    .is_some_and(|rest| rest.starts_with("enter") || rest.starts_with("leave"))
+            })
+            .map(str::to_string)
+            .collect();
+        class.extend(self.classes(phase));
+        element
+            .set_attribute("class", &class.join(" "))
+            .expect("Failed to set class");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_class_sequence_over_phases() {
+        let fade = Transition::new("fade");
+        let sequence: Vec<String> = [
+            TransitionPhase::EnterStart,
+            TransitionPhase::EnterActive,
+            TransitionPhase::Entered,
+            TransitionPhase::LeaveStart,
+            TransitionPhase::LeaveActive,
+            TransitionPhase::Left,
+        ]
+        .into_iter()
+        .map(|phase| fade.classes(phase).join(" "))
+        .collect();
+
+        assert_eq!(
+            sequence,
+            [
+                "fade-enter fade-enter-active",
+                "fade-enter-active",
+                "",
+                "fade-leave fade-leave-active",
+                "fade-leave-active",
+                "",
+            ]
+        );
+    }
+}
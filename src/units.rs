@@ -0,0 +1,46 @@
+//! CSS length helpers for style values: `px(16)` is `"16px"`, `rem(1.5)` is `"1.5rem"`.
+
+/// A length in pixels.
+pub fn px(value: impl Into<f64>) -> String {
+    format!("{}px", value.into())
+}
+
+/// A percentage of the containing block.
+pub fn percent(value: impl Into<f64>) -> String {
+    format!("{}%", value.into())
+}
+
+/// A length relative to the root font size.
+pub fn rem(value: impl Into<f64>) -> String {
+    format!("{}rem", value.into())
+}
+
+/// A length relative to the element's font size.
+pub fn em(value: impl Into<f64>) -> String {
+    format!("{}em", value.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Style;
+
+    #[test]
+    fn test_unit_helpers_format_values() {
+        assert_eq!(px(16), "16px");
+        assert_eq!(px(0.5), "0.5px");
+        assert_eq!(percent(50), "50%");
+        assert_eq!(rem(1.5), "1.5rem");
+        assert_eq!(em(2), "2em");
+    }
+
+    #[test]
+    fn test_units_feed_style_fields() {
+        let style = Style {
+            width: Some(percent(100)),
+            padding: Some(rem(0.25)),
+            ..Default::default()
+        };
+        assert_eq!(style.to_css(), "width: 100%; padding: 0.25rem");
+    }
+}
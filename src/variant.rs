@@ -0,0 +1,77 @@
+//! Class names for component variants, derived from enum variant names.
+
+use std::fmt::Debug;
+
+/// Maps the variants of an enum to BEM-style modifier classes of one block, so
+/// `Button::Primary` is styled with `btn btn--primary` instead of a hand-written string.
+/// Implementing it only takes the block name: `impl VariantClass for Button { const
+/// BLOCK: &'static str = "btn"; }` gives `Button::DangerOutline` the class
+/// `btn--danger-outline`.
+///
+/// The variant name is read from the `Debug` output, so the derived `Debug` impl must
+/// be kept; fields of tuple and struct variants are ignored.
+pub trait VariantClass: Debug {
+    /// The block the modifiers belong to, e.g. `"btn"`.
+    const BLOCK: &'static str;
+
+    /// The modifier class for this variant: the block, `--`, and the variant name in
+    /// kebab case.
+    fn variant_class(&self) -> String {
+        let debug = format!("{:?}", self);
+        let name = debug
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .next()
+            .unwrap_or_default();
+        format!("{}--{}", Self::BLOCK, kebab_case(name))
+    }
+}
+
+/// `DangerOutline` -> `danger-outline`, `Size2X` -> `size2-x`.
+fn kebab_case(name: &str) -> String {
+    let mut kebab = String::with_capacity(name.len() + 4);
+    for (index, c) in name.chars().enumerate() {
+        if c == '_' {
+            kebab.push('-');
+        } else if c.is_uppercase() {
+            if index > 0 && !kebab.ends_with('-') {
+                kebab.push('-');
+            }
+            kebab.extend(c.to_lowercase());
+        } else {
+            kebab.push(c);
+        }
+    }
+    kebab
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Element;
+
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    enum Button {
+        Primary,
+        Secondary,
+        DangerOutline,
+        Icon(&'static str),
+    }
+
+    impl VariantClass for Button {
+        const BLOCK: &'static str = "btn";
+    }
+
+    #[test]
+    fn test_variant_class_uses_kebab_case_variant_name() {
+        assert_eq!(Button::Primary.variant_class(), "btn--primary");
+        assert_eq!(Button::Secondary.variant_class(), "btn--secondary");
+        assert_eq!(Button::DangerOutline.variant_class(), "btn--danger-outline");
+        assert_eq!(Button::Icon("star").variant_class(), "btn--icon");
+
+        let button = Element::new("button")
+            .attr("class", "wide")
+            .variant(&Button::Primary);
+        assert_eq!(button.props["class"], "btn btn--primary wide");
+    }
+}
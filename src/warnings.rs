@@ -0,0 +1,395 @@
+//! Development-time warnings about trees that render correctly but are likely to
+//! misbehave. Warnings are collected in a per-thread channel and read with
+//! `take_warnings`; nothing is printed on its own.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+use crate::Node;
+use crate::diff::slots;
+use crate::escape::is_void_element;
+
+/// Sibling elements of one tag needed before a keyless run looks like a dynamic list.
+const UNKEYED_LIST_THRESHOLD: usize = 3;
+
+/// How many warnings the channel holds before dropping the oldest ones.
+const MAX_WARNINGS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WarningKind {
+    /// Several unkeyed siblings of the same tag, which is how `.map()` output usually
+    /// looks. Without keys, reordering the list re-renders every item in place.
+    UnkeyedList,
+    /// `hydrate` found server-rendered markup that does not match the VDOM and fell
+    /// back to rendering on the client.
+    HydrationMismatch,
+    /// `hydrate` adopted an element whose server-rendered attributes differ from the
+    /// VDOM's, and replaced them with the VDOM's.
+    HydrationAttributeMismatch,
+    /// A `<tr>` placed directly inside a `<table>`. Browsers wrap it in an implied
+    /// `<tbody>` when parsing HTML, so server-rendered markup stops matching the VDOM.
+    TableRowOutsideSection,
+    /// A `<label for="...">` whose `for` matches no `id` in the tree, so clicking the
+    /// label does nothing and screen readers can't tell what it labels.
+    DanglingLabel,
+    /// Two siblings with the same `key`. Keyed reconciliation matches siblings by key,
+    /// so one of them gets the other's DOM node and state after a reorder.
+    DuplicateKey,
+    /// Children inside a void element such as `<br>` or `<img>`. HTML has no way to
+    /// write them, so the string renderer leaves them out.
+    VoidElementChildren,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub message: String,
+}
+
+thread_local! {
+    static WARNINGS: RefCell<Vec<Warning>> = const { RefCell::new(Vec::new()) };
+    static SUPPRESSED: RefCell<Vec<WarningKind>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Drains and returns the warnings emitted so far on this thread, oldest first. Only
+/// the latest 100 are kept, so a page nobody reads warnings on doesn't grow forever.
+pub fn take_warnings() -> Vec<Warning> {
+    WARNINGS.with(|warnings| warnings.take())
+}
+
+/// Turns a kind of warning off (or back on) for this thread.
+pub fn set_warning_suppressed(kind: WarningKind, suppressed: bool) {
+    SUPPRESSED.with(|kinds| {
+        let mut kinds = kinds.borrow_mut();
+        kinds.retain(|k| *k != kind);
+        if suppressed {
+            kinds.push(kind);
+        }
+    });
+}
+
+pub(crate) fn warn(kind: WarningKind, message: String) {
+    if SUPPRESSED.with(|kinds| kinds.borrow().contains(&kind)) {
+        return;
+    }
+    push(Warning { kind, message });
+}
+
+fn push(warning: Warning) {
+    WARNINGS.with(|warnings| {
+        let mut warnings = warnings.borrow_mut();
+        if warnings.len() >= MAX_WARNINGS {
+            warnings.remove(0);
+        }
+        warnings.push(warning);
+    });
+}
+
+/// Walks `node`, rendering components along the way, and emits a warning for every
+/// problem it finds. Mounted roots run it on every render in debug builds.
+pub fn check_tree(node: &Node) {
+    check_node(node);
+    check_label_targets(node);
+}
+
+/// `check_tree` for a mounted root, which mostly finds the same problems on every
+/// update: warnings already in `reported` are not emitted again.
+pub(crate) fn check_tree_once(node: &Node, reported: &RefCell<HashSet<Warning>>) {
+    let earlier = take_warnings();
+    check_tree(node);
+    let found = take_warnings();
+    WARNINGS.with(|warnings| *warnings.borrow_mut() = earlier);
+    let mut reported = reported.borrow_mut();
+    for warning in found {
+        if reported.insert(warning.clone()) {
+            push(warning);
+        }
+    }
+}
+
+fn check_node(node: &Node) {
+    match node {
+        Node::Element(element) => {
+            check_duplicate_keys(&element.tag_name, &element.children);
+            if is_void_element(&element.tag_name) && !element.children.is_empty() {
+                warn(
+                    WarningKind::VoidElementChildren,
+                    format!(
+                        "<{}> is a void element; its {} children are left out of the HTML",
+                        element.tag_name,
+                        element.children.len()
+                    ),
+                );
+            }
+            let children = flatten(&element.children);
+            check_unkeyed_list(&element.tag_name, &children);
+            if element.tag_name == "table" {
+                check_table_rows(&children);
+            }
+            for child in &children {
+                check_node(child);
+            }
+        }
+        Node::Component(v_component) => check_node(&v_component.render()),
+        Node::Lazy(lazy) => check_node(&lazy.render()),
+        Node::Async(v_async) => check_node(&v_async.fallback()),
+        Node::ClientOnly(inner) | Node::ServerOnly(inner) => check_node(inner),
+        Node::Fragment(fragment) => {
+            for child in flatten(&fragment.children) {
+                check_node(&child);
+            }
+        }
+        Node::Text(_) | Node::RawHtml(_) => {}
+    }
+}
+
+/// The children as they end up in the DOM: components rendered, fragments spliced in.
+fn flatten(children: &[Node]) -> Vec<Node> {
+    let mut flat = Vec::with_capacity(children.len());
+    for child in children {
+        match child {
+            Node::Component(v_component) => flat.extend(flatten(&[v_component.render()])),
+            Node::Lazy(lazy) => flat.extend(flatten(&[lazy.render()])),
+            Node::Async(v_async) => flat.extend(flatten(&[v_async.fallback()])),
+            Node::ClientOnly(inner) | Node::ServerOnly(inner) => {
+                flat.extend(flatten(std::slice::from_ref(inner.as_ref())))
+            }
+            Node::Fragment(fragment) => flat.extend(flatten(&fragment.children)),
+            _ => flat.push(child.clone()),
+        }
+    }
+    flat
+}
+
+fn check_unkeyed_list(parent: &str, children: &[Node]) {
+    // tag -> (unkeyed, keyed)
+    let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+    for child in children {
+        if let Node::Element(element) = child {
+            let entry = counts.entry(element.tag_name.as_str()).or_default();
+            if element.key.is_some() {
+                entry.1 += 1;
+            } else {
+                entry.0 += 1;
+            }
+        }
+    }
+    let mut suspicious: Vec<_> = counts
+        .into_iter()
+        .filter(|(_, (unkeyed, keyed))| *unkeyed >= UNKEYED_LIST_THRESHOLD && *keyed == 0)
+        .collect();
+    suspicious.sort();
+    for (tag, (count, _)) in suspicious {
+        warn(
+            WarningKind::UnkeyedList,
+            format!(
+                "<{}> has {} <{}> children without keys; give list items a `key` so they can be reordered efficiently",
+                parent, count, tag
+            ),
+        );
+    }
+}
+
+/// Keys are compared the way the diff sees them, so a keyed component or fragment
+/// counts with its own key.
+fn check_duplicate_keys(parent: &str, children: &[Node]) {
+    let mut seen: Vec<String> = Vec::new();
+    let mut reported: Vec<String> = Vec::new();
+    for key in slots(children).into_iter().filter_map(|slot| slot.key) {
+        if !seen.contains(&key) {
+            seen.push(key);
+        } else if !reported.contains(&key) {
+            warn(
+                WarningKind::DuplicateKey,
+                format!(
+                    "<{}> has several children with key {:?}; sibling keys must be unique",
+                    parent, key
+                ),
+            );
+            reported.push(key);
+        }
+    }
+}
+
+fn check_label_targets(node: &Node) {
+    let mut ids = Vec::new();
+    let mut label_targets = Vec::new();
+    collect_label_targets(node, &mut ids, &mut label_targets);
+    for target in label_targets {
+        if !ids.contains(&target) {
+            warn(
+                WarningKind::DanglingLabel,
+                format!(
+                    "<label for=\"{}\"> points at no element with that id",
+                    target
+                ),
+            );
+        }
+    }
+}
+
+/// Gathers every `id` in the tree and the `for` of every `label`.
+fn collect_label_targets(node: &Node, ids: &mut Vec<String>, label_targets: &mut Vec<String>) {
+    match node {
+        Node::Element(element) => {
+            if let Some(id) = element.props.get("id") {
+                ids.push(id.clone());
+            }
+            if element.tag_name == "label"
+                && let Some(target) = element.props.get("for")
+            {
+                label_targets.push(target.clone());
+            }
+            for child in &element.children {
+                collect_label_targets(child, ids, label_targets);
+            }
+        }
+        Node::Fragment(_)
+        | Node::Component(_)
+        | Node::Lazy(_)
+        | Node::Async(_)
+        | Node::ClientOnly(_)
+        | Node::ServerOnly(_) => {
+            for child in flatten(std::slice::from_ref(node)) {
+                collect_label_targets(&child, ids, label_targets);
+            }
+        }
+        Node::Text(_) | Node::RawHtml(_) => {}
+    }
+}
+
+fn check_table_rows(children: &[Node]) {
+    let rows = children
+        .iter()
+        .filter(|child| matches!(child, Node::Element(element) if element.tag_name == "tr"))
+        .count();
+    if rows > 0 {
+        warn(
+            WarningKind::TableRowOutsideSection,
+            format!(
+                "<table> has {} <tr> children; wrap rows in <thead>, <tbody> or <tfoot>",
+                rows
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Element;
+
+    fn list(keyed: bool) -> Node {
+        let items = ["a", "b", "c"].map(|item| {
+            let li = Element::new("li").child(item);
+            if keyed { li.key(item) } else { li }
+        });
+        Node::Element(Element::new("ul").children_from(items))
+    }
+
+    #[test]
+    fn test_unkeyed_list_warns() {
+        check_tree(&list(false));
+
+        let warnings = take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, WarningKind::UnkeyedList);
+        assert!(warnings[0].message.contains("<ul> has 3 <li> children"));
+
+        check_tree(&list(true));
+        assert!(take_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_suppressed_warnings_are_dropped() {
+        set_warning_suppressed(WarningKind::UnkeyedList, true);
+        check_tree(&list(false));
+        assert!(take_warnings().is_empty());
+
+        set_warning_suppressed(WarningKind::UnkeyedList, false);
+        check_tree(&list(false));
+        assert_eq!(take_warnings().len(), 1);
+    }
+
+    #[test]
+    fn test_rows_directly_under_table_warn() {
+        use crate::tag::{table, tbody, td, tr};
+
+        check_tree(&Node::Element(table().child(tr().child(td().child("1")))));
+        let warnings = take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, WarningKind::TableRowOutsideSection);
+
+        check_tree(&Node::Element(
+            table().child(tbody().child(tr().child(td().child("1")))),
+        ));
+        assert!(take_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_label_without_matching_id_warns() {
+        let form = |input_id: &str| {
+            Node::Element(
+                Element::new("form")
+                    .child(Element::new("label").attr("for", "email").child("Email"))
+                    .child(Element::new("textarea").attr("id", input_id)),
+            )
+        };
+
+        check_tree(&form("mail"));
+        let warnings = take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, WarningKind::DanglingLabel);
+        assert!(warnings[0].message.contains("for=\"email\""));
+
+        check_tree(&form("email"));
+        assert!(take_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_sibling_keys_warn() {
+        let list = |second: &str| {
+            Node::Element(
+                Element::new("ul")
+                    .child(Element::new("li").key("a").child("A"))
+                    .child(Element::new("li").key(second).child("B")),
+            )
+        };
+
+        check_tree(&list("a"));
+        let warnings = take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, WarningKind::DuplicateKey);
+        assert!(
+            warnings[0]
+                .message
+                .contains("<ul> has several children with key \"a\"")
+        );
+
+        check_tree(&list("b"));
+        assert!(take_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_root_checks_report_each_warning_once() {
+        let reported = RefCell::default();
+        check_tree_once(&list(false), &reported);
+        check_tree_once(&list(false), &reported);
+
+        let warnings = take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, WarningKind::UnkeyedList);
+    }
+
+    #[test]
+    fn test_warning_channel_keeps_only_the_latest_warnings() {
+        for i in 0..MAX_WARNINGS + 20 {
+            warn(WarningKind::DuplicateKey, format!("warning {}", i));
+        }
+
+        let warnings = take_warnings();
+        assert_eq!(warnings.len(), MAX_WARNINGS);
+        assert_eq!(warnings[0].message, "warning 20");
+        assert_eq!(warnings[MAX_WARNINGS - 1].message, "warning 119");
+    }
+}
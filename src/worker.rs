@@ -0,0 +1,185 @@
+//! An experimental protocol for diffing off the main thread: a web worker builds the
+//! VDOM, diffs it and posts the patches as a JSON message; the main thread decodes
+//! them and applies them to the real DOM.
+//!
+//! Nodes travel as HTML from the string renderer, so what can't be expressed in
+//! markup doesn't survive the trip: listeners, `on_create` callbacks and
+//! `Node::ClientOnly` content. Trees that need those should be rendered on the main
+//! thread.
+
+use serde::{Deserialize, Serialize};
+use web_sys::{Document, Node as DomNode};
+
+use crate::patch::apply_patches;
+use crate::{Node, Patch, RawHtml, render_node_to_string};
+
+/// The serializable form of a `Patch`, with nodes replaced by their markup.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum WirePatch {
+    Replace {
+        path: Vec<usize>,
+        html: String,
+    },
+    ReplaceText {
+        path: Vec<usize>,
+        text: String,
+    },
+    SetAttr {
+        path: Vec<usize>,
+        name: String,
+        value: String,
+    },
+    RemoveAttr {
+        path: Vec<usize>,
+        name: String,
+    },
+    InsertChild {
+        path: Vec<usize>,
+        index: usize,
+        html: String,
+    },
+    RemoveChild {
+        path: Vec<usize>,
+        index: usize,
+    },
+    MoveChild {
+        path: Vec<usize>,
+        from: usize,
+        count: usize,
+        to: usize,
+    },
+    UpdateClasses {
+        path: Vec<usize>,
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+}
+
+/// Converts `patches` to their wire form. `SetListeners` patches are dropped.
+pub fn to_wire_patches(patches: &[Patch]) -> Vec<WirePatch> {
+    patches
+        .iter()
+        .filter_map(|patch| {
+            Some(match patch.clone() {
+                Patch::Replace { path, node } => WirePatch::Replace {
+                    path,
+                    html: render_node_to_string(&node),
+                },
+                Patch::ReplaceText { path, text } => WirePatch::ReplaceText { path, text },
+                Patch::SetAttr { path, name, value } => WirePatch::SetAttr { path, name, value },
+                Patch::RemoveAttr { path, name } => WirePatch::RemoveAttr { path, name },
+                Patch::InsertChild { path, index, node } => WirePatch::InsertChild {
+                    path,
+                    index,
+                    html: render_node_to_string(&node),
+                },
+                Patch::RemoveChild { path, index } => WirePatch::RemoveChild { path, index },
+                Patch::MoveChild {
+                    path,
+                    from,
+                    count,
+                    to,
+                } => WirePatch::MoveChild {
+                    path,
+                    from,
+                    count,
+                    to,
+                },
+                Patch::UpdateClasses { path, add, remove } => {
+                    WirePatch::UpdateClasses { path, add, remove }
+                }
+                Patch::SetListeners { .. } => return None,
+            })
+        })
+        .collect()
+}
+
+/// The message a worker posts for `patches`.
+pub fn encode_patches(patches: &[Patch]) -> String {
+    serde_json::to_string(&to_wire_patches(patches)).expect("Wire patches always serialize")
+}
+
+/// Parses a message produced by `encode_patches`.
+pub fn decode_patches(message: &str) -> Result<Vec<WirePatch>, serde_json::Error> {
+    serde_json::from_str(message)
+}
+
+/// Applies decoded patches to the DOM subtree rooted at `root`, like
+/// `RootHandle::update` does with local patches.
+pub fn apply_wire_patches(patches: &[WirePatch], root: &DomNode, document: &Document) {
+    let patches: Vec<Patch> = patches
+        .iter()
+        .cloned()
+        .map(|patch| match patch {
+            WirePatch::Replace { path, html } => Patch::Replace {
+                path,
+                node: Node::RawHtml(RawHtml::new(html)),
+            },
+            WirePatch::ReplaceText { path, text } => Patch::ReplaceText { path, text },
+            WirePatch::SetAttr { path, name, value } => Patch::SetAttr { path, name, value },
+            WirePatch::RemoveAttr { path, name } => Patch::RemoveAttr { path, name },
+            WirePatch::InsertChild { path, index, html } => Patch::InsertChild {
+                path,
+                index,
+                node: Node::RawHtml(RawHtml::new(html)),
+            },
+            WirePatch::RemoveChild { path, index } => Patch::RemoveChild { path, index },
+            WirePatch::MoveChild {
+                path,
+                from,
+                count,
+                to,
+            } => Patch::MoveChild {
+                path,
+                from,
+                count,
+                to,
+            },
+            WirePatch::UpdateClasses { path, add, remove } => {
+                Patch::UpdateClasses { path, add, remove }
+            }
+        })
+        .collect();
+    apply_patches(&patches, root, document);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Element, diff};
+
+    #[test]
+    fn test_patches_round_trip_through_wire_format() {
+        let old = Node::Element(Element::new("ul").attr("class", "todo").child("empty"));
+        let new = Node::Element(
+            Element::new("ul")
+                .on_click(|_| {})
+                .child("First")
+                .child(Element::new("li").attr("id", "next").child("Second")),
+        );
+
+        let message = encode_patches(&diff(&old, &new));
+        let decoded = decode_patches(&message).unwrap();
+
+        assert_eq!(
+            decoded,
+            vec![
+                WirePatch::RemoveAttr {
+                    path: vec![],
+                    name: "class".to_string(),
+                },
+                WirePatch::ReplaceText {
+                    path: vec![0],
+                    text: "First".to_string(),
+                },
+                WirePatch::InsertChild {
+                    path: vec![],
+                    index: 1,
+                    html: "<li id=\"next\">Second</li>".to_string(),
+                },
+            ]
+        );
+        assert!(message.contains("\"op\":\"insert_child\""));
+    }
+}
@@ -0,0 +1,938 @@
+//! Browser tests for the DOM renderer. They need a real `document`, so they only
+//! compile for wasm32. Run them with `wasm-pack test --headless --firefox` (or
+//! `--chrome`).
+#![cfg(target_arch = "wasm32")]
+
+use rusty_react::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+use web_sys::{Document, Element as DomElement};
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+fn document() -> Document {
+    web_sys::window().unwrap().document().unwrap()
+}
+
+/// Appends a fresh, empty mount point to the test page's body.
+fn mount_point(id: &str) -> DomElement {
+    let document = document();
+    let element = document.create_element("div").unwrap();
+    element.set_id(id);
+    document.body().unwrap().append_child(&element).unwrap();
+    element
+}
+
+#[wasm_bindgen_test]
+fn test_render_returns_the_root_element() {
+    let root = mount_point("render-returns-root");
+
+    let app = render("render-returns-root".to_string());
+
+    assert_eq!(Some(app.clone()), root.first_element_child());
+    assert_eq!(app.get_attribute("class").as_deref(), Some("app-container"));
+}
+
+#[wasm_bindgen_test]
+fn test_render_mounts_app_elements() {
+    let root = mount_point("render-elements");
+
+    render("render-elements".to_string());
+
+    assert_eq!(root.child_element_count(), 1);
+    let app = root.first_element_child().unwrap();
+    assert_eq!(app.tag_name(), "DIV");
+    let children = app.children();
+    assert_eq!(children.length(), 2);
+    assert_eq!(children.item(0).unwrap().tag_name(), "H1");
+    assert_eq!(children.item(1).unwrap().tag_name(), "P");
+}
+
+#[wasm_bindgen_test]
+fn test_render_sets_app_attributes() {
+    let root = mount_point("render-attributes");
+
+    render("render-attributes".to_string());
+
+    let app = root.first_element_child().unwrap();
+    assert_eq!(app.get_attribute("class").as_deref(), Some("app-container"));
+    assert_eq!(app.get_attribute("data-rendered-by").as_deref(), Some("rusty-react"));
+    assert_eq!(app.first_element_child().unwrap().attributes().length(), 0);
+}
+
+#[wasm_bindgen_test]
+fn test_render_creates_text_nodes() {
+    let root = mount_point("render-text");
+
+    render("render-text".to_string());
+
+    let heading = root.query_selector("h1").unwrap().unwrap();
+    let text = heading.first_child().unwrap();
+    assert_eq!(text.node_type(), web_sys::Node::TEXT_NODE);
+    assert_eq!(text.text_content().as_deref(), Some("Hello from a Rusty Component!"));
+    assert_eq!(
+        root.query_selector("p").unwrap().unwrap().text_content().as_deref(),
+        Some("This was rendered via a component trait.")
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_render_replaces_previous_content() {
+    let root = mount_point("render-replace");
+    root.set_inner_html("<span>stale</span>");
+
+    render("render-replace".to_string());
+    render("render-replace".to_string());
+
+    assert_eq!(root.child_element_count(), 1);
+    assert!(root.query_selector("span").unwrap().is_none());
+}
+
+#[wasm_bindgen_test]
+fn test_dom_to_vdom_reconstructs_elements_and_text() {
+    let document = document();
+    let section: DomElement = document.create_element("section").unwrap();
+    section.set_attribute("class", "card").unwrap();
+    let heading = document.create_element("h2").unwrap();
+    heading.append_child(&document.create_text_node("Title")).unwrap();
+    section.append_child(&heading).unwrap();
+    section.append_child(&document.create_comment("ignored")).unwrap();
+    section.append_child(&document.create_text_node("Body")).unwrap();
+
+    let Node::Element(root) = dom_to_vdom(&section) else {
+        panic!("An element should convert to Node::Element");
+    };
+    assert_eq!(root.tag_name, "section");
+    assert_eq!(root.props.get("class").unwrap(), "card");
+    assert_eq!(root.children.len(), 2);
+    assert_eq!(
+        render_node_to_string(&Node::Element(root)),
+        r#"<section class="card"><h2>Title</h2>Body</section>"#
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_on_create_receives_the_created_element() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let document = document();
+    let container = document.create_element("div").unwrap();
+    let seen = Rc::new(RefCell::new(None));
+
+    let recorder = seen.clone();
+    let vdom = Node::Element(
+        Element::new("canvas")
+            .attr("id", "chart")
+            .on_create(move |el| *recorder.borrow_mut() = Some((el.tag_name(), el.id()))),
+    );
+    render_node_to_dom(&vdom, &document, &container);
+
+    assert_eq!(
+        *seen.borrow(),
+        Some(("CANVAS".to_string(), "chart".to_string()))
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_update_patches_the_existing_node() {
+    mount_point("update-attr");
+    let card = |class: &str| {
+        Node::Element(
+            Element::new("div")
+                .attr("class", class)
+                .attr("id", "card")
+                .child("Hello"),
+        )
+    };
+    let handle = mount(card("old"), "update-attr");
+    let before = handle.container().first_element_child().unwrap();
+
+    handle.update(card("new"));
+
+    let after = handle.container().first_element_child().unwrap();
+    assert!(before.is_same_node(Some(&after)), "the element should be reused");
+    assert_eq!(after.get_attribute("class").as_deref(), Some("new"));
+    assert_eq!(after.id(), "card");
+    assert_eq!(after.text_content().as_deref(), Some("Hello"));
+}
+
+#[wasm_bindgen_test]
+fn test_form_values_collects_named_fields() {
+    use wasm_bindgen::JsCast;
+    use web_sys::HtmlInputElement;
+
+    mount_point("form-values");
+    let signup = form()
+        .attr("id", "signup")
+        .child(Element::new("input").attr("name", "email"))
+        .child(Element::new("input").attr("name", "nickname"))
+        .child(Element::new("input").attr("name", "newsletter").attr("type", "checkbox"));
+    let handle = mount(Node::Element(signup), "form-values");
+
+    let fill = |name: &str, value: &str| {
+        let selector = format!("input[name={}]", name);
+        let input = handle.container().query_selector(&selector).unwrap().unwrap();
+        input.unchecked_into::<HtmlInputElement>().set_value(value);
+    };
+    fill("email", "ada@example.com");
+    fill("nickname", "ada");
+
+    let values = handle.form_values("signup");
+    assert_eq!(values.len(), 2);
+    assert_eq!(values["email"], "ada@example.com");
+    assert_eq!(values["nickname"], "ada");
+    assert!(handle.form_values("missing").is_empty());
+}
+
+#[wasm_bindgen_test]
+fn test_canvas_renderer_draws_rect() {
+    let canvas: web_sys::HtmlCanvasElement =
+        document().create_element("canvas").unwrap().dyn_into().unwrap();
+    let mut renderer = CanvasRenderer::from_canvas(&canvas);
+
+    let scene = Element::new("scene")
+        .child(
+            Element::new("rect")
+                .attr("x", "10")
+                .attr("y", "10")
+                .attr("width", "20")
+                .attr("height", "20")
+                .attr("fill", "#ff0000"),
+        )
+        .child(Element::new("text").attr("x", "0").attr("y", "50").child("score: 3"));
+    renderer.render(&Node::Element(scene));
+
+    // Drawing went through without panicking and left the rect's fill on the context.
+    assert_eq!(renderer.context().fill_style().as_string().unwrap(), "#ff0000");
+}
+
+#[wasm_bindgen_test]
+fn test_target_value_reads_input_event_target() {
+    let input: web_sys::HtmlInputElement =
+        document().create_element("input").unwrap().dyn_into().unwrap();
+    input.set_value("typed text");
+
+    let event = web_sys::Event::new("input").unwrap();
+    input.dispatch_event(&event).unwrap();
+
+    assert_eq!(target_value(&event).as_deref(), Some("typed text"));
+}
+
+#[wasm_bindgen_test]
+fn test_scheduled_updates_are_coalesced() {
+    let root = mount_point("schedule-update");
+    let handle = mount(Node::Text("0".to_string()), "schedule-update");
+
+    for count in 1..=3 {
+        handle.schedule_update(Node::Text(count.to_string()));
+    }
+    assert_eq!(root.text_content().unwrap(), "0");
+
+    // All three updates collapse into one reconcile against the last tree.
+    assert!(handle.flush());
+    assert_eq!(root.text_content().unwrap(), "3");
+    assert!(!handle.flush());
+}
+
+#[wasm_bindgen_test]
+fn test_update_with_identical_tree_skips_dom_mutations() {
+    let root = mount_point("identical-update");
+    let tree = || Node::Element(Element::new("p").attr("class", "note").child("unchanged"));
+    let handle = mount(tree(), "identical-update");
+
+    let observer = web_sys::MutationObserver::new(&js_sys::Function::new_no_args("")).unwrap();
+    let options = web_sys::MutationObserverInit::new();
+    options.set_subtree(true);
+    options.set_child_list(true);
+    options.set_attributes(true);
+    options.set_character_data(true);
+    observer.observe_with_options(&root, &options).unwrap();
+
+    handle.update(tree());
+
+    assert_eq!(observer.take_records().length(), 0);
+    observer.disconnect();
+}
+
+#[wasm_bindgen_test]
+fn test_mount_point_exists() {
+    mount_point("exists-check");
+
+    assert!(mount_point_exists("exists-check"));
+    assert!(!mount_point_exists("no-such-mount-point"));
+}
+
+#[wasm_bindgen_test]
+fn test_adjacent_text_siblings_share_one_dom_text_node() {
+    let root = mount_point("coalesce-text");
+    let cells = Element::new("td").child("1").child(", ").child("2").child(Element::new("br")).child("3");
+
+    mount(Node::Element(cells), "coalesce-text");
+
+    let td = root.first_element_child().unwrap();
+    assert_eq!(td.child_nodes().length(), 3);
+    assert_eq!(td.first_child().unwrap().text_content().unwrap(), "1, 2");
+}
+
+#[wasm_bindgen_test]
+fn test_identical_rows_are_cloned_instead_of_created() {
+    mount_point("cloned-rows");
+    let row = || {
+        Element::new("tr")
+            .child(Element::new("td").child("0"))
+            .child(Element::new("td").child("-"))
+    };
+    let table = Element::new("tbody").children_from((0..50).map(|_| row()));
+
+    // Count document.createElement calls while mounting.
+    js_sys::eval(
+        "window.__createCount = 0;
+         window.__createElement = document.createElement;
+         document.createElement = function (...args) {
+             window.__createCount++;
+             return window.__createElement.apply(this, args);
+         };",
+    )
+    .unwrap();
+    mount(Node::Element(table), "cloned-rows");
+    let created =
+        js_sys::eval("document.createElement = window.__createElement; window.__createCount")
+            .unwrap()
+            .as_f64()
+            .unwrap();
+
+    let root = document().get_element_by_id("cloned-rows").unwrap();
+    let tbody = root.first_element_child().unwrap();
+    assert_eq!(tbody.child_element_count(), 50);
+    // tbody plus one row built from scratch (tr and two tds); the other 49 are clones.
+    assert_eq!(created, 4.0);
+}
+
+#[wasm_bindgen_test]
+fn test_hydrate_splits_merged_text_nodes() {
+    let root = mount_point("hydrate-text");
+    let counter = |count: &'static str| {
+        Node::Element(
+            Element::new("p")
+                .child("Count: ")
+                .child(Lazy::new(move || Node::Text(count.to_string()))),
+        )
+    };
+    // The server rendered both text siblings, which the browser parsed as one node.
+    root.set_inner_html(&render_node_to_string(&counter("1")));
+    let paragraph = root.first_element_child().unwrap();
+    assert_eq!(paragraph.child_nodes().length(), 1);
+
+    let handle = hydrate(counter("1"), "hydrate-text");
+
+    assert!(take_warnings().is_empty());
+    assert_eq!(paragraph.child_nodes().length(), 2);
+    handle.update(counter("2"));
+    assert!(root.first_element_child().unwrap().is_same_node(Some(&paragraph)));
+    assert_eq!(paragraph.text_content().unwrap(), "Count: 2");
+}
+
+#[wasm_bindgen_test]
+fn test_compiled_template_only_renders_holes_per_instance() {
+    let root = mount_point("compiled-template");
+    let card = Node::Element(
+        Element::new("article")
+            .child(Element::new("header").child(Element::new("h2").child("User")))
+            .child(Element::new("p").hole("name").child("?")),
+    );
+    let template = compile(&card);
+
+    js_sys::eval(
+        "window.__createCount = 0;
+         window.__createElement = document.createElement;
+         document.createElement = function (...args) {
+             window.__createCount++;
+             return window.__createElement.apply(this, args);
+         };",
+    )
+    .unwrap();
+    for name in ["Ada", "Grace"] {
+        let instance = template.instantiate(&[("name", Node::Text(name.to_string()))]);
+        root.append_child(&instance).unwrap();
+    }
+    let created =
+        js_sys::eval("document.createElement = window.__createElement; window.__createCount")
+            .unwrap()
+            .as_f64()
+            .unwrap();
+
+    // The static article, header and h2 are cloned, not created again.
+    assert_eq!(created, 0.0);
+    assert_eq!(
+        root.inner_html(),
+        "<article><header><h2>User</h2></header><p>Ada</p></article>\
+         <article><header><h2>User</h2></header><p>Grace</p></article>"
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_render_into_iframe_uses_its_document() {
+    let document = document();
+    let iframe: web_sys::HtmlIFrameElement =
+        document.create_element("iframe").unwrap().dyn_into().unwrap();
+    iframe.set_id("preview-frame");
+    document.body().unwrap().append_child(&iframe).unwrap();
+
+    render_into_iframe(
+        Node::Element(Element::new("h1").child("Preview")),
+        "preview-frame",
+    );
+
+    let body = iframe.content_document().unwrap().body().unwrap();
+    assert_eq!(body.inner_html(), "<h1>Preview</h1>");
+}
+
+#[wasm_bindgen_test]
+fn test_render_into_window_uses_the_other_document() {
+    let document = document();
+    let iframe: web_sys::HtmlIFrameElement =
+        document.create_element("iframe").unwrap().dyn_into().unwrap();
+    document.body().unwrap().append_child(&iframe).unwrap();
+    let other_window = iframe.content_window().unwrap();
+    let other_document = iframe.content_document().unwrap();
+    let mount = other_document.create_element("div").unwrap();
+    mount.set_id("popup-root");
+    other_document.body().unwrap().append_child(&mount).unwrap();
+
+    let root = render_into_window(
+        Node::Element(Element::new("p").child("Popup")),
+        &other_window,
+        "popup-root",
+    );
+    assert_eq!(mount.inner_html(), "<p>Popup</p>");
+    assert_eq!(root.container().owner_document(), Some(other_document));
+
+    root.update(Node::Element(Element::new("p").child("Updated")));
+    assert_eq!(mount.inner_html(), "<p>Updated</p>");
+}
+
+#[wasm_bindgen_test]
+fn test_get_element_by_key_finds_rendered_element() {
+    mount_point("element-by-key");
+    let list = |items: &[&str]| {
+        Node::Element(Element::new("ul").children_from(
+            items.iter().map(|item| Element::new("li").key(*item).child(*item)),
+        ))
+    };
+    let handle = mount(list(&["a", "b"]), "element-by-key");
+    handle.update(list(&["c", "a", "b"]));
+
+    let item = handle.get_element_by_key("b").unwrap();
+    assert_eq!(item.tag_name(), "LI");
+    assert_eq!(item.text_content().unwrap(), "b");
+    assert!(handle.get_element_by_key("missing").is_none());
+}
+
+/// Resolves after `ms` milliseconds, so tests can wait for timers to fire.
+async fn sleep(ms: i32) {
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        web_sys::window()
+            .unwrap()
+            .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, ms)
+            .unwrap();
+    });
+    wasm_bindgen_futures::JsFuture::from(promise).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_debounced_input_fires_once_after_rapid_inputs() {
+    let root = mount_point("debounced-input");
+    let calls = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let seen = calls.clone();
+    mount(
+        Node::Element(Element::new("textarea").on_input_debounced(20, move |event| {
+            seen.borrow_mut().push(target_value(event).unwrap());
+        })),
+        "debounced-input",
+    );
+
+    let field: web_sys::HtmlTextAreaElement =
+        root.first_element_child().unwrap().dyn_into().unwrap();
+    for value in ["r", "ru", "rus"] {
+        field.set_value(value);
+        field
+            .dispatch_event(&web_sys::Event::new("input").unwrap())
+            .unwrap();
+    }
+    assert!(calls.borrow().is_empty());
+
+    sleep(60).await;
+    assert_eq!(*calls.borrow(), vec!["rus".to_string()]);
+}
+
+#[wasm_bindgen_test]
+async fn test_removing_a_debounced_input_cancels_its_timer() {
+    let root = mount_point("removed-debounced-input");
+    let calls = std::rc::Rc::new(std::cell::Cell::new(0));
+    let seen = calls.clone();
+    let form = |with_field: bool| {
+        let seen = seen.clone();
+        let field = Element::new("textarea")
+            .on_input_debounced(20, move |_| seen.set(seen.get() + 1));
+        let form = Element::new("form");
+        Node::Element(if with_field { form.child(field) } else { form })
+    };
+    let handle = mount(form(true), "removed-debounced-input");
+    let field = root.query_selector("textarea").unwrap().unwrap();
+    field
+        .dispatch_event(&web_sys::Event::new("input").unwrap())
+        .unwrap();
+
+    handle.update(form(false));
+    // Put back by hand, so only the cancelled timer keeps the handler from running.
+    root.first_element_child().unwrap().append_child(&field).unwrap();
+    sleep(60).await;
+
+    assert_eq!(calls.get(), 0);
+}
+
+#[wasm_bindgen_test]
+fn test_insert_node_at_places_node_at_index() {
+    let document = document();
+    let root = mount_point("insert-node-at");
+    for item in ["a", "b", "c"] {
+        render_node_to_dom(&Node::Element(Element::new("li").child(item)), &document, &root);
+    }
+
+    insert_node_at(&root, &Node::Element(Element::new("li").child("new")), 1, &document);
+
+    assert_eq!(
+        root.inner_html(),
+        "<li>a</li><li>new</li><li>b</li><li>c</li>"
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_bind_syncs_input_and_signal_both_ways() {
+    let root = mount_point("bound-input");
+    let name = Signal::new("Ada".to_string());
+    mount(Node::Element(Element::new("input").bind(&name)), "bound-input");
+    let input: web_sys::HtmlInputElement =
+        root.first_element_child().unwrap().dyn_into().unwrap();
+    assert_eq!(input.value(), "Ada");
+
+    input.set_value("Grace");
+    input
+        .dispatch_event(&web_sys::Event::new("input").unwrap())
+        .unwrap();
+    assert_eq!(name.get(), "Grace");
+
+    name.set("Edsger".to_string());
+    assert_eq!(input.value(), "Edsger");
+}
+
+#[wasm_bindgen_test]
+fn test_bind_stops_writing_to_removed_fields() {
+    let root = mount_point("removed-bound-input");
+    let name = Signal::new("Ada".to_string());
+    let handle = mount(
+        Node::Element(Element::new("form").child(Element::new("input").bind(&name))),
+        "removed-bound-input",
+    );
+    let input: web_sys::HtmlInputElement =
+        root.query_selector("input").unwrap().unwrap().dyn_into().unwrap();
+
+    handle.update(Node::Element(Element::new("form")));
+    name.set("Grace".to_string());
+    name.set("Edsger".to_string());
+
+    assert!(!input.is_connected());
+    assert_eq!(input.value(), "Ada");
+}
+
+#[wasm_bindgen_test]
+fn test_dialog_traps_focus_and_closes_on_escape() {
+    let document = document();
+    let opener: web_sys::HtmlElement = document.create_element("button").unwrap().unchecked_into();
+    document.body().unwrap().append_child(&opener).unwrap();
+    opener.focus().unwrap();
+
+    let closed = std::rc::Rc::new(std::cell::Cell::new(false));
+    let on_close = closed.clone();
+    let handle = Dialog::new(
+        Element::new("div")
+            .child(Element::new("button").attr("id", "dialog-ok").child("OK"))
+            .child(Element::new("button").attr("id", "dialog-cancel").child("Cancel")),
+    )
+    .label("Confirm")
+    .on_close(move || on_close.set(true))
+    .open();
+
+    let active_id = || document.active_element().map(|element| element.id());
+    assert_eq!(active_id().as_deref(), Some("dialog-ok"));
+
+    let press = |key: &str, shift: bool| {
+        let init = web_sys::KeyboardEventInit::new();
+        init.set_key(key);
+        init.set_shift_key(shift);
+        init.set_bubbles(true);
+        let event = web_sys::KeyboardEvent::new_with_keyboard_event_init_dict("keydown", &init)
+            .unwrap();
+        document.active_element().unwrap().dispatch_event(&event).unwrap();
+    };
+    press("Tab", true);
+    assert_eq!(active_id().as_deref(), Some("dialog-cancel"));
+    press("Tab", false);
+    assert_eq!(active_id().as_deref(), Some("dialog-ok"));
+
+    press("Escape", false);
+    assert!(!handle.is_open());
+    assert!(closed.get());
+    assert!(document.get_element_by_id("dialog-ok").is_none());
+    assert_eq!(document.active_element().unwrap(), *opener.unchecked_ref::<DomElement>());
+}
+
+#[wasm_bindgen_test]
+fn test_dialog_closes_on_escape_after_its_handle_is_dropped() {
+    let closed = std::rc::Rc::new(std::cell::Cell::new(false));
+    let on_close = closed.clone();
+    let handle = Dialog::new(Element::new("p").child("Saved"))
+        .on_close(move || on_close.set(true))
+        .open();
+    let dialog = handle.element().clone();
+    drop(handle);
+
+    let init = web_sys::KeyboardEventInit::new();
+    init.set_key("Escape");
+    let event =
+        web_sys::KeyboardEvent::new_with_keyboard_event_init_dict("keydown", &init).unwrap();
+    dialog.dispatch_event(&event).unwrap();
+
+    assert!(closed.get());
+    assert!(!dialog.is_connected());
+}
+
+#[wasm_bindgen_test]
+fn test_class_list_update_keeps_foreign_classes() {
+    let container = mount_point("class-list");
+    let item =
+        |classes: &[&str]| Node::Element(Element::new("li").class_list(classes.iter().copied()));
+    let root = mount(item(&["item", "selected"]), "class-list");
+    let li = container.first_element_child().unwrap();
+    // Added by some other script, e.g. an animation library.
+    li.class_list().add_1("fade-in").unwrap();
+
+    root.update(item(&["item", "done"]));
+
+    assert_eq!(li.get_attribute("class").unwrap(), "item fade-in done");
+}
+
+#[wasm_bindgen_test]
+fn test_math_subtree_uses_mathml_namespace() {
+    let container = mount_point("mathml");
+    let formula = |power: &str| {
+        Node::Element(Element::new("p").child(Element::new("math").child(
+            Element::new("msup")
+                .child(Element::new("mi").child("x"))
+                .child(Element::new(power).child("2")),
+        )))
+    };
+    let root = mount(formula("mn"), "mathml");
+
+    const MATHML: &str = "http://www.w3.org/1998/Math/MathML";
+    let paragraph = container.first_element_child().unwrap();
+    assert_eq!(paragraph.namespace_uri().as_deref(), Some("http://www.w3.org/1999/xhtml"));
+    let math = paragraph.first_element_child().unwrap();
+    assert_eq!(math.namespace_uri().as_deref(), Some(MATHML));
+    let msup = container.query_selector("msup").unwrap().unwrap();
+    assert_eq!(msup.namespace_uri().as_deref(), Some(MATHML));
+
+    // A node created by a patch ends up in the namespace of its parent.
+    root.update(formula("mi"));
+    let replaced = msup.last_element_child().unwrap();
+    assert_eq!(replaced.local_name(), "mi");
+    assert_eq!(replaced.namespace_uri().as_deref(), Some(MATHML));
+}
+
+#[wasm_bindgen_test]
+fn test_apply_patches_sets_attribute() {
+    let document = document();
+    let container = mount_point("apply-patches");
+    let old = Node::Element(Element::new("button").attr("class", "idle").child("Save"));
+    render_node_to_dom(&old, &document, &container);
+    let button = container.first_element_child().unwrap();
+
+    let patches = vec![Patch::SetAttr {
+        path: vec![],
+        name: "class".to_string(),
+        value: "busy".to_string(),
+    }];
+    apply_patches(&patches, &button, &document);
+
+    assert_eq!(button.get_attribute("class").as_deref(), Some("busy"));
+    assert_eq!(button.text_content().as_deref(), Some("Save"));
+}
+
+#[derive(Debug, Clone)]
+struct BetaBanner;
+
+impl Component for BetaBanner {
+    fn render(&self) -> Node {
+        flagged("web-beta-banner", Element::new("p").child("Beta!"))
+    }
+
+    fn clone_rc(&self) -> std::rc::Rc<dyn Component> {
+        std::rc::Rc::new(self.clone())
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_update_shows_component_output_from_changed_global_state() {
+    let container = mount_point("beta-banner");
+    let page = || Node::Element(Element::new("main").child(component(BetaBanner)));
+    let root = mount(page(), "beta-banner");
+    assert_eq!(container.inner_html(), "<main></main>");
+
+    set_flag("web-beta-banner", true);
+    root.update(page());
+
+    assert_eq!(container.inner_html(), "<main><p>Beta!</p></main>");
+}
+
+#[wasm_bindgen_test]
+async fn test_intersection_lazy_swaps_in_content_once_in_view() {
+    let container = mount_point("intersection-lazy");
+    let chart = IntersectionLazy::new(Element::new("figure").child("Chart"));
+    let page = {
+        let chart = chart.clone();
+        move || Node::Component(component(chart.clone()))
+    };
+    let root = mount(page(), "intersection-lazy");
+    assert_eq!(container.inner_html(), "<div></div>");
+
+    let handle = root.clone();
+    let id = chart.subscribe(move |_| handle.schedule_update(page()));
+    // The mount point is in view, so the observer reports it right away.
+    sleep(100).await;
+
+    assert_eq!(container.inner_html(), "<figure>Chart</figure>");
+    chart.unsubscribe(id);
+}
+
+#[derive(Debug, Clone)]
+struct Modal;
+
+impl Component for Modal {
+    fn render(&self) -> Node {
+        Node::Element(Element::new("dialog").key("modal").child("Saved"))
+    }
+
+    fn clone_rc(&self) -> std::rc::Rc<dyn Component> {
+        std::rc::Rc::new(self.clone())
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_get_element_by_key_finds_keyed_element_of_unkeyed_component() {
+    mount_point("element-by-key-component");
+    let page = Node::Element(
+        Element::new("main")
+            .child(Element::new("h1").child("Settings"))
+            .child(component(Modal)),
+    );
+    let handle = mount(page, "element-by-key-component");
+
+    let dialog = handle.get_element_by_key("modal").unwrap();
+    assert_eq!(dialog.tag_name(), "DIALOG");
+    assert_eq!(dialog.text_content().unwrap(), "Saved");
+}
+
+#[wasm_bindgen_test]
+fn test_update_replaces_raw_html_at_the_root() {
+    let container = mount_point("raw-html-root");
+    let root = mount(Node::RawHtml(RawHtml::new("<b>Draft</b>")), "raw-html-root");
+    assert_eq!(container.inner_html(), "<b>Draft</b>");
+
+    root.update(Node::Fragment(Fragment {
+        children: vec![
+            Node::RawHtml(RawHtml::new("<i>Published</i>")),
+            Node::Text(" today".to_string()),
+        ],
+        key: None,
+    }));
+
+    assert_eq!(container.inner_html(), "<i>Published</i> today");
+}
+
+#[wasm_bindgen_test]
+fn test_signing_in_swaps_guest_content_for_authenticated_content() {
+    let container = mount_point("auth-header");
+    let header = || {
+        Node::Element(
+            Element::new("header")
+                .child(component(Authenticated::new(Element::new("a").child("Account"))))
+                .child(component(Guest::new(Element::new("a").child("Sign in")))),
+        )
+    };
+    let root = mount(header(), "auth-header");
+    let handle = root.clone();
+    let id = subscribe_auth(move || handle.update(header()));
+    assert_eq!(container.inner_html(), "<header><a>Sign in</a></header>");
+
+    set_current_user(Some("ada".to_string()));
+    assert_eq!(container.inner_html(), "<header><a>Account</a></header>");
+
+    set_current_user(None);
+    assert_eq!(container.inner_html(), "<header><a>Sign in</a></header>");
+    unsubscribe_auth(id);
+}
+
+#[wasm_bindgen_test]
+fn test_fallible_handler_reports_its_error() {
+    let container = mount_point("fallible-handler");
+    let reported = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let recorder = reported.clone();
+    set_event_error_handler(move |error| recorder.borrow_mut().push(error.clone()));
+    mount(
+        Node::Element(
+            Element::new("button")
+                .on_fallible("click", |_| Err("nothing to save"))
+                .child("Save"),
+        ),
+        "fallible-handler",
+    );
+
+    let button: web_sys::HtmlElement = container.first_element_child().unwrap().unchecked_into();
+    button.click();
+
+    assert_eq!(
+        *reported.borrow(),
+        [EventError {
+            event: "click".to_string(),
+            message: "nothing to save".to_string(),
+        }]
+    );
+}
+
+#[derive(Debug, Clone)]
+struct ImportedArticle;
+
+impl Component for ImportedArticle {
+    fn render(&self) -> Node {
+        Node::Element(
+            Element::new("article")
+                .child(RawHtml::new("<!-- imported --><b>Bold</b>"))
+                .child(Element::new("p").child("After")),
+        )
+    }
+
+    fn clone_rc(&self) -> std::rc::Rc<dyn Component> {
+        std::rc::Rc::new(self.clone())
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_hydrate_keeps_comments_that_belong_to_raw_html() {
+    let root = mount_point("hydrate-raw-comment");
+    let options = RenderOptions {
+        hydration_markers: true,
+        ..Default::default()
+    };
+    root.set_inner_html(&render_node_to_string_with(
+        &Node::Component(component(ImportedArticle)),
+        &options,
+    ));
+
+    hydrate(Node::Component(component(ImportedArticle)), "hydrate-raw-comment");
+
+    assert!(take_warnings().is_empty());
+    assert_eq!(
+        root.inner_html(),
+        "<article><!-- imported --><b>Bold</b><p>After</p></article>"
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_hydrate_replaces_mismatched_attributes_and_warns() {
+    let root = mount_point("hydrate-attributes");
+    root.set_inner_html(r#"<a href="/old" target="_blank">Docs</a>"#);
+    let link = root.first_element_child().unwrap();
+
+    hydrate(
+        Node::Element(Element::new("a").attr("href", "/docs").child("Docs")),
+        "hydrate-attributes",
+    );
+
+    let warnings = take_warnings();
+    assert_eq!(warnings.len(), 2);
+    assert!(
+        warnings
+            .iter()
+            .all(|warning| warning.kind == WarningKind::HydrationAttributeMismatch)
+    );
+    // The element was adopted, not rendered again.
+    assert!(root.first_element_child().unwrap().is_same_node(Some(&link)));
+    assert_eq!(root.inner_html(), r#"<a href="/docs">Docs</a>"#);
+}
+
+#[wasm_bindgen_test]
+fn test_hydrate_adopts_manifest_markup_and_keeps_its_ids() {
+    let root = mount_point("hydrate-manifest");
+    let clicks = std::rc::Rc::new(std::cell::Cell::new(0));
+    let counter = clicks.clone();
+    let button = Node::Element(
+        Element::new("button")
+            .on("click", move |_| counter.set(counter.get() + 1))
+            .child("Add"),
+    );
+    let (html, manifest) = render_node_to_string_with_manifest(&button);
+    root.set_inner_html(&html);
+
+    hydrate(button, "hydrate-manifest");
+
+    assert!(take_warnings().is_empty());
+    let element: web_sys::HtmlElement = root.first_element_child().unwrap().unchecked_into();
+    assert_eq!(element.get_attribute(EVENT_ID_ATTRIBUTE), Some(manifest[0].0.clone()));
+    element.click();
+    assert_eq!(clicks.get(), 1);
+}
+
+#[derive(Debug, Clone)]
+struct Ticker {
+    value: u32,
+    renders: std::rc::Rc<std::cell::Cell<u32>>,
+}
+
+impl Component for Ticker {
+    fn render(&self) -> Node {
+        self.renders.set(self.renders.get() + 1);
+        Node::Element(Element::new("output").child(self.value.to_string()))
+    }
+
+    fn clone_rc(&self) -> std::rc::Rc<dyn Component> {
+        std::rc::Rc::new(self.clone())
+    }
+}
+
+#[wasm_bindgen_test]
+async fn test_scheduled_updates_in_one_tick_reconcile_once_after_the_microtask() {
+    let root = mount_point("scheduled-updates");
+    let renders = std::rc::Rc::new(std::cell::Cell::new(0));
+    let ticker = |value| {
+        Node::Component(component(Ticker {
+            value,
+            renders: renders.clone(),
+        }))
+    };
+    let handle = mount(ticker(0), "scheduled-updates");
+    renders.set(0);
+
+    handle.schedule_update(ticker(1));
+    handle.schedule_update(ticker(2));
+    handle.schedule_update(ticker(3));
+    assert_eq!(root.text_content().unwrap(), "0");
+
+    // The flush was queued first, so it has run once this microtask resumes us.
+    let tick = js_sys::Promise::resolve(&wasm_bindgen::JsValue::NULL);
+    wasm_bindgen_futures::JsFuture::from(tick).await.unwrap();
+
+    assert_eq!(root.text_content().unwrap(), "3");
+    assert_eq!(renders.get(), 1);
+    assert!(!handle.flush());
+}